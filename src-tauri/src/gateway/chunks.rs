@@ -0,0 +1,237 @@
+// ---------------------------------------------------------------------------
+// Chunked Payload Delivery
+// ---------------------------------------------------------------------------
+//
+// Chat history and `logs.tail` payloads can hold thousands of entries.
+// Handing them to the webview as one JSON blob stalls the UI while it is
+// parsed, so the backend splits the largest array into fixed-size chunks
+// that the webview can render as they arrive.
+
+use super::manager::GatewayManager;
+use super::protocol::{self, GatewayError};
+use super::scheduler::Priority;
+use serde::Serialize;
+use serde_json::Value;
+use tauri::ipc::Channel;
+
+/// Entries per chunk.
+pub const CHUNK_ITEMS: usize = 200;
+
+/// Event emitted for each chunk of a chunked gateway event.
+pub const EVENT_GATEWAY_EVENT_CHUNK: &str = "gateway://event-chunk";
+
+// ---- Splitting ------------------------------------------------------------
+
+/// Describes how a payload was split. Sent with the head payload.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ChunkHeader {
+    pub stream_id: String,
+    /// Field holding the split array; `None` if the payload was the array.
+    pub field: Option<String>,
+    pub total_items: usize,
+    pub chunks: usize,
+}
+
+/// One slice of the split array.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PayloadChunk {
+    pub stream_id: String,
+    pub index: usize,
+    pub items: Vec<Value>,
+}
+
+pub struct SplitPayload {
+    /// The payload with the split array emptied.
+    pub head: Value,
+    pub header: ChunkHeader,
+    pub chunks: Vec<PayloadChunk>,
+}
+
+/// Split the largest array (the payload itself or a top-level field) if it
+/// has more than `CHUNK_ITEMS` entries. Small payloads are returned as-is.
+pub fn split(mut payload: Value) -> Result<SplitPayload, Value> {
+    let (field, items) = match &mut payload {
+        Value::Array(items) if items.len() > CHUNK_ITEMS => (None, std::mem::take(items)),
+        Value::Object(map) => {
+            let largest = map
+                .iter()
+                .filter_map(|(key, value)| Some((key, value.as_array()?.len())))
+                .filter(|(_, len)| *len > CHUNK_ITEMS)
+                .max_by_key(|(_, len)| *len)
+                .map(|(key, _)| key.clone());
+            let Some(key) = largest else {
+                return Err(payload);
+            };
+            let items = match map.get_mut(&key) {
+                Some(Value::Array(items)) => std::mem::take(items),
+                _ => return Err(payload),
+            };
+            (Some(key), items)
+        }
+        _ => return Err(payload),
+    };
+
+    let stream_id = protocol::generate_request_id();
+    let total_items = items.len();
+    let mut chunks = Vec::with_capacity(total_items.div_ceil(CHUNK_ITEMS));
+    let mut items = items.into_iter();
+    loop {
+        let slice: Vec<_> = items.by_ref().take(CHUNK_ITEMS).collect();
+        if slice.is_empty() {
+            break;
+        }
+        chunks.push(PayloadChunk {
+            stream_id: stream_id.clone(),
+            index: chunks.len(),
+            items: slice,
+        });
+    }
+
+    Ok(SplitPayload {
+        head: payload,
+        header: ChunkHeader {
+            stream_id,
+            field,
+            total_items,
+            chunks: chunks.len(),
+        },
+        chunks,
+    })
+}
+
+// ---- Tauri Commands -------------------------------------------------------
+
+/// Response of `gateway_request_chunked`: the head payload, plus a header
+/// when the array entries follow on the channel.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ChunkedResponse {
+    pub payload: Value,
+    pub chunked: Option<ChunkHeader>,
+}
+
+/// Like `gateway_request`, but large array results (chat history, log
+/// tails) are streamed over `on_chunk` before the head payload is returned.
+#[tauri::command]
+pub async fn gateway_request_chunked(
+    manager: tauri::State<'_, GatewayManager>,
+    gateway_id: String,
+    method: String,
+    params: Option<Value>,
+    priority: Option<Priority>,
+    on_chunk: Channel<PayloadChunk>,
+) -> Result<ChunkedResponse, GatewayError> {
+    let session = manager.session(&gateway_id).ok_or_else(|| {
+        GatewayError::local(
+            "UNKNOWN_GATEWAY",
+            format!("Unknown gateway: {}", gateway_id),
+        )
+    })?;
+    let payload = session
+        .request(&method, params, priority.unwrap_or_default())
+        .await?;
+
+    match split(payload) {
+        Ok(split) => {
+            for chunk in split.chunks {
+                on_chunk
+                    .send(chunk)
+                    .map_err(|e| GatewayError::local("CHANNEL_CLOSED", e.to_string()))?;
+            }
+            Ok(ChunkedResponse {
+                payload: split.head,
+                chunked: Some(split.header),
+            })
+        }
+        Err(payload) => Ok(ChunkedResponse {
+            payload,
+            chunked: None,
+        }),
+    }
+}
+
+// ---- Tests ----------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn entries(count: usize) -> Vec<Value> {
+        (0..count).map(|i| json!({ "seq": i })).collect()
+    }
+
+    /// Put the chunks back into the head payload by index, as the webview
+    /// does, whatever order they arrive in.
+    fn reassemble(parts: SplitPayload, mut arrival: Vec<PayloadChunk>) -> Value {
+        arrival.sort_by_key(|chunk| chunk.index);
+        let items: Vec<_> = arrival.into_iter().flat_map(|c| c.items).collect();
+        assert_eq!(items.len(), parts.header.total_items);
+        let mut head = parts.head;
+        match &parts.header.field {
+            Some(field) => head[field] = Value::Array(items),
+            None => head = Value::Array(items),
+        }
+        head
+    }
+
+    #[test]
+    fn small_payloads_are_not_split() {
+        let payload = json!({ "messages": entries(CHUNK_ITEMS), "sessionKey": "main" });
+        assert_eq!(split(payload.clone()).err(), Some(payload));
+        assert!(split(json!("text")).is_err());
+    }
+
+    #[test]
+    fn chunks_are_numbered_in_order_and_full_but_the_last() {
+        let parts = split(Value::Array(entries(CHUNK_ITEMS * 2 + 1))).unwrap();
+        assert_eq!(parts.header.field, None);
+        assert_eq!(parts.header.total_items, CHUNK_ITEMS * 2 + 1);
+        assert_eq!(parts.header.chunks, 3);
+        assert_eq!(parts.head, json!([]));
+
+        let indexes: Vec<_> = parts.chunks.iter().map(|c| c.index).collect();
+        assert_eq!(indexes, [0, 1, 2]);
+        let sizes: Vec<_> = parts.chunks.iter().map(|c| c.items.len()).collect();
+        assert_eq!(sizes, [CHUNK_ITEMS, CHUNK_ITEMS, 1]);
+        assert!(parts
+            .chunks
+            .iter()
+            .all(|c| c.stream_id == parts.header.stream_id));
+        // Entries keep their order across chunks
+        assert_eq!(parts.chunks[1].items[0], json!({ "seq": CHUNK_ITEMS }));
+    }
+
+    #[test]
+    fn largest_field_is_split_and_the_rest_kept() {
+        let payload = json!({
+            "sessionKey": "main",
+            "messages": entries(CHUNK_ITEMS * 3),
+            "tools": entries(CHUNK_ITEMS + 1),
+        });
+        let parts = split(payload).unwrap();
+        assert_eq!(parts.header.field.as_deref(), Some("messages"));
+        assert_eq!(parts.head["messages"], json!([]));
+        assert_eq!(parts.head["sessionKey"], "main");
+        assert_eq!(
+            parts.head["tools"].as_array().unwrap().len(),
+            CHUNK_ITEMS + 1
+        );
+    }
+
+    #[test]
+    fn chunks_reassemble_into_the_payload_in_any_arrival_order() {
+        let payload = json!({ "lines": entries(CHUNK_ITEMS * 2 + 50), "cursor": 7 });
+        let parts = split(payload.clone()).unwrap();
+        let mut arrival = parts.chunks.clone();
+        arrival.reverse();
+        assert_eq!(reassemble(parts, arrival), payload);
+
+        let payload = Value::Array(entries(CHUNK_ITEMS + 1));
+        let parts = split(payload.clone()).unwrap();
+        let arrival = parts.chunks.clone();
+        assert_eq!(reassemble(parts, arrival), payload);
+    }
+}
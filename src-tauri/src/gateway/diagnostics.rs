@@ -0,0 +1,991 @@
+// ---------------------------------------------------------------------------
+// Gateway Diagnostics ("doctor" in the app)
+// ---------------------------------------------------------------------------
+//
+// Runs every connection step on its own, times it and explains failures:
+//   url → dns → tcp → tls → upgrade → challenge → signature → token →
+//   protocol → latency
+// Steps after the first failure are reported as skipped. A second list
+// covers the local environment, mirroring scripts/doctor.sh: Tailscale,
+// a gateway on localhost, device identity, keychain and dev tooling.
+
+use super::manager::GatewayManager;
+use super::protocol::{self, ConnectAuth, Frame, HelloOkPayload, RequestFrame};
+use super::session::{self, CLOSED};
+use super::tailscale::LocalApi;
+use super::transport::{self, Endpoint, GatewayStream, TlsOptions, TransportError};
+use crate::keychain::{self, KeychainError};
+use serde::Serialize;
+use serde_json::Value;
+use std::future::Future;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+// ---- Constants ------------------------------------------------------------
+
+const STEP_TIMEOUT: Duration = Duration::from_secs(10);
+const LATENCY_SAMPLES: usize = 3;
+/// Default gateway port (`gateway.port`), checked on localhost like doctor.sh.
+const LOCAL_GATEWAY_ADDR: &str = "127.0.0.1:18789";
+const LOCAL_GATEWAY_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// Tools scripts/doctor.sh checks for; only needed to build the app.
+const DEV_TOOLS: [(&str, &str); 7] = [
+    ("rustc", "Rust"),
+    ("cargo", "Cargo"),
+    ("node", "Node.js"),
+    ("pnpm", "pnpm"),
+    ("git", "git"),
+    ("gh", "GitHub CLI"),
+    ("claude", "Claude Code"),
+];
+
+// ---- Report Types ---------------------------------------------------------
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CheckStatus {
+    Pass,
+    Warn,
+    Fail,
+    Skipped,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DiagnosticCheck {
+    pub id: &'static str,
+    pub label: &'static str,
+    pub status: CheckStatus,
+    pub duration_ms: Option<u64>,
+    pub detail: Option<String>,
+    /// What to do about a warning or failure.
+    pub hint: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LatencySummary {
+    pub samples: usize,
+    pub min_ms: f64,
+    pub avg_ms: f64,
+    pub max_ms: f64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DiagnosticReport {
+    pub url: String,
+    /// No connection step failed.
+    pub ok: bool,
+    pub total_ms: u64,
+    pub server_version: Option<String>,
+    pub protocol: Option<u32>,
+    pub latency: Option<LatencySummary>,
+    pub steps: Vec<DiagnosticCheck>,
+    pub environment: Vec<DiagnosticCheck>,
+}
+
+// ---- Step Recorder --------------------------------------------------------
+
+/// Connection steps in order, with display labels.
+const STEPS: [(&str, &str); 10] = [
+    ("url", "Gateway URL"),
+    ("dns", "DNS lookup"),
+    ("tcp", "TCP connect"),
+    ("tls", "TLS handshake"),
+    ("upgrade", "WebSocket upgrade"),
+    ("challenge", "connect.challenge received"),
+    ("signature", "Device signature accepted"),
+    ("token", "Token valid"),
+    ("protocol", "Protocol version in range"),
+    ("latency", "Round-trip latency"),
+];
+
+struct Recorder {
+    checks: Vec<DiagnosticCheck>,
+}
+
+impl Recorder {
+    fn new() -> Self {
+        Self {
+            checks: Vec::with_capacity(STEPS.len()),
+        }
+    }
+
+    fn record(
+        &mut self,
+        id: &'static str,
+        status: CheckStatus,
+        duration: Option<Duration>,
+        detail: Option<String>,
+        hint: Option<String>,
+    ) {
+        let label = STEPS
+            .iter()
+            .find(|(step, _)| *step == id)
+            .map(|(_, label)| *label)
+            .unwrap_or(id);
+        self.checks.push(DiagnosticCheck {
+            id,
+            label,
+            status,
+            duration_ms: duration.map(|d| d.as_millis() as u64),
+            detail,
+            hint,
+        });
+    }
+
+    fn pass(&mut self, id: &'static str, duration: Option<Duration>, detail: impl Into<String>) {
+        self.record(id, CheckStatus::Pass, duration, Some(detail.into()), None);
+    }
+
+    fn fail(
+        &mut self,
+        id: &'static str,
+        duration: Option<Duration>,
+        detail: impl Into<String>,
+        hint: impl Into<String>,
+    ) {
+        self.record(
+            id,
+            CheckStatus::Fail,
+            duration,
+            Some(detail.into()),
+            Some(hint.into()),
+        );
+    }
+
+    fn skip(&mut self, id: &'static str, detail: impl Into<String>) {
+        self.record(id, CheckStatus::Skipped, None, Some(detail.into()), None);
+    }
+
+    /// Mark every step not yet recorded as skipped.
+    fn skip_remaining(&mut self) {
+        for (id, _) in STEPS {
+            if !self.checks.iter().any(|c| c.id == id) {
+                self.skip(id, "Skipped: an earlier step failed");
+            }
+        }
+    }
+}
+
+/// Run one step with the shared timeout, returning its result and duration.
+async fn timed<T, E: ToString>(
+    step: impl Future<Output = Result<T, E>>,
+) -> (Result<T, String>, Duration) {
+    let started = Instant::now();
+    let result = match tokio::time::timeout(STEP_TIMEOUT, step).await {
+        Ok(result) => result.map_err(|e| e.to_string()),
+        Err(_) => Err(format!("Timed out after {}s", STEP_TIMEOUT.as_secs())),
+    };
+    (result, started.elapsed())
+}
+
+// ---- Remediation Hints ----------------------------------------------------
+
+fn transport_hint(err: &TransportError, endpoint: &Endpoint) -> String {
+    match err {
+        TransportError::InvalidUrl(_) => {
+            "Use ws://host:port for plain gateways or wss://host for TLS (e.g. Tailscale Serve)"
+                .to_string()
+        }
+        TransportError::Dns(_) if endpoint.host.ends_with(".ts.net") => {
+            "MagicDNS name did not resolve: connect Tailscale on this device and check MagicDNS is enabled"
+                .to_string()
+        }
+        TransportError::Dns(_) => {
+            "Check the host name, or use the gateway's IP address".to_string()
+        }
+        TransportError::Connect(_) => format!(
+            "Nothing is listening on {}:{}. Is OpenClaw running (openclaw gateway)? Is the port bound beyond loopback (gateway.bind)? Is a firewall blocking it?",
+            endpoint.host, endpoint.port
+        ),
+        TransportError::Tls(_) => {
+            "TLS failed: check the gateway's certificate and that the port serves TLS (use ws:// for plain gateways)"
+                .to_string()
+        }
+        TransportError::UntrustedCertificate { spki_sha256, .. } => format!(
+            "Self-signed or unknown CA: trust this certificate (sha256/{}) or add the CA bundle to the gateway profile",
+            spki_sha256
+        ),
+        TransportError::PinMismatch { .. } => {
+            "The certificate changed since it was pinned. Verify the new key with the gateway owner, then re-pin it"
+                .to_string()
+        }
+        TransportError::Upgrade(_) => {
+            "The server did not accept a WebSocket upgrade: check the URL path and any reverse proxy's WebSocket support"
+                .to_string()
+        }
+    }
+}
+
+/// Which step a rejected `connect` points at.
+enum ConnectFailure {
+    Signature,
+    Token,
+    Protocol,
+}
+
+fn classify_connect_error(code: &str, message: &str) -> ConnectFailure {
+    let message = message.to_lowercase();
+    if message.contains("protocol") {
+        ConnectFailure::Protocol
+    } else if code == "NOT_PAIRED"
+        || message.contains("token")
+        || message.contains("password")
+        || message.contains("unauthorized")
+        || message.contains("pair")
+    {
+        ConnectFailure::Token
+    } else {
+        ConnectFailure::Signature
+    }
+}
+
+// ---- Connection Steps -----------------------------------------------------
+
+/// Diagnose a connection to `url`, step by step.
+pub async fn diagnose(url: &str, tls: &TlsOptions) -> DiagnosticReport {
+    let started = Instant::now();
+    let mut rec = Recorder::new();
+    let mut report = DiagnosticReport {
+        url: url.to_string(),
+        ok: false,
+        total_ms: 0,
+        server_version: None,
+        protocol: None,
+        latency: None,
+        steps: Vec::new(),
+        environment: Vec::new(),
+    };
+
+    run_steps(url, tls, &mut rec, &mut report).await;
+    rec.skip_remaining();
+
+    report.ok = !rec.checks.iter().any(|c| c.status == CheckStatus::Fail);
+    report.steps = rec.checks;
+    report.environment = environment_checks().await;
+    report.total_ms = started.elapsed().as_millis() as u64;
+    report
+}
+
+async fn run_steps(url: &str, tls: &TlsOptions, rec: &mut Recorder, report: &mut DiagnosticReport) {
+    // URL
+    let endpoint = match Endpoint::parse(url) {
+        Ok(endpoint) => {
+            rec.pass(
+                "url",
+                None,
+                format!(
+                    "{}://{}:{}",
+                    if endpoint.tls { "wss" } else { "ws" },
+                    endpoint.host,
+                    endpoint.port
+                ),
+            );
+            endpoint
+        }
+        Err(err) => {
+            let hint = "Use ws://host:port for plain gateways or wss://host for TLS";
+            rec.fail("url", None, err.to_string(), hint);
+            return;
+        }
+    };
+
+    // DNS
+    let (result, elapsed) = timed(transport::resolve(&endpoint)).await;
+    let addrs = match result {
+        Ok(addrs) => {
+            let list: Vec<_> = addrs.iter().map(|a| a.ip().to_string()).collect();
+            rec.pass("dns", Some(elapsed), list.join(", "));
+            addrs
+        }
+        Err(err) => {
+            let hint = transport_hint(&TransportError::Dns(err.clone()), &endpoint);
+            rec.fail("dns", Some(elapsed), err, hint);
+            return;
+        }
+    };
+
+    // TCP
+    let (result, elapsed) = timed(transport::connect_addrs(&addrs)).await;
+    let tcp = match result {
+        Ok(tcp) => {
+            let peer = tcp.peer_addr().map(|a| a.to_string()).unwrap_or_default();
+            rec.pass("tcp", Some(elapsed), format!("Connected to {}", peer));
+            tcp
+        }
+        Err(err) => {
+            let hint = transport_hint(&TransportError::Connect(err.clone()), &endpoint);
+            rec.fail("tcp", Some(elapsed), err, hint);
+            return;
+        }
+    };
+
+    // TLS
+    let stream: Box<dyn GatewayStream> = if endpoint.tls {
+        let started = Instant::now();
+        let result =
+            tokio::time::timeout(STEP_TIMEOUT, transport::connect_tls(&endpoint, tcp, tls)).await;
+        let elapsed = started.elapsed();
+        match result {
+            Ok(Ok(stream)) => {
+                let detail = if tls.pins.is_empty() {
+                    "Certificate trusted"
+                } else {
+                    "Certificate matches pinned key"
+                };
+                rec.pass("tls", Some(elapsed), detail);
+                stream
+            }
+            Ok(Err(err)) => {
+                let hint = transport_hint(&err, &endpoint);
+                rec.fail("tls", Some(elapsed), err.to_string(), hint);
+                return;
+            }
+            Err(_) => {
+                rec.fail(
+                    "tls",
+                    Some(elapsed),
+                    "TLS handshake timed out",
+                    "The port may not serve TLS: try ws:// instead of wss://",
+                );
+                return;
+            }
+        }
+    } else {
+        rec.skip("tls", "Plain ws:// connection");
+        Box::new(tcp)
+    };
+
+    // WebSocket upgrade
+    let (result, elapsed) = timed(transport::upgrade(&endpoint, stream)).await;
+    let (mut sender, mut receiver) = match result {
+        Ok(halves) => {
+            rec.pass("upgrade", Some(elapsed), "HTTP 101 Switching Protocols");
+            halves
+        }
+        Err(err) => {
+            let hint = transport_hint(&TransportError::Upgrade(err.clone()), &endpoint);
+            rec.fail("upgrade", Some(elapsed), err, hint);
+            return;
+        }
+    };
+
+    // connect.challenge
+    let mut buf = Vec::new();
+    let (result, elapsed) = timed(async {
+        loop {
+            if let Frame::Event(frame) = session::read_frame(&mut receiver, &mut buf).await? {
+                if frame.event == "connect.challenge" {
+                    return frame
+                        .payload
+                        .as_ref()
+                        .and_then(|p| p.get("nonce"))
+                        .and_then(Value::as_str)
+                        .map(str::to_string)
+                        .ok_or_else(|| "connect.challenge without a nonce".to_string());
+                }
+            }
+        }
+    })
+    .await;
+    let nonce = match result {
+        Ok(nonce) => {
+            rec.pass("challenge", Some(elapsed), "Nonce received");
+            nonce
+        }
+        Err(err) => {
+            rec.fail(
+                "challenge",
+                Some(elapsed),
+                err,
+                "The server speaks WebSocket but is not an OpenClaw gateway (or predates protocol v3). Check the port",
+            );
+            return;
+        }
+    };
+
+    // Device signature, token and protocol range, all decided by `connect`
+    let device_id = match crate::get_device_id() {
+        Ok(id) => id,
+        Err(err) => {
+            rec.fail(
+                "signature",
+                None,
+                err,
+                "The device identity lives in the system keychain, which is unavailable on this platform",
+            );
+            return;
+        }
+    };
+    let stored_token = keychain::retrieve_token(&device_id, url).ok();
+    let auth = ConnectAuth {
+        token: stored_token.as_ref().map(|t| t.token.clone()),
+        password: keychain::retrieve_password(url).ok(),
+    };
+    let params = match protocol::build_connect_params(&device_id, &nonce, &auth) {
+        Ok(params) => params,
+        Err(err) => {
+            rec.fail(
+                "signature",
+                None,
+                err,
+                "Could not sign the challenge with the device key",
+            );
+            return;
+        }
+    };
+
+    let connect_id = protocol::generate_request_id();
+    let frame = RequestFrame::new(&connect_id, "connect", Some(&params));
+    let (result, elapsed) = timed(async {
+        let text = serde_json::to_string(&frame).map_err(|e| e.to_string())?;
+        session::send_text(&mut sender, text).await?;
+        loop {
+            if let Frame::Response(res) = session::read_frame(&mut receiver, &mut buf).await? {
+                if res.id == connect_id {
+                    return Ok::<_, String>(res);
+                }
+            }
+        }
+    })
+    .await;
+
+    let response = match result {
+        Ok(response) => response,
+        Err(err) if err == CLOSED => {
+            rec.pass("signature", Some(elapsed), "Challenge answered");
+            rec.fail(
+                "token",
+                Some(elapsed),
+                "The gateway closed the connection after connect",
+                "The gateway token or password was rejected. Check OPENCLAW_GATEWAY_TOKEN / the saved password",
+            );
+            return;
+        }
+        Err(err) => {
+            rec.fail(
+                "signature",
+                Some(elapsed),
+                err,
+                "No reply to connect: check gateway logs (openclaw logs) for handshake errors",
+            );
+            return;
+        }
+    };
+
+    if !response.ok {
+        let (code, message) = response
+            .error
+            .map(|e| (e.code, e.message))
+            .unwrap_or_else(|| ("UNKNOWN".to_string(), "Connect rejected".to_string()));
+        let detail = format!("{}: {}", code, message);
+        match classify_connect_error(&code, &message) {
+            ConnectFailure::Protocol => {
+                rec.fail(
+                    "protocol",
+                    Some(elapsed),
+                    detail,
+                    format!(
+                        "This app speaks protocol v{}. Update the app or the gateway so their versions overlap",
+                        protocol::PROTOCOL_VERSION
+                    ),
+                );
+            }
+            ConnectFailure::Token => {
+                rec.pass("signature", Some(elapsed), "Nonce signature accepted");
+                let hint = if code == "NOT_PAIRED" {
+                    "Approve this device on the gateway host (openclaw devices approve), then reconnect"
+                } else if stored_token.is_some() {
+                    "The stored device token was rejected: forget it and pair this device again"
+                } else {
+                    "Pair this device or save the gateway's token/password"
+                };
+                rec.fail("token", Some(elapsed), detail, hint);
+            }
+            ConnectFailure::Signature => {
+                rec.fail(
+                    "signature",
+                    Some(elapsed),
+                    detail,
+                    "The gateway rejected the device signature: check this computer's clock, or remove the device on the gateway and pair again",
+                );
+            }
+        }
+        return;
+    }
+
+    let hello =
+        match serde_json::from_value::<HelloOkPayload>(response.payload.unwrap_or(Value::Null)) {
+            Ok(hello) => hello,
+            Err(err) => {
+                rec.fail(
+                    "protocol",
+                    Some(elapsed),
+                    format!("Invalid hello-ok payload: {}", err),
+                    "The gateway's reply does not match protocol v3: update the app or the gateway",
+                );
+                return;
+            }
+        };
+    if let Some(auth) = &hello.auth {
+        session::persist_device_token(&device_id, url, auth);
+    }
+
+    rec.pass("signature", Some(elapsed), "Nonce signature accepted");
+    rec.pass(
+        "token",
+        Some(elapsed),
+        match (&stored_token, &hello.auth) {
+            (Some(_), _) => "Stored device token accepted",
+            (None, Some(_)) => "Paired: new device token issued and saved",
+            (None, None) => "Accepted without a device token",
+        },
+    );
+    let negotiated = hello.protocol.unwrap_or(protocol::PROTOCOL_VERSION);
+    rec.pass(
+        "protocol",
+        None,
+        format!(
+            "v{} (client supports v{}–v{})",
+            negotiated,
+            protocol::PROTOCOL_VERSION,
+            protocol::PROTOCOL_VERSION
+        ),
+    );
+    report.server_version = Some(hello.server.version.clone());
+    report.protocol = Some(negotiated);
+
+    // Round-trip latency: a few `health` requests in sequence
+    let mut samples = Vec::with_capacity(LATENCY_SAMPLES);
+    for _ in 0..LATENCY_SAMPLES {
+        let id = protocol::generate_request_id();
+        let frame = RequestFrame::new(&id, "health", None);
+        let (result, elapsed) = timed(async {
+            let text = serde_json::to_string(&frame).map_err(|e| e.to_string())?;
+            session::send_text(&mut sender, text).await?;
+            loop {
+                if let Frame::Response(res) = session::read_frame(&mut receiver, &mut buf).await? {
+                    if res.id == id {
+                        return Ok::<_, String>(());
+                    }
+                }
+            }
+        })
+        .await;
+        match result {
+            Ok(()) => samples.push(elapsed.as_secs_f64() * 1000.0),
+            Err(err) => {
+                rec.fail(
+                    "latency",
+                    Some(elapsed),
+                    err,
+                    "The connection dropped after the handshake: check the gateway's logs and network stability",
+                );
+                return;
+            }
+        }
+    }
+    let min = samples.iter().copied().fold(f64::MAX, f64::min);
+    let max = samples.iter().copied().fold(0.0, f64::max);
+    let avg = samples.iter().sum::<f64>() / samples.len() as f64;
+    rec.pass(
+        "latency",
+        None,
+        format!("min {:.1} ms / avg {:.1} ms / max {:.1} ms", min, avg, max),
+    );
+    report.latency = Some(LatencySummary {
+        samples: samples.len(),
+        min_ms: min,
+        avg_ms: avg,
+        max_ms: max,
+    });
+    let _ = sender.close().await;
+}
+
+// ---- Environment Checks ---------------------------------------------------
+
+fn env_check(
+    id: &'static str,
+    label: &'static str,
+    status: CheckStatus,
+    detail: impl Into<String>,
+    hint: Option<&str>,
+) -> DiagnosticCheck {
+    DiagnosticCheck {
+        id,
+        label,
+        status,
+        duration_ms: None,
+        detail: Some(detail.into()),
+        hint: hint.map(str::to_string),
+    }
+}
+
+/// Find an executable on PATH or in the usual install locations (GUI apps
+/// on macOS start with a minimal PATH).
+fn find_executable(name: &str) -> Option<PathBuf> {
+    let mut dirs: Vec<PathBuf> = std::env::var_os("PATH")
+        .map(|path| std::env::split_paths(&path).collect())
+        .unwrap_or_default();
+    dirs.extend(["/opt/homebrew/bin", "/usr/local/bin"].map(PathBuf::from));
+    if let Some(home) = std::env::var_os("HOME") {
+        let home = Path::new(&home);
+        dirs.push(home.join(".cargo/bin"));
+        dirs.push(home.join(".local/bin"));
+    }
+    dirs.into_iter()
+        .map(|dir| dir.join(name))
+        .find(|path| path.is_file())
+}
+
+async fn environment_checks() -> Vec<DiagnosticCheck> {
+    let mut checks = Vec::new();
+
+    // Tailscale
+    let status = match LocalApi::discover() {
+        Some(api) => Some(api.status().await),
+        None => None,
+    };
+    checks.push(match status {
+        Some(Ok(status)) if status.is_running() => {
+            let ip = status
+                .self_node
+                .as_ref()
+                .and_then(|node| node.tailscale_ips.first())
+                .map(|ip| ip.to_string())
+                .unwrap_or_default();
+            env_check("tailscale", "Tailscale", CheckStatus::Pass, format!("Online ({})", ip), None)
+        }
+        Some(Ok(status)) => env_check(
+            "tailscale",
+            "Tailscale",
+            CheckStatus::Warn,
+            format!("Installed but not connected ({})", status.backend_state),
+            Some("Open Tailscale from the menu bar and connect"),
+        ),
+        Some(Err(err)) => env_check(
+            "tailscale",
+            "Tailscale",
+            CheckStatus::Warn,
+            err,
+            Some("Restart Tailscale"),
+        ),
+        None if find_executable("tailscale").is_some()
+            || Path::new("/Applications/Tailscale.app").exists() =>
+        {
+            env_check(
+                "tailscale",
+                "Tailscale",
+                CheckStatus::Pass,
+                "Installed (status not available to this app)",
+                None,
+            )
+        }
+        None => env_check(
+            "tailscale",
+            "Tailscale",
+            CheckStatus::Warn,
+            "Not installed",
+            Some("Install Tailscale (brew install --cask tailscale) to reach gateways on your tailnet"),
+        ),
+    });
+
+    // Local gateway
+    let local = tokio::time::timeout(
+        LOCAL_GATEWAY_TIMEOUT,
+        tokio::net::TcpStream::connect(LOCAL_GATEWAY_ADDR),
+    )
+    .await;
+    checks.push(match local {
+        Ok(Ok(_)) => env_check(
+            "localGateway",
+            "Gateway on localhost",
+            CheckStatus::Pass,
+            format!("Listening on {}", LOCAL_GATEWAY_ADDR),
+            None,
+        ),
+        _ => env_check(
+            "localGateway",
+            "Gateway on localhost",
+            CheckStatus::Warn,
+            format!("Nothing listening on {}", LOCAL_GATEWAY_ADDR),
+            Some("Fine for remote gateways. For a local one, start OpenClaw (openclaw gateway)"),
+        ),
+    });
+
+    // Device identity and keychain
+    checks.push(match crate::get_device_id() {
+        Ok(id) => env_check(
+            "deviceIdentity",
+            "Device identity",
+            CheckStatus::Pass,
+            format!("Device {}…", &id[..id.len().min(12)]),
+            None,
+        ),
+        Err(err) => env_check(
+            "deviceIdentity",
+            "Device identity",
+            CheckStatus::Fail,
+            err,
+            Some("Device keys require the macOS/iOS keychain"),
+        ),
+    });
+    checks.push(match keychain::list_tokens() {
+        Ok(_) => env_check("keychain", "Keychain", CheckStatus::Pass, "Available", None),
+        Err(KeychainError::UnsupportedPlatform) => env_check(
+            "keychain",
+            "Keychain",
+            CheckStatus::Warn,
+            "Not supported on this platform",
+            Some("Device tokens cannot be saved; you will need to pair on every launch"),
+        ),
+        Err(err) => env_check(
+            "keychain",
+            "Keychain",
+            CheckStatus::Fail,
+            err.to_string(),
+            Some("Allow The Fireplace to access the keychain in Keychain Access"),
+        ),
+    });
+
+    // Development tooling (as in scripts/doctor.sh)
+    for (binary, label) in DEV_TOOLS {
+        checks.push(match find_executable(binary) {
+            Some(path) => env_check(
+                binary,
+                label,
+                CheckStatus::Pass,
+                path.display().to_string(),
+                None,
+            ),
+            None => env_check(
+                binary,
+                label,
+                CheckStatus::Skipped,
+                "Not found (only needed for development)",
+                Some("Run ./scripts/doctor.sh --fix to install development tools"),
+            ),
+        });
+    }
+
+    checks
+}
+
+// ---- Tauri Commands -------------------------------------------------------
+
+/// Step-by-step connection diagnostics for `url`, using the TLS settings of
+/// a saved profile with the same URL when there is one.
+#[tauri::command]
+pub async fn diagnose_gateway(
+    manager: tauri::State<'_, GatewayManager>,
+    url: String,
+) -> Result<DiagnosticReport, String> {
+    let normalized = keychain::normalize_gateway_url(&url);
+    let tls = manager
+        .sessions()
+        .iter()
+        .map(|s| s.profile())
+        .find(|p| keychain::normalize_gateway_url(&p.url) == normalized)
+        .map(|p| p.tls_options())
+        .unwrap_or_else(|| TlsOptions {
+            pins: keychain::retrieve_pins(&url)
+                .map(|p| p.spki_sha256)
+                .unwrap_or_default(),
+            ca_bundle_path: None,
+        });
+    Ok(diagnose(&url, &tls).await)
+}
+
+// ---- Tests ----------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::io::AsyncWriteExt;
+    use tokio::net::TcpListener;
+    use tokio_util::compat::TokioAsyncReadCompatExt;
+
+    /// The connection steps for `url`, as `diagnose` reports them.
+    async fn steps(url: &str) -> Vec<DiagnosticCheck> {
+        let mut rec = Recorder::new();
+        let mut report = DiagnosticReport {
+            url: url.to_string(),
+            ok: false,
+            total_ms: 0,
+            server_version: None,
+            protocol: None,
+            latency: None,
+            steps: Vec::new(),
+            environment: Vec::new(),
+        };
+        run_steps(url, &TlsOptions::default(), &mut rec, &mut report).await;
+        rec.skip_remaining();
+        rec.checks
+    }
+
+    fn status(checks: &[DiagnosticCheck], id: &str) -> CheckStatus {
+        checks.iter().find(|c| c.id == id).unwrap().status
+    }
+
+    fn check<'a>(checks: &'a [DiagnosticCheck], id: &str) -> &'a DiagnosticCheck {
+        checks.iter().find(|c| c.id == id).unwrap()
+    }
+
+    /// Every step is reported once, in order, with `failed` the only
+    /// failure and the steps after it skipped.
+    fn assert_failed_at(checks: &[DiagnosticCheck], failed: &str) {
+        let ids: Vec<_> = checks.iter().map(|c| c.id).collect();
+        let expected: Vec<_> = STEPS.iter().map(|(id, _)| *id).collect();
+        assert_eq!(ids, expected);
+        let at = ids.iter().position(|id| *id == failed).unwrap();
+        for check in &checks[..at] {
+            assert_ne!(check.status, CheckStatus::Fail, "{}", check.id);
+        }
+        assert_eq!(checks[at].status, CheckStatus::Fail);
+        assert!(checks[at].hint.is_some());
+        for check in &checks[at + 1..] {
+            assert_eq!(check.status, CheckStatus::Skipped, "{}", check.id);
+        }
+    }
+
+    /// A local port with nothing listening on it.
+    async fn closed_port() -> u16 {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        listener.local_addr().unwrap().port()
+    }
+
+    /// Accept one WebSocket, send `frames` and close it.
+    async fn websocket_server(frames: Vec<Value>) -> u16 {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        tokio::spawn(async move {
+            let (tcp, _) = listener.accept().await.unwrap();
+            let mut server = soketto::handshake::Server::new(tcp.compat());
+            let key = server.receive_request().await.unwrap().key();
+            let accept = soketto::handshake::server::Response::Accept {
+                key,
+                protocol: None,
+            };
+            server.send_response(&accept).await.unwrap();
+            let (mut sender, _receiver) = server.into_builder().finish();
+            for frame in frames {
+                sender.send_text(frame.to_string()).await.unwrap();
+            }
+            let _ = sender.close().await;
+        });
+        port
+    }
+
+    #[tokio::test]
+    async fn invalid_url_fails_first() {
+        let checks = steps("http://gateway").await;
+        assert_failed_at(&checks, "url");
+        assert_eq!(
+            check(&checks, "dns").detail.as_deref(),
+            Some("Skipped: an earlier step failed")
+        );
+    }
+
+    #[tokio::test]
+    async fn refused_port_fails_the_tcp_step() {
+        let port = closed_port().await;
+        let checks = steps(&format!("ws://127.0.0.1:{}", port)).await;
+
+        assert_eq!(
+            check(&checks, "url").detail,
+            Some(format!("ws://127.0.0.1:{}", port))
+        );
+        assert_eq!(status(&checks, "dns"), CheckStatus::Pass);
+        assert_failed_at(&checks, "tcp");
+        let hint = check(&checks, "tcp").hint.clone().unwrap();
+        assert!(hint.contains(&format!("127.0.0.1:{}", port)), "{}", hint);
+    }
+
+    #[tokio::test]
+    async fn http_server_fails_the_upgrade() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        tokio::spawn(async move {
+            let (mut tcp, _) = listener.accept().await.unwrap();
+            let _ = tcp
+                .write_all(b"HTTP/1.1 200 OK\r\ncontent-length: 0\r\n\r\n")
+                .await;
+        });
+        let checks = steps(&format!("ws://127.0.0.1:{}", port)).await;
+
+        assert_eq!(status(&checks, "tcp"), CheckStatus::Pass);
+        assert_eq!(status(&checks, "tls"), CheckStatus::Skipped);
+        assert_eq!(
+            check(&checks, "tls").detail.as_deref(),
+            Some("Plain ws:// connection")
+        );
+        assert_failed_at(&checks, "upgrade");
+    }
+
+    #[tokio::test]
+    async fn websocket_without_a_challenge_is_not_a_gateway() {
+        let port = websocket_server(Vec::new()).await;
+        let checks = steps(&format!("ws://127.0.0.1:{}", port)).await;
+
+        assert_eq!(status(&checks, "upgrade"), CheckStatus::Pass);
+        assert_failed_at(&checks, "challenge");
+        assert!(check(&checks, "challenge")
+            .hint
+            .as_deref()
+            .unwrap()
+            .contains("not an OpenClaw gateway"));
+    }
+
+    #[tokio::test]
+    async fn challenge_leads_to_the_connect_steps() {
+        let challenge = serde_json::json!({
+            "type": "event",
+            "event": "connect.challenge",
+            "payload": { "nonce": "n-1" },
+        });
+        let port = websocket_server(vec![challenge]).await;
+        let checks = steps(&format!("ws://127.0.0.1:{}", port)).await;
+
+        assert_eq!(status(&checks, "challenge"), CheckStatus::Pass);
+        // Without a keychain there is no device key to sign with; with one,
+        // the server hangs up without answering `connect`
+        let failed = if keychain::is_supported() {
+            "token"
+        } else {
+            "signature"
+        };
+        assert_failed_at(&checks, failed);
+    }
+
+    #[test]
+    fn connect_errors_point_at_their_step() {
+        assert!(matches!(
+            classify_connect_error("INVALID_REQUEST", "Unsupported protocol version"),
+            ConnectFailure::Protocol
+        ));
+        assert!(matches!(
+            classify_connect_error("NOT_PAIRED", "Device is not paired"),
+            ConnectFailure::Token
+        ));
+        assert!(matches!(
+            classify_connect_error("INVALID_REQUEST", "Invalid token"),
+            ConnectFailure::Token
+        ));
+        assert!(matches!(
+            classify_connect_error("INVALID_REQUEST", "Bad signature"),
+            ConnectFailure::Signature
+        ));
+    }
+
+    #[test]
+    fn magicdns_failures_point_at_tailscale() {
+        let err = TransportError::Dns("no such host".to_string());
+        let tailnet = Endpoint::parse("wss://gateway.tail1234.ts.net").unwrap();
+        assert!(transport_hint(&err, &tailnet).contains("Tailscale"));
+        let other = Endpoint::parse("ws://gateway.local:18789").unwrap();
+        assert!(!transport_hint(&err, &other).contains("Tailscale"));
+    }
+}
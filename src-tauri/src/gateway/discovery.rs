@@ -0,0 +1,456 @@
+// ---------------------------------------------------------------------------
+// LAN Gateway Discovery
+// ---------------------------------------------------------------------------
+//
+// Finds gateways without the user typing `ws://host:port`:
+// - browses mDNS/DNS-SD for advertised OpenClaw gateways
+// - probes every configured profile URL
+//
+// Each candidate is probed (TCP, TLS, WebSocket upgrade, connect.challenge)
+// and cross-checked against the keychain so the UI can show whether we
+// already hold a device token for it. Loopback interfaces can be enabled so
+// a local advertiser on 127.0.0.1 is discoverable.
+
+use super::manager::GatewayManager;
+use super::protocol::Frame;
+use super::session::{ConnectionState, GatewayProfile};
+use super::transport::{self, TlsOptions, TransportError};
+use crate::keychain;
+use mdns_sd::{IfKind, ServiceDaemon, ServiceEvent, ServiceInfo};
+use serde::Serialize;
+use std::collections::{BTreeMap, HashSet};
+use std::net::IpAddr;
+use std::time::Duration;
+
+// ---- Constants ------------------------------------------------------------
+
+/// DNS-SD service type advertised by the gateway's Bonjour publisher.
+pub const SERVICE_TYPE: &str = "_openclaw-gw._tcp.local.";
+
+const DEFAULT_BROWSE_WINDOW: Duration = Duration::from_secs(3);
+const MAX_BROWSE_WINDOW: Duration = Duration::from_secs(30);
+const PROBE_TIMEOUT: Duration = Duration::from_secs(5);
+
+// ---- Types ----------------------------------------------------------------
+
+/// Where a discovered gateway came from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DiscoverySource {
+    Mdns,
+    Configured,
+}
+
+/// Transport security observed while probing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TlsStatus {
+    /// Plain `ws://`.
+    None,
+    /// `wss://` with a certificate that is trusted or matches a stored pin.
+    Verified,
+    /// `wss://` but the TLS handshake failed or the certificate is untrusted.
+    Failed,
+    /// `wss://` but the probe never reached the handshake (DNS, connect or
+    /// timeout failure).
+    Unknown,
+}
+
+/// A gateway found on the LAN or in the saved profiles.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DiscoveredGateway {
+    pub name: String,
+    pub url: String,
+    pub host: String,
+    pub port: u16,
+    pub addresses: Vec<String>,
+    pub source: DiscoverySource,
+    /// Saved profile with the same URL, if any.
+    pub profile_id: Option<String>,
+    /// From the TXT record or a live session's hello-ok.
+    pub version: Option<String>,
+    pub tls: TlsStatus,
+    /// The probe reached the gateway and received a connect.challenge.
+    pub reachable: bool,
+    pub has_token: bool,
+    pub error: Option<String>,
+}
+
+/// Result of one discovery pass.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DiscoveryReport {
+    pub gateways: Vec<DiscoveredGateway>,
+    /// Set when mDNS browsing was unavailable; configured hosts are still probed.
+    pub mdns_error: Option<String>,
+}
+
+/// Browse settings. `loopback` enables 127.0.0.1 so a local advertiser
+/// (or a test registering a `ServiceInfo`) can be discovered.
+#[derive(Debug, Clone)]
+pub struct BrowseConfig {
+    pub service_type: String,
+    pub window: Duration,
+    pub loopback: bool,
+}
+
+impl Default for BrowseConfig {
+    fn default() -> Self {
+        Self {
+            service_type: SERVICE_TYPE.to_string(),
+            window: DEFAULT_BROWSE_WINDOW,
+            loopback: false,
+        }
+    }
+}
+
+// ---- mDNS Browsing --------------------------------------------------------
+
+/// Browse for advertised gateways for `config.window`, returning every
+/// instance still resolved when the window closes.
+pub async fn browse(config: &BrowseConfig) -> Result<Vec<DiscoveredGateway>, String> {
+    let daemon = ServiceDaemon::new().map_err(|e| e.to_string())?;
+    if config.loopback {
+        daemon
+            .enable_interface(IfKind::LoopbackV4)
+            .map_err(|e| e.to_string())?;
+        daemon
+            .set_multicast_loop_v4(true)
+            .map_err(|e| e.to_string())?;
+    }
+    let events = daemon
+        .browse(&config.service_type)
+        .map_err(|e| e.to_string())?;
+
+    let mut resolved: BTreeMap<String, ServiceInfo> = BTreeMap::new();
+    let deadline = tokio::time::Instant::now() + config.window;
+    while let Ok(Ok(event)) = tokio::time::timeout_at(deadline, events.recv_async()).await {
+        match event {
+            ServiceEvent::ServiceResolved(info) => {
+                resolved.insert(info.get_fullname().to_string(), info);
+            }
+            ServiceEvent::ServiceRemoved(_, fullname) => {
+                resolved.remove(&fullname);
+            }
+            _ => {}
+        }
+    }
+
+    let _ = daemon.stop_browse(&config.service_type);
+    let _ = daemon.shutdown();
+
+    Ok(resolved
+        .values()
+        .filter_map(|info| from_service_info(info, &config.service_type))
+        .collect())
+}
+
+/// Turn a resolved DNS-SD instance into a candidate. TXT keys: `version`,
+/// `tls` ("1"/"true"), `path` (WebSocket path, default "/").
+fn from_service_info(info: &ServiceInfo, service_type: &str) -> Option<DiscoveredGateway> {
+    let txt = |key: &str| info.get_property_val_str(key).map(str::to_string);
+    let tls = matches!(txt("tls").as_deref(), Some("1") | Some("true"));
+    let path = txt("path").unwrap_or_else(|| "/".to_string());
+
+    // Prefer an IPv4 address: `.local` hostnames need the OS resolver to
+    // speak mDNS, which is not a given on Linux.
+    let mut addresses: Vec<IpAddr> = info.get_addresses().iter().copied().collect();
+    addresses.sort_by_key(|addr| (addr.is_ipv6(), *addr));
+    let host = match addresses.first() {
+        Some(IpAddr::V4(v4)) => v4.to_string(),
+        Some(IpAddr::V6(v6)) => format!("[{}]", v6),
+        None => info.get_hostname().trim_end_matches('.').to_string(),
+    };
+    if host.is_empty() {
+        return None;
+    }
+
+    let port = info.get_port();
+    let scheme = if tls { "wss" } else { "ws" };
+    let path = if path.starts_with('/') {
+        path
+    } else {
+        format!("/{}", path)
+    };
+    let name = info
+        .get_fullname()
+        .trim_end_matches(service_type)
+        .trim_end_matches('.')
+        .to_string();
+
+    Some(DiscoveredGateway {
+        name,
+        url: format!(
+            "{}://{}:{}{}",
+            scheme,
+            host,
+            port,
+            path.trim_end_matches('/')
+        ),
+        host: host
+            .trim_start_matches('[')
+            .trim_end_matches(']')
+            .to_string(),
+        port,
+        addresses: addresses.iter().map(IpAddr::to_string).collect(),
+        source: DiscoverySource::Mdns,
+        profile_id: None,
+        version: txt("version"),
+        tls: TlsStatus::None,
+        reachable: false,
+        has_token: false,
+        error: None,
+    })
+}
+
+// ---- Probing --------------------------------------------------------------
+
+/// Connect to `gateway.url` with `tls` and wait for `connect.challenge`,
+/// which proves a gateway (not just any WebSocket server) is listening.
+/// Never sends `connect`, so probing cannot trigger a pairing request.
+pub async fn probe(gateway: &mut DiscoveredGateway, tls: &TlsOptions) {
+    let result = tokio::time::timeout(PROBE_TIMEOUT, async {
+        let (mut sender, mut receiver) = transport::open(&gateway.url, tls).await?;
+        let mut buf = Vec::new();
+        loop {
+            buf.clear();
+            receiver
+                .receive_data(&mut buf)
+                .await
+                .map_err(|e| TransportError::Upgrade(e.to_string()))?;
+            if let Ok(Frame::Event(frame)) = serde_json::from_slice::<Frame>(&buf) {
+                if frame.event == "connect.challenge" {
+                    let _ = sender.close().await;
+                    return Ok(());
+                }
+            }
+        }
+    })
+    .await;
+
+    // Verified only once a frame arrived over the handshake
+    let wss = gateway.url.starts_with("wss://");
+    gateway.tls = match &result {
+        _ if !wss => TlsStatus::None,
+        Ok(Ok(())) => TlsStatus::Verified,
+        Ok(Err(
+            TransportError::Tls(_)
+            | TransportError::UntrustedCertificate { .. }
+            | TransportError::PinMismatch { .. },
+        )) => TlsStatus::Failed,
+        _ => TlsStatus::Unknown,
+    };
+    match result {
+        Ok(Ok(())) => gateway.reachable = true,
+        Ok(Err(err)) => gateway.error = Some(err.to_string()),
+        Err(_) => gateway.error = Some("No connect.challenge from server".to_string()),
+    }
+}
+
+/// TLS settings for probing `url`: those of the saved profile for it, or
+/// just the keychain's pins for a gateway without one.
+fn probe_tls(url: &str, profiles: &[GatewayProfile]) -> TlsOptions {
+    let normalized = keychain::normalize_gateway_url(url);
+    match profiles
+        .iter()
+        .find(|p| keychain::normalize_gateway_url(&p.url) == normalized)
+    {
+        Some(profile) => profile.tls_options(),
+        None => TlsOptions {
+            pins: keychain::retrieve_pins(url)
+                .map(|p| p.spki_sha256)
+                .unwrap_or_default(),
+            ca_bundle_path: None,
+        },
+    }
+}
+
+/// Whether the keychain holds a device token for `url`. `list_tokens` is
+/// consulted first; platforms that cannot enumerate fall back to a direct
+/// lookup for this device.
+fn has_token(url: &str, device_id: Option<&str>, listed: &[keychain::StoredDeviceToken]) -> bool {
+    let normalized = keychain::normalize_gateway_url(url);
+    let listed_match = listed.iter().any(|t| {
+        keychain::normalize_gateway_url(&t.gateway_url) == normalized
+            && device_id.is_none_or(|id| t.device_id == id)
+    });
+    listed_match || device_id.is_some_and(|id| keychain::retrieve_token(id, url).is_ok())
+}
+
+/// Browse, merge in configured profiles, then probe every candidate
+/// concurrently and annotate it with token and live-session info.
+pub async fn discover(manager: &GatewayManager, config: &BrowseConfig) -> DiscoveryReport {
+    let (mut gateways, mdns_error) = match browse(config).await {
+        Ok(found) => (found, None),
+        Err(err) => (Vec::new(), Some(err)),
+    };
+
+    let sessions = manager.summaries();
+    let mut seen: HashSet<String> = gateways
+        .iter()
+        .map(|g| keychain::normalize_gateway_url(&g.url).to_string())
+        .collect();
+    for session in &sessions {
+        let normalized = keychain::normalize_gateway_url(&session.url).to_string();
+        if !seen.insert(normalized) {
+            continue;
+        }
+        let Ok(endpoint) = transport::Endpoint::parse(&session.url) else {
+            continue;
+        };
+        gateways.push(DiscoveredGateway {
+            name: session.name.clone(),
+            url: session.url.clone(),
+            host: endpoint.host,
+            port: endpoint.port,
+            addresses: Vec::new(),
+            source: DiscoverySource::Configured,
+            profile_id: None,
+            version: None,
+            tls: TlsStatus::None,
+            reachable: false,
+            has_token: false,
+            error: None,
+        });
+    }
+
+    let profiles: Vec<_> = manager.sessions().iter().map(|s| s.profile()).collect();
+    let handles: Vec<_> = gateways
+        .into_iter()
+        .map(|mut gateway| {
+            let tls = probe_tls(&gateway.url, &profiles);
+            tauri::async_runtime::spawn(async move {
+                probe(&mut gateway, &tls).await;
+                gateway
+            })
+        })
+        .collect();
+    let mut gateways = Vec::with_capacity(handles.len());
+    for handle in handles {
+        if let Ok(gateway) = handle.await {
+            gateways.push(gateway);
+        }
+    }
+
+    let device_id = crate::get_device_id().ok();
+    let listed = keychain::list_tokens().unwrap_or_default();
+    for gateway in &mut gateways {
+        gateway.has_token = has_token(&gateway.url, device_id.as_deref(), &listed);
+
+        let normalized = keychain::normalize_gateway_url(&gateway.url);
+        if let Some(session) = sessions
+            .iter()
+            .find(|s| keychain::normalize_gateway_url(&s.url) == normalized)
+        {
+            gateway.profile_id = Some(session.id.clone());
+            if session.state == ConnectionState::Connected && session.server_version.is_some() {
+                gateway.version = session.server_version.clone();
+            }
+        }
+    }
+
+    gateways.sort_by(|a, b| {
+        b.reachable
+            .cmp(&a.reachable)
+            .then_with(|| a.name.to_lowercase().cmp(&b.name.to_lowercase()))
+    });
+    DiscoveryReport {
+        gateways,
+        mdns_error,
+    }
+}
+
+// ---- Tauri Commands -------------------------------------------------------
+
+/// Discover gateways on the LAN and probe configured profiles.
+/// `loopback` also browses 127.0.0.1 (local advertisers and tests).
+#[tauri::command]
+pub async fn gateway_discover(
+    manager: tauri::State<'_, GatewayManager>,
+    browse_ms: Option<u64>,
+    loopback: Option<bool>,
+) -> Result<DiscoveryReport, String> {
+    let config = BrowseConfig {
+        window: browse_ms
+            .map(Duration::from_millis)
+            .unwrap_or(DEFAULT_BROWSE_WINDOW)
+            .min(MAX_BROWSE_WINDOW),
+        loopback: loopback.unwrap_or(false),
+        ..BrowseConfig::default()
+    };
+    Ok(discover(&manager, &config).await)
+}
+
+// ---- Tests ----------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Own service type, so gateways advertised on the LAN never interfere.
+    const TEST_SERVICE_TYPE: &str = "_fireplace-test._tcp.local.";
+
+    fn advertise(name: &str, port: u16, properties: &[(&str, &str)]) -> ServiceDaemon {
+        let daemon = ServiceDaemon::new().expect("advertiser daemon");
+        daemon.enable_interface(IfKind::LoopbackV4).unwrap();
+        daemon.set_multicast_loop_v4(true).unwrap();
+        let info = ServiceInfo::new(
+            TEST_SERVICE_TYPE,
+            name,
+            "fireplace-test.local.",
+            "127.0.0.1",
+            port,
+            properties,
+        )
+        .unwrap();
+        daemon.register(info).unwrap();
+        daemon
+    }
+
+    #[tokio::test]
+    async fn browse_finds_loopback_advertiser() {
+        let advertiser = advertise(
+            "Loopback Gateway",
+            18789,
+            &[("version", "2026.1.0"), ("tls", "1"), ("path", "ws")],
+        );
+
+        let found = browse(&BrowseConfig {
+            service_type: TEST_SERVICE_TYPE.to_string(),
+            window: Duration::from_secs(3),
+            loopback: true,
+        })
+        .await
+        .expect("browse");
+        let _ = advertiser.shutdown();
+
+        let gateway = found
+            .iter()
+            .find(|g| g.name == "Loopback Gateway")
+            .expect("advertised gateway is discovered");
+        assert_eq!(gateway.url, "wss://127.0.0.1:18789/ws");
+        assert_eq!(gateway.host, "127.0.0.1");
+        assert_eq!(gateway.port, 18789);
+        assert_eq!(gateway.version.as_deref(), Some("2026.1.0"));
+        assert_eq!(gateway.source, DiscoverySource::Mdns);
+        assert!(gateway.addresses.contains(&"127.0.0.1".to_string()));
+    }
+
+    #[test]
+    fn service_info_defaults_to_plain_root_path() {
+        let info = ServiceInfo::new(
+            TEST_SERVICE_TYPE,
+            "Plain",
+            "plain.local.",
+            "192.168.1.20",
+            18789,
+            &[] as &[(&str, &str)],
+        )
+        .unwrap();
+        let gateway = from_service_info(&info, TEST_SERVICE_TYPE).unwrap();
+        assert_eq!(gateway.name, "Plain");
+        assert_eq!(gateway.url, "ws://192.168.1.20:18789");
+        assert_eq!(gateway.version, None);
+    }
+}
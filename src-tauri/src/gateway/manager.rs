@@ -0,0 +1,728 @@
+// ---------------------------------------------------------------------------
+// Gateway Manager
+// ---------------------------------------------------------------------------
+//
+// Keeps N concurrent gateway sessions alive, one per configured profile.
+// Profiles are persisted with tauri-plugin-store; sessions with
+// `autoConnect` are restored on launch, along with the active gateway chosen
+// in the switcher. Every state or approval change is fanned out to the
+// webview and folded into the tray's aggregate status.
+
+use super::metrics;
+use super::scheduler::{ClassMetrics, Priority};
+use super::session::{
+    ActiveRun, GatewayProfile, GatewaySession, PendingApproval, SessionSummary, UnhealthyAgent,
+};
+use super::transport;
+use crate::keychain;
+use crate::navigation;
+use base64::Engine;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, MutexGuard};
+use tauri::{AppHandle, Emitter, Manager};
+use tauri_plugin_dialog::{DialogExt, MessageDialogButtons, MessageDialogKind};
+use tauri_plugin_store::StoreExt;
+
+const PROFILES_STORE_PATH: &str = "gateways.json";
+const PROFILES_KEY: &str = "profiles";
+const ACTIVE_KEY: &str = "active";
+
+/// Event emitted with the full session list whenever any session changes.
+pub const EVENT_GATEWAY_SESSIONS: &str = "gateway://sessions";
+/// Event emitted with every running chat turn when one starts or ends.
+pub const EVENT_GATEWAY_RUNS: &str = "gateway://runs";
+/// Event emitted with every unhealthy agent when an agent's health changes.
+pub const EVENT_GATEWAY_HEALTH: &str = "gateway://health";
+
+// ---- Manager --------------------------------------------------------------
+
+#[derive(Default)]
+pub struct GatewayManager {
+    sessions: Mutex<HashMap<String, Arc<GatewaySession>>>,
+    /// Profile ID of the active gateway.
+    active: Mutex<Option<String>>,
+}
+
+impl GatewayManager {
+    fn lock(&self) -> MutexGuard<'_, HashMap<String, Arc<GatewaySession>>> {
+        self.sessions.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    pub fn session(&self, gateway_id: &str) -> Option<Arc<GatewaySession>> {
+        self.lock().get(gateway_id).cloned()
+    }
+
+    pub fn sessions(&self) -> Vec<Arc<GatewaySession>> {
+        self.lock().values().cloned().collect()
+    }
+
+    /// All sessions, ordered by display name.
+    pub fn summaries(&self) -> Vec<SessionSummary> {
+        let active = self.active_id();
+        let mut summaries: Vec<_> = self
+            .sessions()
+            .iter()
+            .map(|s| {
+                let mut summary = s.summary();
+                summary.active = active.as_deref() == Some(summary.id.as_str());
+                summary
+            })
+            .collect();
+        summaries.sort_by_key(|s| s.name.to_lowercase());
+        summaries
+    }
+
+    /// Profile ID of the gateway selected in the switcher.
+    pub fn active_id(&self) -> Option<String> {
+        self.active
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone()
+    }
+
+    fn set_active_id(&self, gateway_id: Option<String>) {
+        *self.active.lock().unwrap_or_else(|e| e.into_inner()) = gateway_id;
+    }
+
+    /// Pending exec approvals across every gateway, oldest first.
+    pub fn pending_approvals(&self) -> Vec<PendingApproval> {
+        let mut approvals: Vec<_> = self
+            .sessions()
+            .iter()
+            .flat_map(|s| s.pending_approvals())
+            .collect();
+        approvals.sort_by_key(|a| a.received_at_ms);
+        approvals
+    }
+
+    /// Chat turns in progress across every gateway.
+    pub fn active_runs(&self) -> Vec<ActiveRun> {
+        self.sessions()
+            .iter()
+            .flat_map(|s| s.active_runs())
+            .collect()
+    }
+
+    /// Unhealthy agents across every gateway.
+    pub fn unhealthy_agents(&self) -> Vec<UnhealthyAgent> {
+        self.sessions()
+            .iter()
+            .flat_map(|s| s.unhealthy_agents())
+            .collect()
+    }
+
+    /// Add or update a profile. An existing session keeps running with the
+    /// new settings; it is restarted if its URL changed.
+    pub fn upsert(&self, app: &AppHandle, profile: GatewayProfile) -> Arc<GatewaySession> {
+        let existing = self.session(&profile.id);
+        match existing {
+            Some(session) => {
+                let url_changed = session.profile().url != profile.url;
+                let was_running = session.is_running();
+                session.set_profile(profile);
+                if url_changed && was_running {
+                    session.stop();
+                    session.start(app);
+                }
+                session
+            }
+            None => {
+                let session = GatewaySession::new(profile.clone());
+                if let Some(data) = metrics::load(app, &profile.id) {
+                    session.metrics().restore(data);
+                }
+                self.lock().insert(profile.id, Arc::clone(&session));
+                session
+            }
+        }
+    }
+
+    /// Stop and forget a profile's session.
+    pub fn remove(&self, gateway_id: &str) -> Option<Arc<GatewaySession>> {
+        let session = self.lock().remove(gateway_id);
+        if self.active_id().as_deref() == Some(gateway_id) {
+            self.set_active_id(None);
+        }
+        if let Some(session) = &session {
+            session.stop();
+        }
+        session
+    }
+
+    fn profiles(&self) -> Vec<GatewayProfile> {
+        let mut profiles: Vec<_> = self.sessions().iter().map(|s| s.profile()).collect();
+        profiles.sort_by(|a, b| a.id.cmp(&b.id));
+        profiles
+    }
+}
+
+// ---- Persistence ----------------------------------------------------------
+
+fn load_profiles(app: &AppHandle) -> Vec<GatewayProfile> {
+    app.store(PROFILES_STORE_PATH)
+        .ok()
+        .and_then(|store| store.get(PROFILES_KEY))
+        .and_then(|value| serde_json::from_value(value).ok())
+        .unwrap_or_default()
+}
+
+fn save_profiles(app: &AppHandle, profiles: &[GatewayProfile]) -> Result<(), String> {
+    let store = app.store(PROFILES_STORE_PATH).map_err(|e| e.to_string())?;
+    let value = serde_json::to_value(profiles).map_err(|e| e.to_string())?;
+    store.set(PROFILES_KEY, value);
+    store.save().map_err(|e| e.to_string())
+}
+
+fn load_active(app: &AppHandle) -> Option<String> {
+    app.store(PROFILES_STORE_PATH)
+        .ok()
+        .and_then(|store| store.get(ACTIVE_KEY))
+        .and_then(|value| value.as_str().map(str::to_string))
+}
+
+fn save_active(app: &AppHandle, gateway_id: Option<&str>) -> Result<(), String> {
+    let store = app.store(PROFILES_STORE_PATH).map_err(|e| e.to_string())?;
+    match gateway_id {
+        Some(id) => store.set(ACTIVE_KEY, Value::String(id.to_string())),
+        None => {
+            store.delete(ACTIVE_KEY);
+        }
+    }
+    store.save().map_err(|e| e.to_string())
+}
+
+/// Load saved profiles and start every session marked `autoConnect`.
+pub fn restore(app: &AppHandle) {
+    let manager = app.state::<GatewayManager>();
+    for profile in load_profiles(app) {
+        let auto_connect = profile.auto_connect;
+        let session = manager.upsert(app, profile);
+        if auto_connect {
+            session.start(app);
+        }
+    }
+    let active = load_active(app).filter(|id| manager.session(id).is_some());
+    manager.set_active_id(active);
+    metrics::spawn_flush_task(app);
+    sessions_changed(app);
+}
+
+/// Broadcast the session list and refresh the tray's aggregate status.
+pub fn sessions_changed(app: &AppHandle) {
+    let Some(manager) = app.try_state::<GatewayManager>() else {
+        return;
+    };
+    let summaries = manager.summaries();
+    let _ = app.emit(EVENT_GATEWAY_SESSIONS, &summaries);
+
+    #[cfg(desktop)]
+    crate::tray::set_gateway_summaries(app, summaries, manager.pending_approvals());
+}
+
+/// Broadcast the running chat turns after one started or ended.
+pub fn runs_changed(app: &AppHandle) {
+    let Some(manager) = app.try_state::<GatewayManager>() else {
+        return;
+    };
+    let runs = manager.active_runs();
+    let _ = app.emit(EVENT_GATEWAY_RUNS, &runs);
+
+    #[cfg(desktop)]
+    crate::tray::set_active_runs(app, runs);
+}
+
+/// Broadcast the unhealthy agents after an agent's health changed.
+pub fn health_changed(app: &AppHandle) {
+    let Some(manager) = app.try_state::<GatewayManager>() else {
+        return;
+    };
+    let _ = app.emit(EVENT_GATEWAY_HEALTH, manager.unhealthy_agents());
+
+    #[cfg(desktop)]
+    crate::tray::refresh_dashboard(app);
+}
+
+/// Select the active gateway, or clear the selection with `None`.
+pub fn set_active(app: &AppHandle, gateway_id: Option<&str>) -> Result<(), String> {
+    let manager = app.state::<GatewayManager>();
+    if let Some(id) = gateway_id {
+        if manager.session(id).is_none() {
+            return Err(format!("Unknown gateway: {}", id));
+        }
+    }
+    manager.set_active_id(gateway_id.map(str::to_string));
+    save_active(app, gateway_id)?;
+    sessions_changed(app);
+    Ok(())
+}
+
+/// Delete this device's token for a gateway from the keychain. The session
+/// is disconnected first, since its connection was authenticated with the
+/// token; reconnecting pairs the device again. Fails without touching the
+/// session where there is no keychain.
+pub fn forget_device_token(app: &AppHandle, gateway_id: &str) -> Result<(), String> {
+    if !keychain::is_supported() {
+        return Err(keychain::KeychainError::UnsupportedPlatform.into());
+    }
+    let session = app
+        .state::<GatewayManager>()
+        .session(gateway_id)
+        .ok_or_else(|| format!("Unknown gateway: {}", gateway_id))?;
+    session.stop();
+    let device_id = crate::get_device_id()?;
+    match keychain::delete_token(&device_id, &session.profile().url) {
+        Ok(()) | Err(keychain::KeychainError::NotFound) => Ok(()),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+/// Connect to the gateway at `url` and make it the active gateway, adding
+/// a profile (named `name`, or after the host) when none has the same URL.
+/// A connected session is restarted so credentials saved just before are
+/// used. Returns the profile ID.
+pub fn connect_url(app: &AppHandle, url: &str, name: Option<&str>) -> Result<String, String> {
+    let endpoint = transport::Endpoint::parse(url).map_err(|e| e.to_string())?;
+    let manager = app.state::<GatewayManager>();
+    let normalized = keychain::normalize_gateway_url(url);
+    let existing = manager
+        .sessions()
+        .into_iter()
+        .find(|s| keychain::normalize_gateway_url(&s.profile().url) == normalized);
+    let session = match existing {
+        Some(session) => session,
+        None => {
+            let profile = GatewayProfile::new(
+                unused_profile_id(&manager, &endpoint.host),
+                name.unwrap_or(&endpoint.host).to_string(),
+                url.to_string(),
+            );
+            let session = manager.upsert(app, profile);
+            save_profiles(app, &manager.profiles())?;
+            session
+        }
+    };
+    session.stop();
+    session.start(app);
+    let id = session.profile().id;
+    set_active(app, Some(&id))?;
+    Ok(id)
+}
+
+/// A profile ID derived from `host` ("gateway.local" -> "gateway-local"),
+/// suffixed with a number if taken.
+fn unused_profile_id(manager: &GatewayManager, host: &str) -> String {
+    let base: String = host
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() {
+                c.to_ascii_lowercase()
+            } else {
+                '-'
+            }
+        })
+        .collect();
+    let base = match base.trim_matches('-') {
+        "" => "gateway".to_string(),
+        trimmed => trimmed.to_string(),
+    };
+    let mut id = base.clone();
+    let mut n = 2;
+    while manager.session(&id).is_some() {
+        id = format!("{}-{}", base, n);
+        n += 1;
+    }
+    id
+}
+
+// ---- Tauri Commands -------------------------------------------------------
+
+#[tauri::command]
+pub fn gateway_list_sessions(manager: tauri::State<'_, GatewayManager>) -> Vec<SessionSummary> {
+    manager.summaries()
+}
+
+/// Save a gateway profile, optionally connecting to it right away.
+#[tauri::command]
+pub fn gateway_save_profile(
+    app: AppHandle,
+    manager: tauri::State<'_, GatewayManager>,
+    profile: GatewayProfile,
+    connect: Option<bool>,
+) -> Result<SessionSummary, String> {
+    if profile.id.trim().is_empty() {
+        return Err("Gateway profile ID must not be empty".to_string());
+    }
+    transport::Endpoint::parse(&profile.url).map_err(|e| e.to_string())?;
+
+    let session = manager.upsert(&app, profile);
+    save_profiles(&app, &manager.profiles())?;
+    if connect.unwrap_or(false) {
+        session.start(&app);
+    }
+    sessions_changed(&app);
+    Ok(session.summary())
+}
+
+#[tauri::command]
+pub fn gateway_remove_profile(
+    app: AppHandle,
+    manager: tauri::State<'_, GatewayManager>,
+    gateway_id: String,
+) -> Result<(), String> {
+    let was_active = manager.active_id().as_deref() == Some(gateway_id.as_str());
+    manager.remove(&gateway_id);
+    metrics::forget(&app, &gateway_id);
+    save_profiles(&app, &manager.profiles())?;
+    if was_active {
+        save_active(&app, None)?;
+    }
+    sessions_changed(&app);
+    Ok(())
+}
+
+#[tauri::command]
+pub fn gateway_connect(
+    app: AppHandle,
+    manager: tauri::State<'_, GatewayManager>,
+    gateway_id: String,
+) -> Result<(), String> {
+    let session = manager
+        .session(&gateway_id)
+        .ok_or_else(|| format!("Unknown gateway: {}", gateway_id))?;
+    session.start(&app);
+    Ok(())
+}
+
+#[tauri::command]
+pub fn gateway_disconnect(
+    manager: tauri::State<'_, GatewayManager>,
+    gateway_id: String,
+) -> Result<(), String> {
+    let session = manager
+        .session(&gateway_id)
+        .ok_or_else(|| format!("Unknown gateway: {}", gateway_id))?;
+    session.stop();
+    Ok(())
+}
+
+/// Select the gateway shown as active in the tray switcher and the webview.
+#[tauri::command]
+pub fn gateway_set_active(app: AppHandle, gateway_id: Option<String>) -> Result<(), String> {
+    set_active(&app, gateway_id.as_deref())
+}
+
+/// Forget this device's token for a gateway (see `forget_device_token`).
+#[tauri::command]
+pub fn gateway_forget_device_token(app: AppHandle, gateway_id: String) -> Result<(), String> {
+    forget_device_token(&app, &gateway_id)
+}
+
+/// Send an RPC request on a specific gateway session.
+#[tauri::command]
+pub async fn gateway_request(
+    manager: tauri::State<'_, GatewayManager>,
+    gateway_id: String,
+    method: String,
+    params: Option<Value>,
+    priority: Option<Priority>,
+) -> Result<Value, super::protocol::GatewayError> {
+    let session = manager.session(&gateway_id).ok_or_else(|| {
+        super::protocol::GatewayError::local(
+            "UNKNOWN_GATEWAY",
+            format!("Unknown gateway: {}", gateway_id),
+        )
+    })?;
+    session
+        .request(&method, params, priority.unwrap_or_default())
+        .await
+}
+
+/// Pending exec approvals from every connected gateway.
+#[tauri::command]
+pub fn gateway_pending_approvals(
+    manager: tauri::State<'_, GatewayManager>,
+) -> Vec<PendingApproval> {
+    manager.pending_approvals()
+}
+
+/// Scheduler metrics for one backend session.
+#[tauri::command]
+pub fn gateway_session_scheduler_metrics(
+    manager: tauri::State<'_, GatewayManager>,
+    gateway_id: String,
+) -> Result<Vec<ClassMetrics>, String> {
+    manager
+        .session(&gateway_id)
+        .map(|s| s.scheduler().metrics())
+        .ok_or_else(|| format!("Unknown gateway: {}", gateway_id))
+}
+
+// ---- Certificate Trust ----------------------------------------------------
+
+/// Certificate pins stored for a gateway (base64 SPKI SHA-256).
+#[tauri::command]
+pub fn gateway_certificate_pins(
+    manager: tauri::State<'_, GatewayManager>,
+    gateway_id: String,
+) -> Result<Vec<String>, String> {
+    let session = manager
+        .session(&gateway_id)
+        .ok_or_else(|| format!("Unknown gateway: {}", gateway_id))?;
+    match keychain::retrieve_pins(&session.profile().url) {
+        Ok(pins) => Ok(pins.spki_sha256),
+        Err(keychain::KeychainError::NotFound) => Ok(Vec::new()),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+/// Pin a certificate key for a gateway and reconnect. Used both for the
+/// trust-on-first-use prompt and for pins entered by hand. `replace` drops
+/// existing pins (accepting a rotated certificate after a mismatch).
+#[tauri::command]
+pub fn gateway_trust_certificate(
+    app: AppHandle,
+    manager: tauri::State<'_, GatewayManager>,
+    gateway_id: String,
+    spki_sha256: String,
+    replace: Option<bool>,
+) -> Result<Vec<String>, String> {
+    let session = manager
+        .session(&gateway_id)
+        .ok_or_else(|| format!("Unknown gateway: {}", gateway_id))?;
+    trust_certificate(&app, &session, &spki_sha256, replace.unwrap_or(false))
+}
+
+fn trust_certificate(
+    app: &AppHandle,
+    session: &Arc<GatewaySession>,
+    spki_sha256: &str,
+    replace: bool,
+) -> Result<Vec<String>, String> {
+    let pin = transport::normalize_pin(spki_sha256);
+    let valid = base64::engine::general_purpose::STANDARD
+        .decode(&pin)
+        .map(|digest| digest.len() == 32)
+        .unwrap_or(false);
+    if !valid {
+        return Err("Pin must be a base64 SHA-256 digest".to_string());
+    }
+
+    let url = session.profile().url;
+    let mut pins = if replace {
+        Vec::new()
+    } else {
+        keychain::retrieve_pins(&url)
+            .map(|p| p.spki_sha256)
+            .unwrap_or_default()
+    };
+    if !pins.contains(&pin) {
+        pins.push(pin);
+    }
+    keychain::store_pins(&keychain::StoredCertificatePins {
+        gateway_url: url,
+        spki_sha256: pins.clone(),
+        pinned_at_ms: super::now_ms(),
+    })
+    .map_err(|e| e.to_string())?;
+
+    session.stop();
+    session.start(app);
+    Ok(pins)
+}
+
+/// Ask whether to trust the certificate a gateway presented after it was
+/// refused: unknown (trust on first use) or different from the pinned key
+/// (`mismatch`). Trusting pins the key, replacing old pins on a mismatch,
+/// and reconnects.
+pub fn confirm_certificate(app: &AppHandle, gateway_id: &str, spki_sha256: &str, mismatch: bool) {
+    let Some(session) = app.state::<GatewayManager>().session(gateway_id) else {
+        return;
+    };
+    let profile = session.profile();
+    navigation::show_main_window(app);
+    if !keychain::is_supported() {
+        app.dialog()
+            .message(format!(
+                "The gateway at\n\n{}\n\npresented a certificate that is not trusted, \
+                 and certificate pins cannot be saved on this platform. Add the \
+                 gateway's CA to the profile instead.",
+                profile.url
+            ))
+            .title("Certificate Not Trusted")
+            .kind(MessageDialogKind::Error)
+            .show(|_| {});
+        return;
+    }
+    let (title, message, ok) = if mismatch {
+        (
+            "Certificate Changed",
+            format!(
+                "The gateway \"{}\" at\n\n{}\n\npresented a different certificate \
+                 from the one pinned for it. Its key fingerprint (SHA-256) is\n\n{}\n\n\
+                 This is expected only if the gateway's certificate was replaced. \
+                 Trusting it forgets the old pin.",
+                profile.name, profile.url, spki_sha256
+            ),
+            "Trust New Certificate",
+        )
+    } else {
+        (
+            "Trust Certificate?",
+            format!(
+                "The gateway \"{}\" at\n\n{}\n\npresented a certificate that is not \
+                 signed by a trusted authority. Its key fingerprint (SHA-256) is\n\n{}\n\n\
+                 Only trust it if the fingerprint matches the one the gateway shows.",
+                profile.name, profile.url, spki_sha256
+            ),
+            "Trust",
+        )
+    };
+    let spki_sha256 = spki_sha256.to_string();
+    app.dialog()
+        .message(message)
+        .title(title)
+        .kind(MessageDialogKind::Warning)
+        .buttons(MessageDialogButtons::OkCancelCustom(
+            ok.to_string(),
+            "Cancel".to_string(),
+        ))
+        .show({
+            let app = app.clone();
+            move |confirmed| {
+                if !confirmed {
+                    return;
+                }
+                if let Err(err) = trust_certificate(&app, &session, &spki_sha256, mismatch) {
+                    app.dialog()
+                        .message(err)
+                        .title("Certificate Not Trusted")
+                        .kind(MessageDialogKind::Error)
+                        .show(|_| {});
+                }
+            }
+        });
+}
+
+/// Remove every pin for a gateway, returning it to CA verification.
+#[tauri::command]
+pub fn gateway_clear_certificate_pins(
+    manager: tauri::State<'_, GatewayManager>,
+    gateway_id: String,
+) -> Result<(), String> {
+    let session = manager
+        .session(&gateway_id)
+        .ok_or_else(|| format!("Unknown gateway: {}", gateway_id))?;
+    match keychain::delete_pins(&session.profile().url) {
+        Ok(()) | Err(keychain::KeychainError::NotFound) => Ok(()),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+// ---- Password Auth --------------------------------------------------------
+
+/// Save (or with `None`, delete) the shared password for a gateway that
+/// uses password auth, e.g. behind Tailscale Funnel.
+#[tauri::command]
+pub fn gateway_set_password(
+    manager: tauri::State<'_, GatewayManager>,
+    gateway_id: String,
+    password: Option<String>,
+) -> Result<(), String> {
+    let session = manager
+        .session(&gateway_id)
+        .ok_or_else(|| format!("Unknown gateway: {}", gateway_id))?;
+    let url = session.profile().url;
+    match password.filter(|p| !p.is_empty()) {
+        Some(password) => keychain::store_password(&url, &password).map_err(|e| e.to_string()),
+        None => match keychain::delete_password(&url) {
+            Ok(()) | Err(keychain::KeychainError::NotFound) => Ok(()),
+            Err(e) => Err(e.to_string()),
+        },
+    }
+}
+
+// ---- Tests ----------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn manager(profiles: &[(&str, &str)]) -> GatewayManager {
+        let manager = GatewayManager::default();
+        for (id, name) in profiles {
+            let profile = GatewayProfile::new(
+                id.to_string(),
+                name.to_string(),
+                format!("ws://{}.local:18789", id),
+            );
+            manager
+                .lock()
+                .insert(id.to_string(), GatewaySession::new(profile));
+        }
+        manager
+    }
+
+    #[test]
+    fn summaries_are_sorted_by_name_and_mark_the_active_gateway() {
+        let manager = manager(&[("lab", "lab"), ("home", "Home"), ("work", "Attic")]);
+        manager.set_active_id(Some("home".to_string()));
+
+        let summaries = manager.summaries();
+        let names: Vec<_> = summaries.iter().map(|s| s.name.as_str()).collect();
+        assert_eq!(names, vec!["Attic", "Home", "lab"]);
+        let active: Vec<_> = summaries.iter().map(|s| s.active).collect();
+        assert_eq!(active, vec![false, true, false]);
+    }
+
+    #[test]
+    fn removing_the_active_gateway_clears_the_selection() {
+        let manager = manager(&[("home", "Home"), ("lab", "Lab")]);
+        manager.set_active_id(Some("home".to_string()));
+
+        assert!(manager.remove("lab").is_some());
+        assert_eq!(manager.active_id().as_deref(), Some("home"));
+        assert!(manager.remove("home").is_some());
+        assert_eq!(manager.active_id(), None);
+        assert!(manager.remove("home").is_none());
+    }
+
+    #[test]
+    fn profile_ids_come_from_the_host_and_avoid_taken_ones() {
+        let manager = manager(&[("gateway-local", "Gateway")]);
+        assert_eq!(
+            unused_profile_id(&manager, "Gateway.local"),
+            "gateway-local-2"
+        );
+        assert_eq!(unused_profile_id(&manager, "10.0.0.5"), "10-0-0-5");
+        assert_eq!(unused_profile_id(&manager, "::"), "gateway");
+    }
+
+    #[test]
+    fn pending_approvals_span_gateways_oldest_first() {
+        let manager = manager(&[("home", "Home"), ("lab", "Lab")]);
+        let payload = |id: &str, timeout_ms: u64| json!({ "id": id, "command": "ls", "timeoutMs": timeout_ms });
+        for (gateway_id, id, age_ms, timeout_ms) in [
+            ("home", "newer", 1_000, 60_000),
+            ("lab", "older", 2_000, 60_000),
+            ("lab", "expired", 3_000, 1_000),
+        ] {
+            let session = manager.session(gateway_id).unwrap();
+            session.receive_approval(&payload(id, timeout_ms), age_ms);
+        }
+
+        let ids: Vec<_> = manager
+            .pending_approvals()
+            .into_iter()
+            .map(|a| (a.gateway_id, a.id))
+            .collect();
+        assert_eq!(
+            ids,
+            vec![
+                ("lab".to_string(), "older".to_string()),
+                ("home".to_string(), "newer".to_string()),
+            ]
+        );
+    }
+}
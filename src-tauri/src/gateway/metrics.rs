@@ -0,0 +1,563 @@
+// ---------------------------------------------------------------------------
+// Connection Health Metrics
+// ---------------------------------------------------------------------------
+//
+// Per-session ring buffers of tick arrival jitter, request round-trip times
+// and disconnects, so a slow gateway can be told apart from a slow network.
+// Buffers are persisted with tauri-plugin-store and restored on launch;
+// `gateway_metrics` returns them as time series plus percentiles.
+
+use super::manager::GatewayManager;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, VecDeque};
+use std::sync::{Mutex, MutexGuard};
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+use tauri_plugin_store::StoreExt;
+
+// ---- Constants ------------------------------------------------------------
+
+const METRICS_STORE_PATH: &str = "gateway-metrics.json";
+
+/// Ticks kept per gateway (about 3 hours at the default 15s interval).
+const TICK_CAPACITY: usize = 720;
+/// Request samples kept per gateway, across all methods.
+const RTT_CAPACITY: usize = 2_000;
+/// Disconnect records kept per gateway.
+const DISCONNECT_CAPACITY: usize = 100;
+
+/// Weight of the newest RTT sample in the "current latency" average.
+const LATENCY_EWMA_ALPHA: f64 = 0.3;
+
+/// How often dirty metrics are written to disk and the tray refreshed.
+const FLUSH_INTERVAL: Duration = Duration::from_secs(30);
+
+// ---- Ring Buffer ----------------------------------------------------------
+
+/// Fixed-capacity FIFO that drops the oldest entry when full.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RingBuffer<T> {
+    capacity: usize,
+    items: VecDeque<T>,
+}
+
+impl<T> RingBuffer<T> {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            items: VecDeque::with_capacity(capacity.min(64)),
+        }
+    }
+
+    pub fn push(&mut self, item: T) {
+        while self.items.len() >= self.capacity {
+            self.items.pop_front();
+        }
+        self.items.push_back(item);
+    }
+
+    pub fn iter(&self) -> impl DoubleEndedIterator<Item = &T> {
+        self.items.iter()
+    }
+
+    /// Re-apply `capacity` after loading data saved with a different one.
+    fn with_capacity(mut self, capacity: usize) -> Self {
+        self.capacity = capacity;
+        while self.items.len() > capacity {
+            self.items.pop_front();
+        }
+        self
+    }
+}
+
+// ---- Samples --------------------------------------------------------------
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TickSample {
+    pub at_ms: i64,
+    /// Time since the previous tick.
+    pub interval_ms: u64,
+    /// Deviation from the policy's `tickIntervalMs`.
+    pub jitter_ms: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RttSample {
+    pub at_ms: i64,
+    pub method: String,
+    pub rtt_ms: f64,
+    pub ok: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DisconnectRecord {
+    pub at_ms: i64,
+    pub reason: String,
+    /// How long the connection had been up (0 if it never connected).
+    pub connected_for_ms: u64,
+}
+
+/// Everything persisted for one gateway.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MetricsData {
+    ticks: RingBuffer<TickSample>,
+    rtts: RingBuffer<RttSample>,
+    disconnects: RingBuffer<DisconnectRecord>,
+    reconnects: u64,
+}
+
+impl Default for MetricsData {
+    fn default() -> Self {
+        Self {
+            ticks: RingBuffer::new(TICK_CAPACITY),
+            rtts: RingBuffer::new(RTT_CAPACITY),
+            disconnects: RingBuffer::new(DISCONNECT_CAPACITY),
+            reconnects: 0,
+        }
+    }
+}
+
+// ---- Reports --------------------------------------------------------------
+
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Percentiles {
+    pub p50: f64,
+    pub p95: f64,
+    pub p99: f64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MethodLatency {
+    pub method: String,
+    pub count: usize,
+    pub errors: usize,
+    pub rtt_ms: Percentiles,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GatewayMetricsReport {
+    pub gateway_id: String,
+    pub current_latency_ms: Option<f64>,
+    pub tick_jitter_ms: Percentiles,
+    pub methods: Vec<MethodLatency>,
+    pub reconnects: u64,
+    pub ticks: Vec<TickSample>,
+    pub rtts: Vec<RttSample>,
+    pub disconnects: Vec<DisconnectRecord>,
+}
+
+/// Nearest-rank percentiles of `values` (sorted in place).
+fn percentiles(values: &mut [f64]) -> Percentiles {
+    if values.is_empty() {
+        return Percentiles::default();
+    }
+    values.sort_by(|a, b| a.total_cmp(b));
+    let rank = |p: f64| {
+        let index = ((p / 100.0) * values.len() as f64).ceil() as usize;
+        values[index.clamp(1, values.len()) - 1]
+    };
+    Percentiles {
+        p50: rank(50.0),
+        p95: rank(95.0),
+        p99: rank(99.0),
+    }
+}
+
+// ---- Session Metrics ------------------------------------------------------
+
+struct MetricsInner {
+    data: MetricsData,
+    last_tick_ms: Option<i64>,
+    current_latency_ms: Option<f64>,
+    dirty: bool,
+}
+
+/// Health metrics for one gateway session.
+pub struct SessionMetrics {
+    inner: Mutex<MetricsInner>,
+}
+
+impl Default for SessionMetrics {
+    fn default() -> Self {
+        Self {
+            inner: Mutex::new(MetricsInner {
+                data: MetricsData::default(),
+                last_tick_ms: None,
+                current_latency_ms: None,
+                dirty: false,
+            }),
+        }
+    }
+}
+
+impl SessionMetrics {
+    fn lock(&self) -> MutexGuard<'_, MetricsInner> {
+        self.inner.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Replace the buffers with data loaded from disk.
+    pub fn restore(&self, data: MetricsData) {
+        let mut inner = self.lock();
+        inner.data = MetricsData {
+            ticks: data.ticks.with_capacity(TICK_CAPACITY),
+            rtts: data.rtts.with_capacity(RTT_CAPACITY),
+            disconnects: data.disconnects.with_capacity(DISCONNECT_CAPACITY),
+            reconnects: data.reconnects,
+        };
+    }
+
+    /// A new connection started; the next tick has no predecessor.
+    pub fn connected(&self) {
+        self.lock().last_tick_ms = None;
+    }
+
+    pub fn record_tick(&self, at_ms: i64, expected_interval_ms: u64) {
+        let mut inner = self.lock();
+        if let Some(last) = inner.last_tick_ms.replace(at_ms) {
+            let interval_ms = (at_ms - last).max(0) as u64;
+            inner.data.ticks.push(TickSample {
+                at_ms,
+                interval_ms,
+                jitter_ms: interval_ms.abs_diff(expected_interval_ms),
+            });
+            inner.dirty = true;
+        }
+    }
+
+    pub fn record_rtt(&self, method: &str, rtt: Duration, ok: bool) {
+        let rtt_ms = rtt.as_secs_f64() * 1000.0;
+        let mut inner = self.lock();
+        inner.current_latency_ms = Some(match inner.current_latency_ms {
+            Some(avg) => avg + LATENCY_EWMA_ALPHA * (rtt_ms - avg),
+            None => rtt_ms,
+        });
+        inner.data.rtts.push(RttSample {
+            at_ms: super::now_ms(),
+            method: method.to_string(),
+            rtt_ms,
+            ok,
+        });
+        inner.dirty = true;
+    }
+
+    pub fn record_disconnect(&self, reason: &str, connected_for: Duration) {
+        let mut inner = self.lock();
+        inner.data.disconnects.push(DisconnectRecord {
+            at_ms: super::now_ms(),
+            reason: reason.to_string(),
+            connected_for_ms: connected_for.as_millis() as u64,
+        });
+        inner.current_latency_ms = None;
+        inner.last_tick_ms = None;
+        inner.dirty = true;
+    }
+
+    pub fn record_reconnect(&self) {
+        let mut inner = self.lock();
+        inner.data.reconnects += 1;
+        inner.dirty = true;
+    }
+
+    /// Smoothed RTT of recent requests while connected.
+    pub fn current_latency_ms(&self) -> Option<f64> {
+        self.lock().current_latency_ms
+    }
+
+    /// Data to persist, if anything changed since the last call.
+    fn take_dirty(&self) -> Option<MetricsData> {
+        let mut inner = self.lock();
+        if !inner.dirty {
+            return None;
+        }
+        inner.dirty = false;
+        Some(inner.data.clone())
+    }
+
+    /// Time series and percentiles, limited to samples at or after `since_ms`.
+    pub fn report(&self, gateway_id: &str, since_ms: i64) -> GatewayMetricsReport {
+        let inner = self.lock();
+        let data = &inner.data;
+
+        let ticks: Vec<_> = data
+            .ticks
+            .iter()
+            .filter(|t| t.at_ms >= since_ms)
+            .cloned()
+            .collect();
+        let rtts: Vec<_> = data
+            .rtts
+            .iter()
+            .filter(|r| r.at_ms >= since_ms)
+            .cloned()
+            .collect();
+
+        let mut jitter: Vec<f64> = ticks.iter().map(|t| t.jitter_ms as f64).collect();
+
+        let mut by_method: BTreeMap<&str, (Vec<f64>, usize)> = BTreeMap::new();
+        for sample in &rtts {
+            let entry = by_method.entry(sample.method.as_str()).or_default();
+            entry.0.push(sample.rtt_ms);
+            if !sample.ok {
+                entry.1 += 1;
+            }
+        }
+        let methods = by_method
+            .into_iter()
+            .map(|(method, (mut values, errors))| MethodLatency {
+                method: method.to_string(),
+                count: values.len(),
+                errors,
+                rtt_ms: percentiles(&mut values),
+            })
+            .collect();
+
+        GatewayMetricsReport {
+            gateway_id: gateway_id.to_string(),
+            current_latency_ms: inner.current_latency_ms,
+            tick_jitter_ms: percentiles(&mut jitter),
+            methods,
+            reconnects: data.reconnects,
+            disconnects: data
+                .disconnects
+                .iter()
+                .filter(|d| d.at_ms >= since_ms)
+                .cloned()
+                .collect(),
+            ticks,
+            rtts,
+        }
+    }
+}
+
+// ---- Persistence ----------------------------------------------------------
+
+/// Saved metrics for `gateway_id`, if any.
+pub fn load(app: &AppHandle, gateway_id: &str) -> Option<MetricsData> {
+    app.store(METRICS_STORE_PATH)
+        .ok()
+        .and_then(|store| store.get(gateway_id))
+        .and_then(|value| serde_json::from_value(value).ok())
+}
+
+/// Write every session whose metrics changed since the last flush.
+fn flush(app: &AppHandle) -> Result<(), String> {
+    let manager = app.state::<GatewayManager>();
+    let store = app.store(METRICS_STORE_PATH).map_err(|e| e.to_string())?;
+    let mut changed = false;
+    for session in manager.sessions() {
+        if let Some(data) = session.metrics().take_dirty() {
+            let value = serde_json::to_value(&data).map_err(|e| e.to_string())?;
+            store.set(session.profile().id, value);
+            changed = true;
+        }
+    }
+    if changed {
+        store.save().map_err(|e| e.to_string())?;
+    }
+    Ok(())
+}
+
+/// Drop saved metrics for a removed gateway.
+pub fn forget(app: &AppHandle, gateway_id: &str) {
+    if let Ok(store) = app.store(METRICS_STORE_PATH) {
+        if store.delete(gateway_id) {
+            let _ = store.save();
+        }
+    }
+}
+
+/// Periodically persist metrics and refresh the session list (and with it
+/// the tray tooltip's latency).
+pub fn spawn_flush_task(app: &AppHandle) {
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        let mut interval = tokio::time::interval(FLUSH_INTERVAL);
+        interval.tick().await;
+        loop {
+            interval.tick().await;
+            let _ = flush(&app);
+            super::manager::sessions_changed(&app);
+        }
+    });
+}
+
+// ---- Tauri Commands -------------------------------------------------------
+
+/// Health metrics for one gateway (or all), optionally since a timestamp.
+#[tauri::command]
+pub fn gateway_metrics(
+    manager: tauri::State<'_, GatewayManager>,
+    gateway_id: Option<String>,
+    since_ms: Option<i64>,
+) -> Result<Vec<GatewayMetricsReport>, String> {
+    let since_ms = since_ms.unwrap_or(0);
+    match gateway_id {
+        Some(id) => {
+            let session = manager
+                .session(&id)
+                .ok_or_else(|| format!("Unknown gateway: {}", id))?;
+            Ok(vec![session.metrics().report(&id, since_ms)])
+        }
+        None => Ok(manager
+            .summaries()
+            .iter()
+            .filter_map(|summary| {
+                manager
+                    .session(&summary.id)
+                    .map(|s| s.metrics().report(&summary.id, since_ms))
+            })
+            .collect()),
+    }
+}
+
+// ---- Tests ----------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// What `flush` writes and `load` reads back, through JSON as the
+    /// store keeps it.
+    fn save_and_load(metrics: &SessionMetrics) -> MetricsData {
+        let data = metrics.take_dirty().expect("metrics changed");
+        let value = serde_json::to_value(&data).unwrap();
+        serde_json::from_value(value).unwrap()
+    }
+
+    #[test]
+    fn ring_buffer_drops_the_oldest() {
+        let mut buffer = RingBuffer::new(3);
+        for i in 0..5 {
+            buffer.push(i);
+        }
+        assert_eq!(buffer.iter().copied().collect::<Vec<_>>(), [2, 3, 4]);
+
+        let buffer = buffer.with_capacity(2);
+        assert_eq!(buffer.iter().copied().collect::<Vec<_>>(), [3, 4]);
+    }
+
+    #[test]
+    fn ticks_record_the_interval_and_its_jitter() {
+        let metrics = SessionMetrics::default();
+        metrics.connected();
+        // The first tick of a connection has nothing to compare with
+        metrics.record_tick(1_000, 15_000);
+        metrics.record_tick(17_000, 15_000);
+        metrics.record_tick(30_000, 15_000);
+        metrics.record_disconnect("closed", Duration::from_secs(30));
+        metrics.record_tick(40_000, 15_000);
+
+        let report = metrics.report("home", 0);
+        let ticks: Vec<_> = report
+            .ticks
+            .iter()
+            .map(|t| (t.interval_ms, t.jitter_ms))
+            .collect();
+        assert_eq!(ticks, [(16_000, 1_000), (13_000, 2_000)]);
+        assert_eq!(report.tick_jitter_ms.p50, 1_000.0);
+        assert_eq!(report.tick_jitter_ms.p99, 2_000.0);
+        assert_eq!(report.current_latency_ms, None);
+    }
+
+    #[test]
+    fn rtts_are_grouped_by_method() {
+        let metrics = SessionMetrics::default();
+        for ms in [10, 20, 30, 40] {
+            metrics.record_rtt("chat.send", Duration::from_millis(ms), ms != 40);
+        }
+        metrics.record_rtt("health", Duration::from_millis(5), true);
+
+        let report = metrics.report("home", 0);
+        let methods: Vec<_> = report
+            .methods
+            .iter()
+            .map(|m| (m.method.as_str(), m.count, m.errors))
+            .collect();
+        assert_eq!(methods, [("chat.send", 4, 1), ("health", 1, 0)]);
+        assert_eq!(report.methods[0].rtt_ms.p50, 20.0);
+        assert_eq!(report.methods[0].rtt_ms.p95, 40.0);
+
+        // Smoothed towards the newest sample
+        let latency = metrics.current_latency_ms().unwrap();
+        assert!(latency > 5.0 && latency < 40.0, "{}", latency);
+    }
+
+    #[test]
+    fn metrics_survive_a_save_and_reload() {
+        let metrics = SessionMetrics::default();
+        metrics.record_tick(0, 15_000);
+        metrics.record_tick(15_500, 15_000);
+        metrics.record_rtt("health", Duration::from_millis(12), true);
+        metrics.record_disconnect("timeout", Duration::from_secs(60));
+        metrics.record_reconnect();
+
+        let data = save_and_load(&metrics);
+        // Nothing changed since: nothing to write
+        assert!(metrics.take_dirty().is_none());
+
+        let restored = SessionMetrics::default();
+        restored.restore(data);
+        let report = restored.report("home", 0);
+        assert_eq!(report.ticks.len(), 1);
+        assert_eq!(report.ticks[0].jitter_ms, 500);
+        assert_eq!(report.rtts.len(), 1);
+        assert_eq!(report.rtts[0].method, "health");
+        assert_eq!(report.disconnects.len(), 1);
+        assert_eq!(report.disconnects[0].reason, "timeout");
+        assert_eq!(report.disconnects[0].connected_for_ms, 60_000);
+        assert_eq!(report.reconnects, 1);
+        // Restoring is not a change to write back
+        assert!(restored.take_dirty().is_none());
+        // Latency is only known while connected
+        assert_eq!(report.current_latency_ms, None);
+    }
+
+    #[test]
+    fn reload_applies_the_current_capacity() {
+        let mut data = MetricsData::default();
+        let mut disconnects = RingBuffer::new(DISCONNECT_CAPACITY * 2);
+        for i in 0..DISCONNECT_CAPACITY * 2 {
+            disconnects.push(DisconnectRecord {
+                at_ms: i as i64,
+                reason: "closed".to_string(),
+                connected_for_ms: 0,
+            });
+        }
+        data.disconnects = disconnects;
+        let value = serde_json::to_value(&data).unwrap();
+
+        let metrics = SessionMetrics::default();
+        metrics.restore(serde_json::from_value(value).unwrap());
+        let report = metrics.report("home", 0);
+        assert_eq!(report.disconnects.len(), DISCONNECT_CAPACITY);
+        // The oldest were dropped
+        assert_eq!(report.disconnects[0].at_ms, DISCONNECT_CAPACITY as i64);
+
+        // New records keep to the capacity too
+        metrics.record_disconnect("closed", Duration::ZERO);
+        assert_eq!(
+            metrics.report("home", 0).disconnects.len(),
+            DISCONNECT_CAPACITY
+        );
+    }
+
+    #[test]
+    fn report_is_limited_to_samples_since() {
+        let metrics = SessionMetrics::default();
+        metrics.record_tick(0, 1_000);
+        metrics.record_tick(1_000, 1_000);
+        metrics.record_tick(2_000, 1_000);
+
+        let report = metrics.report("home", 1_500);
+        assert_eq!(report.ticks.len(), 1);
+        assert_eq!(report.ticks[0].at_ms, 2_000);
+        assert_eq!(report.gateway_id, "home");
+    }
+}
@@ -0,0 +1,25 @@
+// ---------------------------------------------------------------------------
+// OpenClaw Gateway (Rust side)
+// ---------------------------------------------------------------------------
+// Backend gateway sessions: transport, protocol frames, per-gateway session
+// loops, the multi-gateway manager, LAN discovery, connection diagnostics,
+// Tailscale awareness and the shared request scheduler.
+
+pub mod chunks;
+pub mod diagnostics;
+pub mod discovery;
+pub mod manager;
+pub mod metrics;
+pub mod protocol;
+pub mod scheduler;
+pub mod session;
+pub mod tailscale;
+pub mod transport;
+
+/// Current Unix time in milliseconds.
+pub fn now_ms() -> i64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_millis() as i64
+}
@@ -0,0 +1,284 @@
+// ---------------------------------------------------------------------------
+// OpenClaw Gateway Protocol v3 -- Wire Frames & Handshake Helpers
+// ---------------------------------------------------------------------------
+//
+// Rust mirror of src/gateway/types.ts and src/gateway/protocol.ts for the
+// subset of the protocol the backend sessions need.
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+pub const PROTOCOL_VERSION: u32 = 3;
+
+/// Scopes requested by every operator connection (matches the JS client).
+pub const OPERATOR_SCOPES: [&str; 4] = [
+    "operator.read",
+    "operator.write",
+    "operator.admin",
+    "operator.approvals",
+];
+
+/// Methods that require an `idempotencyKey` param.
+pub const SIDE_EFFECTING_METHODS: [&str; 2] = ["chat.send", "node.invoke"];
+
+// ---- Wire Frames ----------------------------------------------------------
+
+/// Request frame sent from client to gateway.
+#[derive(Debug, Serialize)]
+pub struct RequestFrame<'a> {
+    #[serde(rename = "type")]
+    pub kind: &'static str,
+    pub id: &'a str,
+    pub method: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub params: Option<&'a Value>,
+}
+
+impl<'a> RequestFrame<'a> {
+    pub fn new(id: &'a str, method: &'a str, params: Option<&'a Value>) -> Self {
+        Self {
+            kind: "req",
+            id,
+            method,
+            params,
+        }
+    }
+}
+
+/// Response frame received from gateway in reply to a request.
+#[derive(Debug, Deserialize)]
+pub struct ResponseFrame {
+    pub id: String,
+    pub ok: bool,
+    #[serde(default)]
+    pub payload: Option<Value>,
+    #[serde(default)]
+    pub error: Option<GatewayError>,
+}
+
+/// Event frame pushed from gateway to client.
+#[derive(Debug, Clone, Deserialize)]
+pub struct EventFrame {
+    pub event: String,
+    #[serde(default)]
+    pub payload: Option<Value>,
+    #[serde(default)]
+    pub seq: Option<u64>,
+}
+
+/// Inbound frame, dispatched on its `type` field. Server-initiated
+/// requests are not expected in v3 and fail to parse (and are skipped).
+#[derive(Debug, Deserialize)]
+#[serde(tag = "type")]
+pub enum Frame {
+    #[serde(rename = "res")]
+    Response(ResponseFrame),
+    #[serde(rename = "event")]
+    Event(EventFrame),
+}
+
+/// Error shape carried by failed response frames.
+#[derive(Debug, Clone, Serialize, Deserialize, thiserror::Error)]
+#[serde(rename_all = "camelCase")]
+#[error("{code}: {message}")]
+pub struct GatewayError {
+    pub code: String,
+    pub message: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub details: Option<Value>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub retryable: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub retry_after_ms: Option<u64>,
+}
+
+impl GatewayError {
+    /// Client-side error that never reached (or came back from) the gateway.
+    pub fn local(code: &str, message: impl Into<String>) -> Self {
+        Self {
+            code: code.to_string(),
+            message: message.into(),
+            details: None,
+            retryable: None,
+            retry_after_ms: None,
+        }
+    }
+}
+
+// ---- Handshake Payloads ---------------------------------------------------
+
+#[derive(Debug, Deserialize)]
+pub struct ConnectChallengePayload {
+    pub nonce: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HelloOkServer {
+    pub version: String,
+    #[serde(default)]
+    pub commit: Option<String>,
+    #[serde(default)]
+    pub host: Option<String>,
+    pub conn_id: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GatewayPolicy {
+    #[serde(default)]
+    pub max_payload: u64,
+    #[serde(default)]
+    pub max_buffered_bytes: u64,
+    pub tick_interval_ms: u64,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HelloOkAuth {
+    pub device_token: String,
+    pub role: String,
+    #[serde(default)]
+    pub scopes: Vec<String>,
+    #[serde(default)]
+    pub issued_at_ms: Option<i64>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HelloOkPayload {
+    #[serde(rename = "type")]
+    pub kind: String,
+    /// Negotiated protocol version.
+    #[serde(default)]
+    pub protocol: Option<u32>,
+    pub server: HelloOkServer,
+    pub policy: GatewayPolicy,
+    #[serde(default)]
+    pub auth: Option<HelloOkAuth>,
+    #[serde(default)]
+    pub snapshot: Option<HelloOkSnapshot>,
+}
+
+/// The subset of the hello-ok snapshot the backend uses.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HelloOkSnapshot {
+    /// Gateway auth mode: "none" | "token" | "password" | "trusted-proxy".
+    #[serde(default)]
+    pub auth_mode: Option<String>,
+}
+
+/// Credentials sent in `connect.params.auth`.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ConnectAuth {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub token: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub password: Option<String>,
+}
+
+// ---- Event Payloads -------------------------------------------------------
+
+/// `shutdown` event: the gateway is going down, possibly to restart.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ShutdownEventPayload {
+    #[serde(default)]
+    pub reason: String,
+    #[serde(default)]
+    pub restart_expected_ms: Option<u64>,
+}
+
+// ---- Helpers --------------------------------------------------------------
+
+/// Generate a unique request ID.
+pub fn generate_request_id() -> String {
+    format!("{:032x}", rand::random::<u128>())
+}
+
+/// Generate an idempotency key for side-effecting methods.
+pub fn generate_idempotency_key() -> String {
+    format!("idem_{:032x}", rand::random::<u128>())
+}
+
+/// Client ID reported in the handshake for this platform.
+fn client_id() -> &'static str {
+    if cfg!(target_os = "ios") {
+        "openclaw-ios"
+    } else if cfg!(target_os = "macos") {
+        "openclaw-macos"
+    } else {
+        "gateway-client"
+    }
+}
+
+/// Build the pipe-delimited device auth payload signed during the handshake.
+/// Matches `buildDeviceAuthPayload` in src/gateway/protocol.ts (v2 format).
+fn build_device_auth_payload(
+    device_id: &str,
+    client_id: &str,
+    client_mode: &str,
+    signed_at_ms: i64,
+    token: Option<&str>,
+    nonce: &str,
+) -> String {
+    [
+        "v2",
+        device_id,
+        client_id,
+        client_mode,
+        "operator",
+        &OPERATOR_SCOPES.join(","),
+        &signed_at_ms.to_string(),
+        token.unwrap_or(""),
+        nonce,
+    ]
+    .join("|")
+}
+
+/// Build the `connect` request params for the v3 handshake, signing the
+/// challenge nonce with the device key (which never leaves Rust).
+pub fn build_connect_params(
+    device_id: &str,
+    nonce: &str,
+    auth: &ConnectAuth,
+) -> Result<Value, String> {
+    let client_id = client_id();
+    let client_mode = "backend";
+    let signed_at = super::now_ms();
+
+    let payload = build_device_auth_payload(
+        device_id,
+        client_id,
+        client_mode,
+        signed_at,
+        auth.token.as_deref(),
+        nonce,
+    );
+    let signature = crate::sign_payload(payload)?;
+    let public_key = crate::get_device_public_key()?;
+
+    Ok(serde_json::json!({
+        "minProtocol": PROTOCOL_VERSION,
+        "maxProtocol": PROTOCOL_VERSION,
+        "client": {
+            "id": client_id,
+            "version": env!("CARGO_PKG_VERSION"),
+            "platform": std::env::consts::OS,
+            "mode": client_mode,
+            "displayName": "The Fireplace",
+        },
+        "role": "operator",
+        "scopes": OPERATOR_SCOPES,
+        "auth": auth,
+        "device": {
+            "id": device_id,
+            "publicKey": public_key,
+            "signature": signature,
+            "signedAt": signed_at,
+            "nonce": nonce,
+        },
+        "userAgent": format!("{}/{}", client_id, env!("CARGO_PKG_VERSION")),
+    }))
+}
@@ -0,0 +1,599 @@
+// ---------------------------------------------------------------------------
+// Gateway Request Scheduler
+// ---------------------------------------------------------------------------
+//
+// Adaptive, per-method-class rate limiting for gateway RPC requests.
+//
+// Each method class (reads, writes, `chat.send`, admin) has its own token
+// bucket. Callers wait in a per-class queue where interactive requests are
+// always served before background polling. When the gateway answers with a
+// retryable error carrying `retryAfterMs`, the whole class is paused until
+// that deadline instead of hammering the server with retries.
+//
+// Webview callers wait under a ticket ID so an aborted request can give up
+// its place in the queue (`gateway_release_slot`) instead of taking a token
+// nobody will use.
+
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::sync::{oneshot, Notify};
+
+// ---- Method Classes -------------------------------------------------------
+
+/// Budget class a gateway method is scheduled under.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum MethodClass {
+    Read,
+    Write,
+    Chat,
+    Admin,
+}
+
+impl MethodClass {
+    pub const ALL: [MethodClass; 4] = [
+        MethodClass::Read,
+        MethodClass::Write,
+        MethodClass::Chat,
+        MethodClass::Admin,
+    ];
+
+    /// Classify a gateway method name (e.g. "sessions.list").
+    pub fn of(method: &str) -> Self {
+        match method {
+            // `agent` starts an agent turn, like a chat message
+            "chat.send" | "agent" => MethodClass::Chat,
+
+            "config.set" | "config.apply" | "config.patch" | "update.run"
+            | "exec.approvals.set" | "exec.approvals.node.set" | "agents.create"
+            | "agents.delete" | "device.pair.approve" | "device.pair.reject"
+            | "device.token.rotate" | "device.token.revoke" | "node.pair.approve"
+            | "node.pair.reject" | "channels.logout" => MethodClass::Admin,
+
+            "sessions.patch" | "sessions.reset" | "sessions.delete" | "sessions.compact"
+            | "agents.update" | "agents.files.set" | "skills.install" | "skills.update"
+            | "exec.approval.resolve" | "cron.add" | "cron.update" | "cron.remove"
+            | "cron.run" | "chat.abort" | "chat.inject" | "node.invoke" => MethodClass::Write,
+
+            _ => MethodClass::Read,
+        }
+    }
+
+    fn index(self) -> usize {
+        match self {
+            MethodClass::Read => 0,
+            MethodClass::Write => 1,
+            MethodClass::Chat => 2,
+            MethodClass::Admin => 3,
+        }
+    }
+
+    /// Default budget: (burst capacity, sustained refill per second).
+    fn default_budget(self) -> (f64, f64) {
+        match self {
+            MethodClass::Read => (20.0, 10.0),
+            MethodClass::Write => (10.0, 5.0),
+            MethodClass::Chat => (5.0, 1.0),
+            MethodClass::Admin => (3.0, 0.5),
+        }
+    }
+}
+
+/// Scheduling priority. Interactive requests jump ahead of background polling.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Priority {
+    #[default]
+    Interactive,
+    Background,
+}
+
+// ---- Per-Class State ------------------------------------------------------
+
+/// Upper bound on how long a queued caller sleeps between checks when it is
+/// not at the head of the queue (it is normally woken earlier by a notify).
+const QUEUE_POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+/// Longest pause a gateway's `retryAfterMs` can impose on a class.
+const MAX_BACKOFF: Duration = Duration::from_secs(5 * 60);
+
+/// How many tickets released before their wait began are remembered.
+const MAX_EARLY_RELEASES: usize = 256;
+
+struct ClassState {
+    capacity: f64,
+    refill_per_sec: f64,
+    tokens: f64,
+    last_refill: Instant,
+    /// Server-driven pause from `retryAfterMs`.
+    blocked_until: Option<Instant>,
+    interactive: VecDeque<u64>,
+    background: VecDeque<u64>,
+    metrics: ClassCounters,
+}
+
+#[derive(Default)]
+struct ClassCounters {
+    granted: u64,
+    queued: u64,
+    backoffs: u64,
+    total_wait_ms: u64,
+    max_wait_ms: u64,
+    last_retry_after_ms: Option<u64>,
+}
+
+impl ClassState {
+    fn new(class: MethodClass, now: Instant) -> Self {
+        let (capacity, refill_per_sec) = class.default_budget();
+        Self {
+            capacity,
+            refill_per_sec,
+            tokens: capacity,
+            last_refill: now,
+            blocked_until: None,
+            interactive: VecDeque::new(),
+            background: VecDeque::new(),
+            metrics: ClassCounters::default(),
+        }
+    }
+
+    fn refill(&mut self, now: Instant) {
+        let elapsed = now.saturating_duration_since(self.last_refill).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * self.refill_per_sec).min(self.capacity);
+        self.last_refill = now;
+    }
+
+    fn head(&self) -> Option<u64> {
+        self.interactive.front().or(self.background.front()).copied()
+    }
+
+    fn remove_ticket(&mut self, ticket: u64) {
+        self.interactive.retain(|t| *t != ticket);
+        self.background.retain(|t| *t != ticket);
+    }
+
+    /// Try to take a token for `ticket`. Returns how long to wait otherwise.
+    fn try_take(&mut self, ticket: u64, now: Instant) -> Result<(), Duration> {
+        if let Some(until) = self.blocked_until {
+            if until > now {
+                return Err(until - now);
+            }
+            self.blocked_until = None;
+        }
+
+        self.refill(now);
+
+        if self.head() != Some(ticket) {
+            // Someone else is ahead of us; we are woken when they are served.
+            return Err(self.time_to_next_token().max(QUEUE_POLL_INTERVAL));
+        }
+
+        if self.tokens >= 1.0 {
+            self.tokens -= 1.0;
+            self.remove_ticket(ticket);
+            Ok(())
+        } else {
+            Err(self.time_to_next_token())
+        }
+    }
+
+    fn time_to_next_token(&self) -> Duration {
+        if self.tokens >= 1.0 || self.refill_per_sec <= 0.0 {
+            return Duration::ZERO;
+        }
+        Duration::from_secs_f64((1.0 - self.tokens) / self.refill_per_sec)
+    }
+}
+
+// ---- Scheduler ------------------------------------------------------------
+
+struct Inner {
+    classes: [ClassState; 4],
+    next_ticket: u64,
+}
+
+/// Cancellation of waits started by ticket ID.
+#[derive(Default)]
+struct Releases {
+    waiting: HashMap<String, oneshot::Sender<()>>,
+    /// Released before their wait began; the two IPC calls can race.
+    early: VecDeque<String>,
+}
+
+/// Shared request scheduler. Cheap to clone; all clones share budgets.
+#[derive(Clone)]
+pub struct RequestScheduler {
+    inner: Arc<Mutex<Inner>>,
+    notifiers: Arc<[Notify; 4]>,
+    releases: Arc<Mutex<Releases>>,
+}
+
+impl Default for RequestScheduler {
+    fn default() -> Self {
+        let now = Instant::now();
+        Self {
+            inner: Arc::new(Mutex::new(Inner {
+                classes: MethodClass::ALL.map(|c| ClassState::new(c, now)),
+                next_ticket: 0,
+            })),
+            notifiers: Arc::new([Notify::new(), Notify::new(), Notify::new(), Notify::new()]),
+            releases: Arc::new(Mutex::new(Releases::default())),
+        }
+    }
+}
+
+/// Removes a queued ticket if the waiting future is dropped before it is served.
+struct TicketGuard<'a> {
+    scheduler: &'a RequestScheduler,
+    class: MethodClass,
+    ticket: u64,
+    served: bool,
+}
+
+impl Drop for TicketGuard<'_> {
+    fn drop(&mut self) {
+        if !self.served {
+            self.scheduler.lock().classes[self.class.index()].remove_ticket(self.ticket);
+            self.scheduler.notifiers[self.class.index()].notify_waiters();
+        }
+    }
+}
+
+impl RequestScheduler {
+    fn lock(&self) -> std::sync::MutexGuard<'_, Inner> {
+        self.inner.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Wait until `method` may be sent. Returns the time spent waiting.
+    pub async fn acquire(&self, method: &str, priority: Priority) -> Duration {
+        let class = MethodClass::of(method);
+        let idx = class.index();
+        let started = Instant::now();
+
+        let ticket = {
+            let mut inner = self.lock();
+            inner.next_ticket += 1;
+            let ticket = inner.next_ticket;
+            let state = &mut inner.classes[idx];
+            match priority {
+                Priority::Interactive => state.interactive.push_back(ticket),
+                Priority::Background => state.background.push_back(ticket),
+            }
+            ticket
+        };
+
+        let mut guard = TicketGuard {
+            scheduler: self,
+            class,
+            ticket,
+            served: false,
+        };
+        let mut waited = false;
+
+        loop {
+            // Register for wakeups before checking, so a notify between the
+            // check and the await is not lost.
+            let notified = self.notifiers[idx].notified();
+            tokio::pin!(notified);
+            notified.as_mut().enable();
+
+            let attempt = self.lock().classes[idx].try_take(ticket, Instant::now());
+            match attempt {
+                Ok(()) => break,
+                Err(wait) => {
+                    waited = true;
+                    // Wake on our own deadline, or earlier when the queue moves.
+                    let wait = wait.max(Duration::from_millis(1));
+                    let _ = tokio::time::timeout(wait, notified).await;
+                }
+            }
+        }
+
+        guard.served = true;
+        self.notifiers[idx].notify_waiters();
+
+        let wait = started.elapsed();
+        let wait_ms = wait.as_millis() as u64;
+        let mut inner = self.lock();
+        let counters = &mut inner.classes[idx].metrics;
+        counters.granted += 1;
+        if waited {
+            counters.queued += 1;
+        }
+        counters.total_wait_ms += wait_ms;
+        counters.max_wait_ms = counters.max_wait_ms.max(wait_ms);
+        wait
+    }
+
+    /// Like `acquire`, but the wait is cancelled by `release(ticket_id)`.
+    /// Returns `None` if it was, without taking a token.
+    pub async fn acquire_ticket(
+        &self,
+        ticket_id: &str,
+        method: &str,
+        priority: Priority,
+    ) -> Option<Duration> {
+        let cancelled = {
+            let mut releases = self.releases.lock().unwrap_or_else(|e| e.into_inner());
+            if let Some(pos) = releases.early.iter().position(|t| t == ticket_id) {
+                releases.early.remove(pos);
+                return None;
+            }
+            let (cancel, cancelled) = oneshot::channel();
+            releases.waiting.insert(ticket_id.to_string(), cancel);
+            cancelled
+        };
+
+        // Dropping the `acquire` future gives up its place in the queue
+        let waited = tokio::select! {
+            waited = self.acquire(method, priority) => Some(waited),
+            _ = cancelled => None,
+        };
+        self.releases
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .waiting
+            .remove(ticket_id);
+        waited
+    }
+
+    /// Cancel the wait started with `ticket_id`. Does nothing once the slot
+    /// was granted.
+    pub fn release(&self, ticket_id: &str) {
+        let mut releases = self.releases.lock().unwrap_or_else(|e| e.into_inner());
+        match releases.waiting.remove(ticket_id) {
+            Some(cancel) => {
+                let _ = cancel.send(());
+            }
+            None => {
+                if releases.early.len() == MAX_EARLY_RELEASES {
+                    releases.early.pop_front();
+                }
+                releases.early.push_back(ticket_id.to_string());
+            }
+        }
+    }
+
+    /// Pause the class of `method` for `retry_after_ms`, as instructed by the
+    /// gateway, but never longer than `MAX_BACKOFF`.
+    pub fn backoff(&self, method: &str, retry_after_ms: u64) {
+        let class = MethodClass::of(method);
+        let until = Instant::now() + Duration::from_millis(retry_after_ms).min(MAX_BACKOFF);
+        {
+            let mut inner = self.lock();
+            let state = &mut inner.classes[class.index()];
+            state.blocked_until = Some(state.blocked_until.map_or(until, |b| b.max(until)));
+            state.metrics.backoffs += 1;
+            state.metrics.last_retry_after_ms = Some(retry_after_ms);
+        }
+        self.notifiers[class.index()].notify_waiters();
+    }
+
+    /// Snapshot of queue depth, wait times and backoff state per class.
+    pub fn metrics(&self) -> Vec<ClassMetrics> {
+        let now = Instant::now();
+        let mut inner = self.lock();
+        MethodClass::ALL
+            .iter()
+            .map(|class| {
+                let state = &mut inner.classes[class.index()];
+                state.refill(now);
+                let m = &state.metrics;
+                ClassMetrics {
+                    class: *class,
+                    capacity: state.capacity,
+                    refill_per_sec: state.refill_per_sec,
+                    tokens_available: state.tokens,
+                    queue_depth_interactive: state.interactive.len(),
+                    queue_depth_background: state.background.len(),
+                    granted: m.granted,
+                    queued: m.queued,
+                    backoffs: m.backoffs,
+                    avg_wait_ms: if m.granted > 0 {
+                        m.total_wait_ms as f64 / m.granted as f64
+                    } else {
+                        0.0
+                    },
+                    max_wait_ms: m.max_wait_ms,
+                    last_retry_after_ms: m.last_retry_after_ms,
+                    blocked_for_ms: state
+                        .blocked_until
+                        .map(|b| b.saturating_duration_since(now).as_millis() as u64)
+                        .unwrap_or(0),
+                }
+            })
+            .collect()
+    }
+}
+
+/// Per-class scheduler metrics returned to the frontend.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ClassMetrics {
+    pub class: MethodClass,
+    pub capacity: f64,
+    pub refill_per_sec: f64,
+    pub tokens_available: f64,
+    pub queue_depth_interactive: usize,
+    pub queue_depth_background: usize,
+    pub granted: u64,
+    pub queued: u64,
+    pub backoffs: u64,
+    pub avg_wait_ms: f64,
+    pub max_wait_ms: u64,
+    pub last_retry_after_ms: Option<u64>,
+    pub blocked_for_ms: u64,
+}
+
+// ---- Tauri Commands -------------------------------------------------------
+
+/// Wait for a send slot for `method`. Resolves with the time spent queued
+/// (ms). A wait under a `ticket` fails once `gateway_release_slot` cancels it.
+#[tauri::command]
+pub async fn gateway_acquire_slot(
+    scheduler: tauri::State<'_, RequestScheduler>,
+    method: String,
+    priority: Option<Priority>,
+    ticket: Option<String>,
+) -> Result<u64, String> {
+    let priority = priority.unwrap_or_default();
+    let waited = match ticket {
+        Some(ticket) => scheduler
+            .acquire_ticket(&ticket, &method, priority)
+            .await
+            .ok_or_else(|| format!("Request cancelled: {}", method))?,
+        None => scheduler.acquire(&method, priority).await,
+    };
+    Ok(waited.as_millis() as u64)
+}
+
+/// Give up a queued `gateway_acquire_slot` wait, e.g. when the request was
+/// aborted.
+#[tauri::command]
+pub fn gateway_release_slot(scheduler: tauri::State<'_, RequestScheduler>, ticket: String) {
+    scheduler.release(&ticket);
+}
+
+/// Report a retryable gateway error so the method's class backs off.
+#[tauri::command]
+pub fn gateway_report_backoff(
+    scheduler: tauri::State<'_, RequestScheduler>,
+    method: String,
+    retry_after_ms: u64,
+) {
+    scheduler.backoff(&method, retry_after_ms);
+}
+
+#[tauri::command]
+pub fn gateway_scheduler_metrics(
+    scheduler: tauri::State<'_, RequestScheduler>,
+) -> Vec<ClassMetrics> {
+    scheduler.metrics()
+}
+
+// ---- Tests ----------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn class_metrics(scheduler: &RequestScheduler, class: MethodClass) -> ClassMetrics {
+        scheduler
+            .metrics()
+            .into_iter()
+            .find(|m| m.class == class)
+            .unwrap()
+    }
+
+    #[test]
+    fn agent_turns_are_scheduled_with_chat() {
+        assert_eq!(MethodClass::of("agent"), MethodClass::Chat);
+        assert_eq!(MethodClass::of("agents.list"), MethodClass::Read);
+        assert_eq!(MethodClass::of("chat.send"), MethodClass::Chat);
+        assert_eq!(MethodClass::of("config.apply"), MethodClass::Admin);
+    }
+
+    #[test]
+    fn tokens_refill_up_to_capacity() {
+        let start = Instant::now();
+        let mut state = ClassState::new(MethodClass::Read, start);
+        state.tokens = 0.0;
+
+        state.refill(start + Duration::from_millis(500));
+        assert_eq!(state.tokens, 5.0);
+        state.refill(start + Duration::from_secs(60));
+        assert_eq!(state.tokens, state.capacity);
+    }
+
+    #[test]
+    fn empty_bucket_reports_time_to_next_token() {
+        let start = Instant::now();
+        let mut state = ClassState::new(MethodClass::Chat, start);
+        state.tokens = 0.0;
+        state.background.push_back(1);
+
+        assert_eq!(state.try_take(1, start), Err(Duration::from_secs(1)));
+        assert_eq!(state.try_take(1, start + Duration::from_secs(1)), Ok(()));
+        assert!(state.background.is_empty());
+    }
+
+    #[test]
+    fn interactive_tickets_are_served_before_background() {
+        let now = Instant::now();
+        let mut state = ClassState::new(MethodClass::Read, now);
+        state.background.push_back(1);
+        state.interactive.push_back(2);
+
+        assert!(state.try_take(1, now).is_err());
+        assert_eq!(state.try_take(2, now), Ok(()));
+        assert_eq!(state.try_take(1, now), Ok(()));
+    }
+
+    #[test]
+    fn backoff_pauses_the_class_no_longer_than_the_cap() {
+        let scheduler = RequestScheduler::default();
+        scheduler.backoff("sessions.list", 60 * 60 * 1000);
+
+        let read = class_metrics(&scheduler, MethodClass::Read);
+        assert_eq!(read.backoffs, 1);
+        assert_eq!(read.last_retry_after_ms, Some(60 * 60 * 1000));
+        assert!(read.blocked_for_ms <= MAX_BACKOFF.as_millis() as u64);
+        assert!(read.blocked_for_ms > MAX_BACKOFF.as_millis() as u64 - 1000);
+        assert_eq!(class_metrics(&scheduler, MethodClass::Write).blocked_for_ms, 0);
+
+        let now = Instant::now();
+        let mut inner = scheduler.lock();
+        let state = &mut inner.classes[MethodClass::Read.index()];
+        state.interactive.push_back(1);
+        assert!(state.try_take(1, now).is_err());
+        assert_eq!(state.try_take(1, now + MAX_BACKOFF), Ok(()));
+    }
+
+    #[tokio::test]
+    async fn released_ticket_leaves_the_queue_without_a_token() {
+        let scheduler = RequestScheduler::default();
+        for _ in 0..3 {
+            scheduler.acquire("config.apply", Priority::Interactive).await;
+        }
+
+        let waiting = tokio::spawn({
+            let scheduler = scheduler.clone();
+            async move {
+                scheduler
+                    .acquire_ticket("t1", "config.apply", Priority::Interactive)
+                    .await
+            }
+        });
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        assert_eq!(
+            class_metrics(&scheduler, MethodClass::Admin).queue_depth_interactive,
+            1
+        );
+
+        scheduler.release("t1");
+        assert_eq!(waiting.await.unwrap(), None);
+        let admin = class_metrics(&scheduler, MethodClass::Admin);
+        assert_eq!(admin.queue_depth_interactive, 0);
+        assert_eq!(admin.granted, 3);
+    }
+
+    #[tokio::test]
+    async fn release_before_the_wait_cancels_it() {
+        let scheduler = RequestScheduler::default();
+        scheduler.release("t2");
+
+        assert_eq!(
+            scheduler
+                .acquire_ticket("t2", "sessions.list", Priority::Interactive)
+                .await,
+            None
+        );
+        assert!(scheduler
+            .acquire_ticket("t3", "sessions.list", Priority::Interactive)
+            .await
+            .is_some());
+        assert_eq!(class_metrics(&scheduler, MethodClass::Read).granted, 1);
+    }
+}
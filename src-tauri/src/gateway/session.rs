@@ -0,0 +1,1354 @@
+// ---------------------------------------------------------------------------
+// Gateway Session
+// ---------------------------------------------------------------------------
+//
+// One backend connection to one gateway profile. Owns the WebSocket, runs
+// the v3 handshake with the profile's device token from the keychain,
+// matches responses to requests, tracks pending exec approvals, running
+// chat turns and agent health, and re-emits every gateway event, tagged
+// with the gateway ID, to the windows the `hub` subscribes to it.
+
+use super::chunks::{self, ChunkHeader};
+use super::metrics::SessionMetrics;
+use super::protocol::{
+    self, ConnectAuth, ConnectChallengePayload, EventFrame, Frame, GatewayError, GatewayPolicy,
+    HelloOkAuth, HelloOkPayload, HelloOkServer, RequestFrame, ShutdownEventPayload,
+};
+use super::scheduler::{Priority, RequestScheduler};
+use super::tailscale::{self, AuthMode};
+use super::transport::{self, TlsOptions, TransportError, WsReceiver, WsSender};
+use crate::keychain::{self, StoredDeviceToken};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Emitter};
+use tokio::sync::{mpsc, oneshot, watch};
+
+// ---- Constants ------------------------------------------------------------
+
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(15);
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+const RECONNECT_MIN: Duration = Duration::from_secs(1);
+const RECONNECT_MAX: Duration = Duration::from_secs(30);
+/// Reconnect if nothing arrives within this multiple of the tick interval.
+const TICK_WATCHDOG_MULTIPLIER: u64 = 2;
+/// Retry interval once a restarting gateway's expected restart time passed.
+const RESTART_POLL_INTERVAL: Duration = Duration::from_secs(2);
+/// Cap on the announced restart time and on the grace window after it, so
+/// a bogus `restartExpectedMs` cannot hold requests indefinitely.
+const MAX_RESTART_HOLD: Duration = Duration::from_secs(30 * 60);
+
+/// Event emitted for every gateway event, tagged with the gateway ID, to
+/// the windows subscribed to it.
+pub const EVENT_GATEWAY_EVENT: &str = "gateway://event";
+/// Event emitted whenever a session's connection state changes.
+pub const EVENT_GATEWAY_STATE: &str = "gateway://state";
+/// Event emitted when a gateway's TLS certificate needs a trust decision.
+pub const EVENT_GATEWAY_CERTIFICATE: &str = "gateway://certificate";
+
+// ---- Profile & State ------------------------------------------------------
+
+/// A configured gateway the backend can connect to.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GatewayProfile {
+    /// Stable identifier used to tag events (e.g. "home", "lab").
+    pub id: String,
+    /// Display name for menus and lists.
+    pub name: String,
+    /// WebSocket URL (`ws://` or `wss://`).
+    pub url: String,
+    /// Connect automatically when the app starts.
+    #[serde(default = "default_true")]
+    pub auto_connect: bool,
+    /// Path to a PEM file of extra CAs to trust for `wss://` (unpinned
+    /// profiles).
+    #[serde(default, alias = "caBundle", skip_serializing_if = "Option::is_none")]
+    pub ca_bundle_path: Option<String>,
+    /// How the handshake authenticates (password lives in the keychain).
+    #[serde(default)]
+    pub auth_mode: AuthMode,
+    /// Grace period after an announced restart before the user is notified
+    /// that the gateway has not come back.
+    #[serde(default = "default_restart_window_ms")]
+    pub restart_window_ms: u64,
+}
+
+impl GatewayProfile {
+    /// A profile with default settings: token auth, connecting at launch.
+    pub fn new(id: String, name: String, url: String) -> Self {
+        Self {
+            id,
+            name,
+            url,
+            auto_connect: default_true(),
+            ca_bundle_path: None,
+            auth_mode: AuthMode::default(),
+            restart_window_ms: default_restart_window_ms(),
+        }
+    }
+
+    /// TLS settings: certificate pins from the keychain plus the CA file.
+    pub fn tls_options(&self) -> TlsOptions {
+        TlsOptions {
+            pins: keychain::retrieve_pins(&self.url)
+                .map(|p| p.spki_sha256)
+                .unwrap_or_default(),
+            ca_bundle_path: self.ca_bundle_path.as_ref().map(Into::into),
+        }
+    }
+}
+
+fn default_true() -> bool {
+    true
+}
+
+fn default_restart_window_ms() -> u64 {
+    60_000
+}
+
+/// Connection state, mirroring `GatewayConnectionState` in the JS client.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ConnectionState {
+    Disconnected,
+    Connecting,
+    Challenged,
+    Authenticating,
+    Connected,
+    Reconnecting,
+}
+
+impl ConnectionState {
+    /// Human-readable label used by the tray.
+    pub fn label(self) -> &'static str {
+        match self {
+            ConnectionState::Connected => "Connected",
+            ConnectionState::Connecting
+            | ConnectionState::Challenged
+            | ConnectionState::Authenticating => "Connecting",
+            ConnectionState::Reconnecting => "Reconnecting",
+            ConnectionState::Disconnected => "Disconnected",
+        }
+    }
+}
+
+/// An `exec.approval.requested` event that has not been resolved yet.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PendingApproval {
+    pub gateway_id: String,
+    pub id: String,
+    pub command: Option<String>,
+    pub agent_id: Option<String>,
+    pub host: Option<String>,
+    pub session_key: Option<String>,
+    pub timeout_ms: Option<u64>,
+    pub received_at_ms: i64,
+    pub payload: Value,
+}
+
+impl PendingApproval {
+    fn from_event(gateway_id: &str, payload: &Value) -> Option<Self> {
+        let id = payload.get("id")?.as_str()?.to_string();
+        let field = |key: &str| payload.get(key).and_then(Value::as_str).map(str::to_string);
+        Some(Self {
+            gateway_id: gateway_id.to_string(),
+            id,
+            command: field("command"),
+            agent_id: field("agentId"),
+            host: field("host"),
+            session_key: field("sessionKey"),
+            timeout_ms: payload.get("timeoutMs").and_then(Value::as_u64),
+            received_at_ms: super::now_ms(),
+            payload: payload.clone(),
+        })
+    }
+
+    /// Whether the approval's `timeoutMs` ran out by `now_ms`; the gateway
+    /// has dropped the request by then.
+    fn expired(&self, now_ms: i64) -> bool {
+        self.timeout_ms.is_some_and(|timeout| {
+            self.received_at_ms
+                .saturating_add(i64::try_from(timeout).unwrap_or(i64::MAX))
+                <= now_ms
+        })
+    }
+}
+
+/// A chat turn that has started streaming and not finished yet.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ActiveRun {
+    pub gateway_id: String,
+    pub session_key: String,
+    pub run_id: String,
+}
+
+/// Health statuses (from `health` events) that count as unhealthy.
+const UNHEALTHY_STATUSES: [&str; 4] = ["offline", "stopped", "degraded", "error"];
+
+/// An agent whose last reported health is unhealthy.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UnhealthyAgent {
+    pub gateway_id: String,
+    pub agent_id: String,
+    pub status: String,
+}
+
+/// Snapshot of a session for the frontend and the tray.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionSummary {
+    pub id: String,
+    pub name: String,
+    pub url: String,
+    pub state: ConnectionState,
+    pub server_version: Option<String>,
+    pub last_error: Option<String>,
+    pub pending_approvals: usize,
+    pub reconnect_attempts: u32,
+    /// Smoothed request round-trip time while connected.
+    pub latency_ms: Option<f64>,
+    /// Connectivity warnings, e.g. a Funnel gateway without password auth.
+    pub warnings: Vec<String>,
+    /// Whether this is the gateway selected in the switcher.
+    pub active: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct GatewayEventEnvelope<'a> {
+    gateway_id: &'a str,
+    event: &'a str,
+    payload: &'a Option<Value>,
+    seq: Option<u64>,
+    /// Present when the payload's largest array follows as chunk events.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    chunked: Option<&'a ChunkHeader>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct GatewayStateEnvelope<'a> {
+    gateway_id: &'a str,
+    state: ConnectionState,
+    error: Option<&'a str>,
+}
+
+/// Why a certificate was refused: never seen before (trust-on-first-use
+/// prompt) or different from the pinned key.
+#[derive(Debug, Clone, Copy, Serialize)]
+#[serde(rename_all = "camelCase")]
+enum CertificateIssue {
+    Untrusted,
+    PinMismatch,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct GatewayCertificateEnvelope<'a> {
+    gateway_id: &'a str,
+    url: &'a str,
+    issue: CertificateIssue,
+    spki_sha256: &'a str,
+    pinned: &'a [String],
+    error: String,
+}
+
+/// Failure of one connection attempt. TLS trust failures are kept typed
+/// because they stop the reconnect loop until the user decides.
+enum ConnectError {
+    Transport(TransportError),
+    Other(String),
+}
+
+impl From<String> for ConnectError {
+    fn from(err: String) -> Self {
+        ConnectError::Other(err)
+    }
+}
+
+impl From<TransportError> for ConnectError {
+    fn from(err: TransportError) -> Self {
+        ConnectError::Transport(err)
+    }
+}
+
+impl std::fmt::Display for ConnectError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ConnectError::Transport(err) => err.fmt(f),
+            ConnectError::Other(err) => f.write_str(err),
+        }
+    }
+}
+
+/// Set by a `shutdown` event: requests are held and reconnects follow the
+/// announced restart time until `deadline`.
+#[derive(Debug, Clone)]
+struct RestartHold {
+    reason: String,
+    resume_at: Instant,
+    deadline: Instant,
+}
+
+impl RestartHold {
+    /// The hold announced by a `shutdown` event at `now`: until the
+    /// expected restart time plus the profile's grace `window_ms`.
+    fn announced(payload: Option<Value>, window_ms: u64, now: Instant) -> Self {
+        let shutdown = payload.and_then(|p| serde_json::from_value::<ShutdownEventPayload>(p).ok());
+        let expected = Duration::from_millis(
+            shutdown
+                .as_ref()
+                .and_then(|s| s.restart_expected_ms)
+                .unwrap_or(0),
+        )
+        .min(MAX_RESTART_HOLD);
+        let window = Duration::from_millis(window_ms).min(MAX_RESTART_HOLD);
+        Self {
+            reason: shutdown
+                .map(|s| s.reason)
+                .filter(|r| !r.is_empty())
+                .unwrap_or_else(|| "shutdown".to_string()),
+            resume_at: now + expected,
+            deadline: now + expected + window,
+        }
+    }
+
+    fn expired(&self, now: Instant) -> bool {
+        now >= self.deadline
+    }
+
+    /// Wait before the next reconnect attempt: until the announced restart
+    /// time, then every `RESTART_POLL_INTERVAL`, never past the deadline.
+    fn retry_wait(&self, now: Instant) -> Duration {
+        self.resume_at
+            .saturating_duration_since(now)
+            .max(RESTART_POLL_INTERVAL)
+            .min(self.deadline.saturating_duration_since(now))
+    }
+}
+
+// ---- Session --------------------------------------------------------------
+
+struct SessionInner {
+    profile: GatewayProfile,
+    state: ConnectionState,
+    server: Option<HelloOkServer>,
+    policy: Option<GatewayPolicy>,
+    last_error: Option<String>,
+    last_seq: u64,
+    reconnect_attempts: u32,
+    connected_at: Option<Instant>,
+    restart: Option<RestartHold>,
+    approvals: Vec<PendingApproval>,
+    /// Chat turns in progress, by session key, with their run IDs.
+    active_runs: HashMap<String, String>,
+    /// Last reported health status, by agent ID.
+    agent_health: HashMap<String, String>,
+    warnings: Vec<String>,
+    outbound: Option<mpsc::UnboundedSender<String>>,
+    stop: Option<watch::Sender<bool>>,
+    /// The connection loop; a restarted loop waits for the old one.
+    task: Option<tauri::async_runtime::JoinHandle<()>>,
+}
+
+impl SessionInner {
+    /// Approvals still waiting for an answer, dropping those that timed out.
+    fn live_approvals(&mut self) -> &[PendingApproval] {
+        let now = super::now_ms();
+        self.approvals.retain(|a| !a.expired(now));
+        &self.approvals
+    }
+}
+
+/// Spawn `run` once `previous` has finished, so a stopped loop's teardown
+/// (clearing approvals, the outbound channel and the state) cannot land on
+/// top of the loop that replaced it.
+fn spawn_after(
+    previous: Option<tauri::async_runtime::JoinHandle<()>>,
+    run: impl std::future::Future<Output = ()> + Send + 'static,
+) -> tauri::async_runtime::JoinHandle<()> {
+    tauri::async_runtime::spawn(async move {
+        if let Some(previous) = previous {
+            let _ = previous.await;
+        }
+        run.await;
+    })
+}
+
+/// An in-flight request. The frame is kept so it can be re-sent after a
+/// gateway restart.
+struct PendingRequest {
+    frame: String,
+    waiter: oneshot::Sender<Result<Value, GatewayError>>,
+}
+
+type PendingMap = HashMap<String, PendingRequest>;
+
+pub struct GatewaySession {
+    inner: Mutex<SessionInner>,
+    pending: Mutex<PendingMap>,
+    scheduler: RequestScheduler,
+    metrics: SessionMetrics,
+}
+
+impl GatewaySession {
+    pub fn new(profile: GatewayProfile) -> Arc<Self> {
+        Arc::new(Self {
+            inner: Mutex::new(SessionInner {
+                profile,
+                state: ConnectionState::Disconnected,
+                server: None,
+                policy: None,
+                last_error: None,
+                last_seq: 0,
+                reconnect_attempts: 0,
+                connected_at: None,
+                restart: None,
+                approvals: Vec::new(),
+                active_runs: HashMap::new(),
+                agent_health: HashMap::new(),
+                warnings: Vec::new(),
+                outbound: None,
+                stop: None,
+                task: None,
+            }),
+            pending: Mutex::new(HashMap::new()),
+            scheduler: RequestScheduler::default(),
+            metrics: SessionMetrics::default(),
+        })
+    }
+
+    fn lock(&self) -> MutexGuard<'_, SessionInner> {
+        self.inner.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn lock_pending(&self) -> MutexGuard<'_, PendingMap> {
+        self.pending.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    // ---- Accessors --------------------------------------------------------
+
+    pub fn profile(&self) -> GatewayProfile {
+        self.lock().profile.clone()
+    }
+
+    pub fn set_profile(&self, profile: GatewayProfile) {
+        self.lock().profile = profile;
+    }
+
+    pub fn state(&self) -> ConnectionState {
+        self.lock().state
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.lock().stop.is_some()
+    }
+
+    pub fn scheduler(&self) -> &RequestScheduler {
+        &self.scheduler
+    }
+
+    pub fn metrics(&self) -> &SessionMetrics {
+        &self.metrics
+    }
+
+    pub fn pending_approvals(&self) -> Vec<PendingApproval> {
+        self.lock().live_approvals().to_vec()
+    }
+
+    /// Record an approval as if its event had arrived `age_ms` ago.
+    #[cfg(test)]
+    pub(super) fn receive_approval(&self, payload: &Value, age_ms: i64) {
+        let gateway_id = self.profile().id;
+        let mut approval = PendingApproval::from_event(&gateway_id, payload).unwrap();
+        approval.received_at_ms -= age_ms;
+        self.lock().approvals.push(approval);
+    }
+
+    /// Chat turns in progress, by session key.
+    pub fn active_runs(&self) -> Vec<ActiveRun> {
+        let inner = self.lock();
+        let mut runs: Vec<_> = inner
+            .active_runs
+            .iter()
+            .map(|(session_key, run_id)| ActiveRun {
+                gateway_id: inner.profile.id.clone(),
+                session_key: session_key.clone(),
+                run_id: run_id.clone(),
+            })
+            .collect();
+        runs.sort_by(|a, b| a.session_key.cmp(&b.session_key));
+        runs
+    }
+
+    /// Agents whose last reported health is unhealthy.
+    pub fn unhealthy_agents(&self) -> Vec<UnhealthyAgent> {
+        let inner = self.lock();
+        let mut agents: Vec<_> = inner
+            .agent_health
+            .iter()
+            .filter(|(_, status)| UNHEALTHY_STATUSES.contains(&status.as_str()))
+            .map(|(agent_id, status)| UnhealthyAgent {
+                gateway_id: inner.profile.id.clone(),
+                agent_id: agent_id.clone(),
+                status: status.clone(),
+            })
+            .collect();
+        agents.sort_by(|a, b| a.agent_id.cmp(&b.agent_id));
+        agents
+    }
+
+    pub fn summary(&self) -> SessionSummary {
+        let mut inner = self.lock();
+        let pending_approvals = inner.live_approvals().len();
+        SessionSummary {
+            id: inner.profile.id.clone(),
+            name: inner.profile.name.clone(),
+            url: inner.profile.url.clone(),
+            state: inner.state,
+            server_version: inner.server.as_ref().map(|s| s.version.clone()),
+            last_error: inner.last_error.clone(),
+            pending_approvals,
+            reconnect_attempts: inner.reconnect_attempts,
+            latency_ms: self.metrics.current_latency_ms(),
+            warnings: inner.warnings.clone(),
+            active: false,
+        }
+    }
+
+    // ---- Lifecycle --------------------------------------------------------
+
+    /// Start the connection loop (no-op if it is already running). After a
+    /// `stop`, the new loop begins once the old one has wound down.
+    pub fn start(self: &Arc<Self>, app: &AppHandle) {
+        let mut inner = self.lock();
+        if inner.stop.is_some() {
+            return;
+        }
+        let (tx, stop_rx) = watch::channel(false);
+        inner.stop = Some(tx);
+        inner.reconnect_attempts = 0;
+
+        let session = Arc::clone(self);
+        let app = app.clone();
+        let previous = inner.task.take();
+        inner.task = Some(spawn_after(previous, async move {
+            session.run(app, stop_rx).await;
+        }));
+    }
+
+    /// Stop the connection loop and close the socket.
+    pub fn stop(&self) {
+        if let Some(tx) = self.lock().stop.take() {
+            let _ = tx.send(true);
+        }
+    }
+
+    async fn run(self: Arc<Self>, app: AppHandle, mut stop_rx: watch::Receiver<bool>) {
+        let mut delay = RECONNECT_MIN;
+
+        loop {
+            self.set_state(&app, ConnectionState::Connecting, None);
+
+            let result = tokio::select! {
+                result = self.connect_and_serve(&app) => result,
+                _ = stop_rx.wait_for(|stopped| *stopped) => Ok(false),
+            };
+
+            let stopped = *stop_rx.borrow();
+            // Approvals, turns and health are only known while connected
+            let (connected_at, restart, had_approvals, had_runs, had_health) = {
+                let mut inner = self.lock();
+                inner.outbound = None;
+                let had_approvals = !inner.approvals.is_empty();
+                let had_runs = !inner.active_runs.is_empty();
+                let had_health = !inner.agent_health.is_empty();
+                inner.approvals.clear();
+                inner.active_runs.clear();
+                inner.agent_health.clear();
+                if stopped {
+                    inner.restart = None;
+                }
+                (
+                    inner.connected_at.take(),
+                    inner.restart.clone(),
+                    had_approvals,
+                    had_runs,
+                    had_health,
+                )
+            };
+            if had_approvals {
+                super::manager::sessions_changed(&app);
+            }
+            if had_runs {
+                super::manager::runs_changed(&app);
+            }
+            if had_health {
+                super::manager::health_changed(&app);
+            }
+            // Requests survive an announced restart and are re-sent later.
+            if restart.is_none() {
+                self.fail_all_pending("Gateway connection closed");
+            }
+
+            if let Some(connected_at) = connected_at {
+                let reason = match (&result, &restart) {
+                    _ if stopped => "Disconnected by user".to_string(),
+                    (_, Some(restart)) => format!("Gateway shutdown: {}", restart.reason),
+                    (Ok(_), None) => "Closed by gateway".to_string(),
+                    (Err(err), None) => err.to_string(),
+                };
+                self.metrics
+                    .record_disconnect(&reason, connected_at.elapsed());
+            }
+
+            if stopped {
+                self.set_state(&app, ConnectionState::Disconnected, None);
+                break;
+            }
+
+            let error = match result {
+                // Reached `connected` before the socket closed; start backoff fresh.
+                Ok(true) => {
+                    delay = RECONNECT_MIN;
+                    None
+                }
+                Ok(false) => None,
+                Err(ConnectError::Transport(err)) if self.certificate_rejected(&app, &err) => {
+                    // Retrying cannot succeed until the pins change.
+                    self.lock().stop = None;
+                    let message = err.to_string();
+                    self.set_state(&app, ConnectionState::Disconnected, Some(&message));
+                    break;
+                }
+                Err(err) => Some(err.to_string()),
+            };
+
+            self.lock().reconnect_attempts += 1;
+            self.metrics.record_reconnect();
+
+            // A restarting gateway is retried on its announced schedule;
+            // otherwise exponential backoff with jitter (up to 30% of the delay)
+            let now = Instant::now();
+            let wait = match restart {
+                Some(restart) if !restart.expired(now) => {
+                    let error = format!("Gateway restarting: {}", restart.reason);
+                    self.set_state(&app, ConnectionState::Reconnecting, Some(&error));
+                    restart.retry_wait(now)
+                }
+                restart => {
+                    if let Some(restart) = restart {
+                        self.restart_overdue(&app, &restart);
+                    }
+                    self.set_state(&app, ConnectionState::Reconnecting, error.as_deref());
+                    let backoff = delay + delay.mul_f64(rand::random::<f64>() * 0.3);
+                    delay = (delay * 2).min(RECONNECT_MAX);
+                    backoff
+                }
+            };
+            tokio::select! {
+                _ = tokio::time::sleep(wait) => {}
+                _ = stop_rx.wait_for(|stopped| *stopped) => {
+                    self.lock().restart = None;
+                    self.fail_all_pending("Gateway connection closed");
+                    self.set_state(&app, ConnectionState::Disconnected, None);
+                    break;
+                }
+            }
+        }
+    }
+
+    /// The gateway did not come back within its restart window: fail the
+    /// held requests and tell the user.
+    fn restart_overdue(&self, app: &AppHandle, restart: &RestartHold) {
+        let (id, name) = {
+            let mut inner = self.lock();
+            inner.restart = None;
+            (inner.profile.id.clone(), inner.profile.name.clone())
+        };
+        self.fail_all_pending("Gateway did not come back after restart");
+        crate::notifications::notify_error(
+            app,
+            &id,
+            "Gateway did not restart",
+            &format!(
+                "{} went down ({}) and has not come back.",
+                name, restart.reason
+            ),
+        );
+    }
+
+    /// Deadline of the current restart hold, if the gateway announced one.
+    fn restart_deadline(&self) -> Option<Instant> {
+        self.lock().restart.as_ref().map(|r| r.deadline)
+    }
+
+    fn set_state(&self, app: &AppHandle, state: ConnectionState, error: Option<&str>) {
+        let gateway_id = {
+            let mut inner = self.lock();
+            if inner.state == state && error.is_none() {
+                return;
+            }
+            inner.state = state;
+            if let Some(error) = error {
+                inner.last_error = Some(error.to_string());
+            } else if state == ConnectionState::Connected {
+                inner.last_error = None;
+            }
+            inner.profile.id.clone()
+        };
+
+        let _ = app.emit(
+            EVENT_GATEWAY_STATE,
+            GatewayStateEnvelope {
+                gateway_id: &gateway_id,
+                state,
+                error,
+            },
+        );
+        super::manager::sessions_changed(app);
+    }
+
+    /// Emit a certificate trust event for pin mismatches and untrusted
+    /// certificates, and ask the user whether to trust the certificate.
+    /// Returns false for any other error.
+    fn certificate_rejected(&self, app: &AppHandle, err: &TransportError) -> bool {
+        let (issue, spki_sha256, pinned) = match err {
+            TransportError::UntrustedCertificate { spki_sha256, .. } => {
+                (CertificateIssue::Untrusted, spki_sha256, &[][..])
+            }
+            TransportError::PinMismatch { expected, actual } => {
+                (CertificateIssue::PinMismatch, actual, expected.as_slice())
+            }
+            _ => return false,
+        };
+        let profile = self.profile();
+        let _ = app.emit(
+            EVENT_GATEWAY_CERTIFICATE,
+            GatewayCertificateEnvelope {
+                gateway_id: &profile.id,
+                url: &profile.url,
+                issue,
+                spki_sha256,
+                pinned,
+                error: err.to_string(),
+            },
+        );
+        super::manager::confirm_certificate(
+            app,
+            &profile.id,
+            spki_sha256,
+            matches!(issue, CertificateIssue::PinMismatch),
+        );
+        true
+    }
+
+    // ---- Connection -------------------------------------------------------
+
+    /// Connect, handshake and serve frames until the socket closes.
+    /// Returns `Ok(true)` if the connection was fully established.
+    async fn connect_and_serve(&self, app: &AppHandle) -> Result<bool, ConnectError> {
+        let profile = self.profile();
+        let url = profile.url.clone();
+
+        // Tailnet checks are advisory; a missing tailscaled is not an error.
+        let tailnet = tailscale::assess(&url, profile.auth_mode).await.ok();
+        self.lock().warnings = tailnet
+            .as_ref()
+            .map(|t| t.warnings.clone())
+            .unwrap_or_default();
+
+        let (mut sender, mut receiver) = transport::open(&url, &profile.tls_options()).await?;
+        let mut buf = Vec::new();
+
+        // Step 1: wait for connect.challenge
+        let challenge = tokio::time::timeout(HANDSHAKE_TIMEOUT, async {
+            loop {
+                if let Frame::Event(frame) = read_frame(&mut receiver, &mut buf).await? {
+                    if frame.event == "connect.challenge" {
+                        return serde_json::from_value::<ConnectChallengePayload>(
+                            frame.payload.unwrap_or(Value::Null),
+                        )
+                        .map_err(|e| format!("Invalid connect.challenge payload: {}", e));
+                    }
+                }
+            }
+        })
+        .await
+        .map_err(|_| "Handshake timeout: no connect.challenge from server".to_string())??;
+        self.set_state(app, ConnectionState::Challenged, None);
+
+        // Step 2: send connect with the stored device token, if any, plus the
+        // shared password for password-auth gateways
+        let device_id = crate::get_device_id()?;
+        let auth = ConnectAuth {
+            token: keychain::retrieve_token(&device_id, &url)
+                .ok()
+                .map(|t| t.token),
+            password: match profile.auth_mode {
+                AuthMode::Password => Some(
+                    keychain::retrieve_password(&url)
+                        .map_err(|_| "No password saved for this gateway".to_string())?,
+                ),
+                AuthMode::Token | AuthMode::Tailscale => None,
+            },
+        };
+        let params = protocol::build_connect_params(&device_id, &challenge.nonce, &auth)?;
+        let connect_id = protocol::generate_request_id();
+        let frame =
+            serde_json::to_string(&RequestFrame::new(&connect_id, "connect", Some(&params)))
+                .map_err(|e| e.to_string())?;
+        send_text(&mut sender, frame).await?;
+        self.set_state(app, ConnectionState::Authenticating, None);
+
+        // Step 3: wait for hello-ok
+        let hello = tokio::time::timeout(HANDSHAKE_TIMEOUT, async {
+            loop {
+                if let Frame::Response(res) = read_frame(&mut receiver, &mut buf).await? {
+                    if res.id != connect_id {
+                        continue;
+                    }
+                    if !res.ok {
+                        return Err(res
+                            .error
+                            .map(|e| format!("Connect rejected: {}", e))
+                            .unwrap_or_else(|| "Connect rejected".to_string()));
+                    }
+                    return serde_json::from_value::<HelloOkPayload>(
+                        res.payload.unwrap_or(Value::Null),
+                    )
+                    .map_err(|e| format!("Invalid hello-ok payload: {}", e));
+                }
+            }
+        })
+        .await
+        .map_err(|_| "Connect request timed out waiting for hello-ok".to_string())??;
+
+        if hello.kind != "hello-ok" {
+            return Err(format!("Expected hello-ok, got: {}", hello.kind).into());
+        }
+
+        // Step 4: persist the (possibly rotated) device token
+        if let Some(auth) = &hello.auth {
+            persist_device_token(&device_id, &url, auth);
+        }
+
+        let server_auth_mode = hello.snapshot.as_ref().and_then(|s| s.auth_mode.as_deref());
+        let auth_warning = tailnet
+            .as_ref()
+            .and_then(|t| tailscale::server_auth_warning(t.exposure, server_auth_mode));
+
+        let tick_interval_ms = hello.policy.tick_interval_ms.max(1_000);
+        let (outbound_tx, outbound_rx) = mpsc::unbounded_channel();
+        {
+            let mut inner = self.lock();
+            inner.server = Some(hello.server);
+            inner.policy = Some(hello.policy);
+            inner.last_seq = 0;
+            inner.reconnect_attempts = 0;
+            inner.connected_at = Some(Instant::now());
+            inner.warnings.extend(auth_warning);
+            // Re-send requests held across a gateway restart; idempotency
+            // keys make this safe for side-effecting methods.
+            if inner.restart.take().is_some() {
+                for pending in self.lock_pending().values() {
+                    let _ = outbound_tx.send(pending.frame.clone());
+                }
+            }
+            inner.outbound = Some(outbound_tx);
+        }
+        tauri::async_runtime::spawn(write_loop(sender, outbound_rx));
+        self.metrics.connected();
+        self.set_state(app, ConnectionState::Connected, None);
+
+        // Serve frames; any traffic (ticks included) resets the watchdog.
+        let watchdog = Duration::from_millis(tick_interval_ms * TICK_WATCHDOG_MULTIPLIER);
+        loop {
+            match tokio::time::timeout(watchdog, read_frame(&mut receiver, &mut buf)).await {
+                Err(_) => return Err("Tick watchdog timeout".to_string().into()),
+                Ok(Err(err)) if err == CLOSED => return Ok(true),
+                Ok(Err(err)) => return Err(err.into()),
+                Ok(Ok(Frame::Response(res))) => {
+                    let pending = self.lock_pending().remove(&res.id);
+                    if let Some(PendingRequest { waiter, .. }) = pending {
+                        let result = if res.ok {
+                            Ok(res.payload.unwrap_or(Value::Null))
+                        } else {
+                            Err(res.error.unwrap_or_else(|| {
+                                GatewayError::local(
+                                    "UNKNOWN",
+                                    "Request failed without error details",
+                                )
+                            }))
+                        };
+                        let _ = waiter.send(result);
+                    }
+                }
+                Ok(Ok(Frame::Event(event))) => self.handle_event(app, event),
+            }
+        }
+    }
+
+    fn handle_event(&self, app: &AppHandle, frame: EventFrame) {
+        let mut approvals_changed = false;
+        let mut runs_changed = false;
+        let mut health_changed = false;
+        let mut requested = None;
+        let mut resolved = None;
+        let gateway_id = {
+            let mut inner = self.lock();
+            if let Some(seq) = frame.seq {
+                inner.last_seq = seq;
+            }
+            let gateway_id = inner.profile.id.clone();
+
+            // Approvals past their timeout are gone on the gateway
+            let now_ms = super::now_ms();
+            let before = inner.approvals.len();
+            inner.approvals.retain(|a| !a.expired(now_ms));
+            approvals_changed |= inner.approvals.len() != before;
+
+            match (frame.event.as_str(), &frame.payload) {
+                ("shutdown", payload) => {
+                    inner.restart = Some(RestartHold::announced(
+                        payload.clone(),
+                        inner.profile.restart_window_ms,
+                        Instant::now(),
+                    ));
+                }
+                ("tick", _) => {
+                    let expected_ms = inner.policy.as_ref().map_or(0, |p| p.tick_interval_ms);
+                    self.metrics.record_tick(super::now_ms(), expected_ms);
+                }
+                ("exec.approval.requested", Some(payload)) => {
+                    if let Some(approval) = PendingApproval::from_event(&gateway_id, payload) {
+                        inner.approvals.retain(|a| a.id != approval.id);
+                        inner.approvals.push(approval.clone());
+                        requested = Some(approval);
+                        approvals_changed = true;
+                    }
+                }
+                ("exec.approval.resolved", Some(payload)) => {
+                    if let Some(id) = payload.get("id").and_then(Value::as_str) {
+                        let before = inner.approvals.len();
+                        inner.approvals.retain(|a| a.id != id);
+                        approvals_changed |= inner.approvals.len() != before;
+                        let decision = payload.get("decision").and_then(Value::as_str);
+                        resolved = Some((id.to_string(), decision.map(str::to_string)));
+                    }
+                }
+                ("chat", Some(payload)) => {
+                    runs_changed = Self::track_run(&mut inner.active_runs, payload);
+                }
+                ("health", Some(payload)) => {
+                    let field = |key: &str| payload.get(key).and_then(Value::as_str);
+                    if let (Some(agent_id), Some(status)) = (field("agentId"), field("status")) {
+                        let previous = inner
+                            .agent_health
+                            .insert(agent_id.to_string(), status.to_string());
+                        health_changed = previous.as_deref() != Some(status);
+                    }
+                }
+                _ => {}
+            }
+            gateway_id
+        };
+
+        // Large events go out head-first, then in chunks, so the webview
+        // never has to parse one huge payload.
+        let split = frame.payload.map(chunks::split);
+        let (payload, split) = match split {
+            Some(Ok(split)) => (Some(split.head), Some((split.header, split.chunks))),
+            Some(Err(payload)) => (Some(payload), None),
+            None => (None, None),
+        };
+        // Only to the windows subscribed to the event
+        let envelope = GatewayEventEnvelope {
+            gateway_id: &gateway_id,
+            event: &frame.event,
+            payload: &payload,
+            seq: frame.seq,
+            chunked: split.as_ref().map(|(header, _)| header),
+        };
+        let split_chunks = split.as_ref().map_or(&[][..], |(_, chunks)| chunks);
+        for window in crate::hub::recipients(app, &gateway_id, &frame.event, payload.as_ref()) {
+            let _ = app.emit_to(window.as_str(), EVENT_GATEWAY_EVENT, &envelope);
+            for chunk in split_chunks {
+                let _ = app.emit_to(window.as_str(), chunks::EVENT_GATEWAY_EVENT_CHUNK, chunk);
+            }
+        }
+
+        if let Some(approval) = &requested {
+            crate::notifications::notify_exec_approval(app, approval);
+        }
+        if let Some((id, decision)) = &resolved {
+            crate::notifications::exec_approval_resolved(app, &gateway_id, id, decision.as_deref());
+        }
+        if approvals_changed {
+            super::manager::sessions_changed(app);
+        }
+        if runs_changed {
+            super::manager::runs_changed(app);
+        }
+        if health_changed {
+            super::manager::health_changed(app);
+        }
+    }
+
+    /// Fold a `chat` event into the running turns. Handles both the
+    /// state-based schema (`state: delta | final | aborted | error`) and the
+    /// legacy one (`delta`, `done`, `error`). Returns whether a turn started
+    /// or ended.
+    fn track_run(active_runs: &mut HashMap<String, String>, payload: &Value) -> bool {
+        let field = |key: &str| payload.get(key).and_then(Value::as_str);
+        let (Some(session_key), Some(run_id)) = (field("sessionKey"), field("runId")) else {
+            return false;
+        };
+        let finished = match field("state") {
+            Some(state) => state != "delta",
+            None => {
+                payload
+                    .get("done")
+                    .and_then(Value::as_bool)
+                    .unwrap_or(false)
+                    || payload.get("error").is_some_and(|e| !e.is_null())
+            }
+        };
+        if finished {
+            active_runs.remove(session_key).is_some()
+        } else {
+            active_runs
+                .insert(session_key.to_string(), run_id.to_string())
+                .as_deref()
+                != Some(run_id)
+        }
+    }
+
+    fn fail_all_pending(&self, reason: &str) {
+        let pending: Vec<_> = self.lock_pending().drain().collect();
+        for (_, PendingRequest { waiter, .. }) in pending {
+            let _ = waiter.send(Err(GatewayError::local("DISCONNECTED", reason)));
+        }
+    }
+
+    // ---- Requests ---------------------------------------------------------
+
+    /// Send an RPC request and wait for the matching response payload.
+    pub async fn request(
+        &self,
+        method: &str,
+        params: Option<Value>,
+        priority: Priority,
+    ) -> Result<Value, GatewayError> {
+        // During an announced restart requests wait for the reconnect.
+        if self.state() != ConnectionState::Connected && self.restart_deadline().is_none() {
+            return Err(GatewayError::local(
+                "NOT_CONNECTED",
+                format!(
+                    "Cannot send {}: gateway is {}",
+                    method,
+                    self.state().label()
+                ),
+            ));
+        }
+
+        self.scheduler.acquire(method, priority).await;
+
+        let mut params = params;
+        if protocol::SIDE_EFFECTING_METHODS.contains(&method) {
+            let obj = params.get_or_insert_with(|| Value::Object(Default::default()));
+            if let Some(map) = obj.as_object_mut() {
+                map.entry("idempotencyKey")
+                    .or_insert_with(|| Value::String(protocol::generate_idempotency_key()));
+            }
+        }
+
+        let id = protocol::generate_request_id();
+        let frame = serde_json::to_string(&RequestFrame::new(&id, method, params.as_ref()))
+            .map_err(|e| GatewayError::local("INVALID_PARAMS", e.to_string()))?;
+
+        // Register and send under the session lock so a reconnect cannot
+        // re-send the frame a second time.
+        let (tx, mut rx) = oneshot::channel();
+        {
+            let inner = self.lock();
+            let sent = inner
+                .outbound
+                .as_ref()
+                .map(|o| o.send(frame.clone()).is_ok())
+                .unwrap_or(false);
+            if !sent && inner.restart.is_none() {
+                return Err(GatewayError::local(
+                    "DISCONNECTED",
+                    "Gateway socket is not open",
+                ));
+            }
+            self.lock_pending()
+                .insert(id.clone(), PendingRequest { frame, waiter: tx });
+        }
+
+        // The timeout stretches to cover a restart announced meanwhile.
+        let sent_at = Instant::now();
+        let mut deadline = sent_at + REQUEST_TIMEOUT;
+        let result = loop {
+            match tokio::time::timeout_at(deadline.into(), &mut rx).await {
+                Ok(result) => break Ok(result),
+                Err(_) => match self.restart_deadline() {
+                    Some(until) if until > deadline => deadline = until,
+                    _ => break Err(()),
+                },
+            }
+        };
+        match result {
+            Err(_) => {
+                self.lock_pending().remove(&id);
+                Err(GatewayError::local(
+                    "TIMEOUT",
+                    format!(
+                        "Request timeout after {}ms: {}",
+                        sent_at.elapsed().as_millis(),
+                        method
+                    ),
+                ))
+            }
+            Ok(Err(_)) => Err(GatewayError::local(
+                "DISCONNECTED",
+                "Gateway connection closed",
+            )),
+            Ok(Ok(Err(err))) => {
+                self.metrics.record_rtt(method, sent_at.elapsed(), false);
+                if err.retryable == Some(true) {
+                    if let Some(retry_after_ms) = err.retry_after_ms {
+                        self.scheduler.backoff(method, retry_after_ms);
+                    }
+                }
+                Err(err)
+            }
+            Ok(Ok(Ok(payload))) => {
+                self.metrics.record_rtt(method, sent_at.elapsed(), true);
+                Ok(payload)
+            }
+        }
+    }
+}
+
+/// Store the device token from hello-ok. Keychain failures must not fail
+/// the connection, so errors are ignored.
+pub(super) fn persist_device_token(device_id: &str, url: &str, auth: &HelloOkAuth) {
+    let token = StoredDeviceToken {
+        token: auth.device_token.clone(),
+        device_id: device_id.to_string(),
+        gateway_url: url.to_string(),
+        issued_at_ms: auth.issued_at_ms.unwrap_or_else(super::now_ms),
+        stored_at_ms: super::now_ms(),
+        role: auth.role.clone(),
+        scopes: auth.scopes.clone(),
+    };
+    let _ = keychain::store_token(device_id, url, &token);
+}
+
+// ---- Socket Helpers -------------------------------------------------------
+
+/// Sentinel error returned by `read_frame` when the peer closed cleanly.
+pub(super) const CLOSED: &str = "closed";
+
+/// Frames at least this large are parsed on the blocking pool.
+const OFFLOAD_PARSE_BYTES: usize = 256 * 1024;
+
+/// Read the next frame and parse it. Text and binary frames both carry
+/// JSON; anything else is skipped.
+pub(super) async fn read_frame(
+    receiver: &mut WsReceiver,
+    buf: &mut Vec<u8>,
+) -> Result<Frame, String> {
+    loop {
+        buf.clear();
+        match receiver.receive_data(buf).await {
+            Ok(_) if buf.len() >= OFFLOAD_PARSE_BYTES => {
+                let data = std::mem::take(buf);
+                let (data, frame) = tauri::async_runtime::spawn_blocking(move || {
+                    let frame = serde_json::from_slice::<Frame>(&data).ok();
+                    (data, frame)
+                })
+                .await
+                .map_err(|e| e.to_string())?;
+                *buf = data;
+                if let Some(frame) = frame {
+                    return Ok(frame);
+                }
+            }
+            Ok(_) => {
+                if let Ok(frame) = serde_json::from_slice::<Frame>(buf) {
+                    return Ok(frame);
+                }
+            }
+            Err(soketto::connection::Error::Closed) => return Err(CLOSED.to_string()),
+            Err(e) => return Err(e.to_string()),
+        }
+    }
+}
+
+pub(super) async fn send_text(sender: &mut WsSender, text: String) -> Result<(), String> {
+    sender
+        .send_text_owned(text)
+        .await
+        .map_err(|e| e.to_string())?;
+    sender.flush().await.map_err(|e| e.to_string())
+}
+
+/// Drain queued outbound frames onto the socket until the queue closes.
+async fn write_loop(mut sender: WsSender, mut outbound: mpsc::UnboundedReceiver<String>) {
+    while let Some(text) = outbound.recv().await {
+        if send_text(&mut sender, text).await.is_err() {
+            break;
+        }
+    }
+    let _ = sender.close().await;
+}
+
+// ---- Tests ----------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn session() -> Arc<GatewaySession> {
+        GatewaySession::new(GatewayProfile::new(
+            "home".to_string(),
+            "Home".to_string(),
+            "ws://127.0.0.1:18789".to_string(),
+        ))
+    }
+
+    fn payload(id: &str, timeout_ms: u64) -> Value {
+        json!({ "id": id, "command": "ls", "timeoutMs": timeout_ms })
+    }
+
+    #[test]
+    fn summary_counts_the_approvals_that_are_listed() {
+        let session = session();
+        session.receive_approval(&payload("expired", 1_000), 5_000);
+        session.receive_approval(&payload("live", 60_000), 5_000);
+
+        assert_eq!(session.summary().pending_approvals, 1);
+        let ids: Vec<_> = session
+            .pending_approvals()
+            .into_iter()
+            .map(|a| a.id)
+            .collect();
+        assert_eq!(ids, vec!["live"]);
+    }
+
+    #[test]
+    fn approvals_without_timeout_never_expire() {
+        let approval = PendingApproval::from_event("home", &json!({ "id": "a" })).unwrap();
+        assert!(!approval.expired(i64::MAX));
+    }
+
+    #[test]
+    fn shutdown_holds_until_the_restart_time_plus_the_window() {
+        let now = Instant::now();
+        let hold = RestartHold::announced(
+            Some(json!({ "reason": "update", "restartExpectedMs": 10_000 })),
+            5_000,
+            now,
+        );
+        assert_eq!(hold.reason, "update");
+        assert_eq!(hold.resume_at, now + Duration::from_secs(10));
+        assert_eq!(hold.deadline, now + Duration::from_secs(15));
+
+        // Without details the gateway is retried right away
+        let hold = RestartHold::announced(None, 5_000, now);
+        assert_eq!(hold.reason, "shutdown");
+        assert_eq!(hold.resume_at, now);
+        assert_eq!(hold.deadline, now + Duration::from_secs(5));
+    }
+
+    #[test]
+    fn restart_hold_is_capped() {
+        let now = Instant::now();
+        let hold = RestartHold::announced(
+            Some(json!({ "reason": "", "restartExpectedMs": u64::MAX })),
+            u64::MAX,
+            now,
+        );
+        assert_eq!(hold.reason, "shutdown");
+        assert_eq!(hold.resume_at, now + MAX_RESTART_HOLD);
+        assert_eq!(hold.deadline, now + MAX_RESTART_HOLD * 2);
+        assert!(!hold.expired(now + MAX_RESTART_HOLD * 2 - Duration::from_secs(1)));
+        assert!(hold.expired(now + MAX_RESTART_HOLD * 2));
+    }
+
+    #[test]
+    fn reconnects_follow_the_announced_restart() {
+        let now = Instant::now();
+        let hold = RestartHold::announced(
+            Some(json!({ "reason": "update", "restartExpectedMs": 10_000 })),
+            5_000,
+            now,
+        );
+        // Until the restart time, then polled, never past the deadline
+        assert_eq!(hold.retry_wait(now), Duration::from_secs(10));
+        assert_eq!(
+            hold.retry_wait(now + Duration::from_secs(10)),
+            RESTART_POLL_INTERVAL
+        );
+        assert_eq!(
+            hold.retry_wait(now + Duration::from_secs(14)),
+            Duration::from_secs(1)
+        );
+        assert!(!hold.expired(now + Duration::from_secs(14)));
+        assert!(hold.expired(now + Duration::from_secs(15)));
+    }
+
+    #[tokio::test]
+    async fn requests_wait_out_an_announced_restart() {
+        let session = session();
+        let err = session
+            .request("health", None, Priority::Interactive)
+            .await
+            .unwrap_err();
+        assert_eq!(err.code, "NOT_CONNECTED");
+
+        session.lock().restart = Some(RestartHold::announced(None, 60_000, Instant::now()));
+        let held = tokio::time::timeout(
+            Duration::from_millis(50),
+            session.request("health", None, Priority::Interactive),
+        )
+        .await;
+        assert!(held.is_err(), "request should be held");
+        // Kept for re-sending once the gateway is back
+        assert_eq!(session.lock_pending().len(), 1);
+    }
+
+    #[test]
+    fn restarted_loop_starts_after_the_old_one_ends() {
+        let order = Arc::new(Mutex::new(Vec::new()));
+        let old = spawn_after(None, {
+            let order = Arc::clone(&order);
+            async move {
+                tokio::time::sleep(Duration::from_millis(20)).await;
+                order.lock().unwrap().push("old loop ended");
+            }
+        });
+        let new = spawn_after(Some(old), {
+            let order = Arc::clone(&order);
+            async move {
+                order.lock().unwrap().push("new loop started");
+            }
+        });
+
+        tauri::async_runtime::block_on(new).unwrap();
+        assert_eq!(
+            *order.lock().unwrap(),
+            vec!["old loop ended", "new loop started"]
+        );
+    }
+}
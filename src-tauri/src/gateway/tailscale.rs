@@ -0,0 +1,537 @@
+// ---------------------------------------------------------------------------
+// Tailscale Awareness
+// ---------------------------------------------------------------------------
+//
+// Recognises gateways reached over a tailnet (see docs/gateway/tailscale.md)
+// by asking the local tailscaled for its status over the LocalAPI socket:
+// - Serve:  `https://<node>.<tailnet>.ts.net` on a peer in our netmap;
+//           Tailscale identity headers authenticate us
+// - Funnel: a `*.ts.net` name that is not reachable through our tailnet,
+//           i.e. public; the gateway must use password auth. Without a
+//           readable status the two cannot be told apart (Unknown).
+// - Direct: `ws://<tailscale-ip>:18789` (`gateway.bind: "tailnet"`); token
+//
+// The socket path is injectable so a fake LocalAPI server can stand in for
+// tailscaled.
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::net::IpAddr;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+// ---- Constants ------------------------------------------------------------
+
+/// Where tailscaled listens: Linux packages, then the macOS open-source
+/// daemon. The Mac App Store variant has no socket and is reported as
+/// unavailable.
+const SOCKET_CANDIDATES: [&str; 2] = [
+    "/var/run/tailscale/tailscaled.sock",
+    "/var/run/tailscaled.socket",
+];
+
+const LOCAL_API_TIMEOUT: Duration = Duration::from_secs(2);
+const MAGIC_DNS_DOMAIN: &str = ".ts.net";
+
+// ---- LocalAPI Status ------------------------------------------------------
+
+/// One node (ourselves or a peer) from `/localapi/v0/status`.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct NodeStatus {
+    #[serde(default, rename = "DNSName")]
+    pub dns_name: String,
+    #[serde(default)]
+    pub host_name: String,
+    #[serde(default, rename = "TailscaleIPs")]
+    pub tailscale_ips: Vec<IpAddr>,
+}
+
+impl NodeStatus {
+    /// MagicDNS name without the trailing root dot.
+    pub fn magic_dns_name(&self) -> &str {
+        self.dns_name.trim_end_matches('.')
+    }
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct TailnetInfo {
+    #[serde(default)]
+    pub name: String,
+    #[serde(default, rename = "MagicDNSSuffix")]
+    pub magic_dns_suffix: String,
+}
+
+/// The subset of tailscaled's status the app needs.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct TailscaleStatus {
+    #[serde(default)]
+    pub backend_state: String,
+    #[serde(default, rename = "Self")]
+    pub self_node: Option<NodeStatus>,
+    #[serde(default)]
+    pub current_tailnet: Option<TailnetInfo>,
+    #[serde(default)]
+    pub peer: HashMap<String, NodeStatus>,
+}
+
+impl TailscaleStatus {
+    pub fn is_running(&self) -> bool {
+        self.backend_state == "Running"
+    }
+
+    fn nodes(&self) -> impl Iterator<Item = &NodeStatus> {
+        self.self_node.iter().chain(self.peer.values())
+    }
+
+    /// The node (self or peer) with this MagicDNS name, short host name or IP.
+    pub fn find_node(&self, host: &str) -> Option<&NodeStatus> {
+        let host = host.trim_end_matches('.').to_lowercase();
+        let ip: Option<IpAddr> = host.parse().ok();
+        self.nodes().find(|node| {
+            node.magic_dns_name().eq_ignore_ascii_case(&host)
+                || node.host_name.eq_ignore_ascii_case(&host)
+                || ip.is_some_and(|ip| node.tailscale_ips.contains(&ip))
+        })
+    }
+}
+
+// ---- LocalAPI Client ------------------------------------------------------
+
+/// Client for tailscaled's LocalAPI over its Unix socket.
+#[derive(Debug, Clone)]
+pub struct LocalApi {
+    socket: PathBuf,
+}
+
+impl LocalApi {
+    pub fn at(socket: impl Into<PathBuf>) -> Self {
+        Self {
+            socket: socket.into(),
+        }
+    }
+
+    /// The first well-known socket that exists on this machine.
+    pub fn discover() -> Option<Self> {
+        SOCKET_CANDIDATES
+            .iter()
+            .map(Path::new)
+            .find(|path| path.exists())
+            .map(Self::at)
+    }
+
+    pub async fn status(&self) -> Result<TailscaleStatus, String> {
+        let body = tokio::time::timeout(LOCAL_API_TIMEOUT, self.get("/localapi/v0/status"))
+            .await
+            .map_err(|_| "tailscaled did not answer in time".to_string())??;
+        serde_json::from_slice(&body).map_err(|e| format!("Invalid tailscaled status: {}", e))
+    }
+
+    /// Minimal HTTP/1.0 GET; the connection closes after the response.
+    #[cfg(unix)]
+    async fn get(&self, path: &str) -> Result<Vec<u8>, String> {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let mut stream = tokio::net::UnixStream::connect(&self.socket)
+            .await
+            .map_err(|e| {
+                format!(
+                    "Cannot reach tailscaled at {}: {}",
+                    self.socket.display(),
+                    e
+                )
+            })?;
+        let request = format!(
+            "GET {} HTTP/1.0\r\nHost: local-tailscaled.sock\r\nTailscale-Cap: 1\r\n\r\n",
+            path
+        );
+        stream
+            .write_all(request.as_bytes())
+            .await
+            .map_err(|e| e.to_string())?;
+
+        let mut response = Vec::new();
+        stream
+            .read_to_end(&mut response)
+            .await
+            .map_err(|e| e.to_string())?;
+
+        let split = response
+            .windows(4)
+            .position(|w| w == b"\r\n\r\n")
+            .ok_or_else(|| "Malformed tailscaled response".to_string())?;
+        let status_line = String::from_utf8_lossy(&response[..split]);
+        let status_line = status_line.lines().next().unwrap_or_default();
+        if status_line.split_whitespace().nth(1) != Some("200") {
+            return Err(format!("tailscaled returned {}", status_line));
+        }
+        Ok(response[split + 4..].to_vec())
+    }
+
+    #[cfg(not(unix))]
+    async fn get(&self, _path: &str) -> Result<Vec<u8>, String> {
+        Err("The tailscaled LocalAPI socket is not supported on this platform".to_string())
+    }
+}
+
+// ---- Assessment -----------------------------------------------------------
+
+/// How the gateway URL reaches the gateway.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TailnetExposure {
+    /// Not a tailnet address.
+    None,
+    /// Tailscale Serve: tailnet-only HTTPS proxy.
+    Serve,
+    /// Tailscale Funnel: public HTTPS.
+    Funnel,
+    /// Direct bind to the gateway's tailnet IP.
+    Direct,
+    /// A MagicDNS name, but tailscaled's status could not be read, so
+    /// Serve and Funnel cannot be told apart.
+    Unknown,
+}
+
+/// How the backend authenticates the `connect` handshake.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AuthMode {
+    /// Device token (or a pasted gateway token).
+    #[default]
+    Token,
+    /// Shared gateway password, kept in the keychain.
+    Password,
+    /// Tailscale Serve identity headers; only the device token is sent.
+    Tailscale,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TailnetAssessment {
+    pub exposure: TailnetExposure,
+    /// tailscaled answered and is logged in.
+    pub tailscale_running: bool,
+    pub tailnet_name: Option<String>,
+    /// Same gateway, addressed by its MagicDNS name.
+    pub magic_dns_url: Option<String>,
+    pub recommended_auth: AuthMode,
+    pub warnings: Vec<String>,
+}
+
+/// CGNAT range (100.64.0.0/10) and the Tailscale ULA prefix (fd7a:115c:a1e0::/48).
+fn is_tailscale_ip(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(v4) => {
+            let [a, b, ..] = v4.octets();
+            a == 100 && (64..128).contains(&b)
+        }
+        IpAddr::V6(v6) => {
+            let s = v6.segments();
+            s[0] == 0xfd7a && s[1] == 0x115c && s[2] == 0xa1e0
+        }
+    }
+}
+
+/// Classify `url` against a tailscaled status (`None` when tailscaled is
+/// unavailable) and the auth mode the profile is configured with.
+pub fn assess_with(
+    url: &str,
+    auth_mode: AuthMode,
+    status: Option<&TailscaleStatus>,
+) -> Result<TailnetAssessment, String> {
+    let parsed = url::Url::parse(url).map_err(|e| format!("Invalid gateway URL: {}", e))?;
+    let host = parsed
+        .host_str()
+        .ok_or_else(|| "Invalid gateway URL: missing host".to_string())?
+        .trim_start_matches('[')
+        .trim_end_matches(']')
+        .to_lowercase();
+    let tls = parsed.scheme() == "wss";
+
+    let running = status.is_some_and(TailscaleStatus::is_running);
+    let node = status.filter(|_| running).and_then(|s| s.find_node(&host));
+    let ip: Option<IpAddr> = host.parse().ok();
+    let magic_suffix = status
+        .and_then(|s| s.current_tailnet.as_ref())
+        .map(|t| t.magic_dns_suffix.trim_end_matches('.').to_lowercase())
+        .filter(|s| !s.is_empty());
+    let is_magic_dns = host.ends_with(MAGIC_DNS_DOMAIN)
+        || magic_suffix
+            .as_deref()
+            .is_some_and(|suffix| host.ends_with(&format!(".{}", suffix)));
+
+    let exposure = if ip.is_some_and(is_tailscale_ip) || (node.is_some() && !is_magic_dns) {
+        TailnetExposure::Direct
+    } else if is_magic_dns && node.is_some() {
+        TailnetExposure::Serve
+    } else if is_magic_dns && running {
+        // A ts.net name we cannot reach through our own tailnet is public.
+        TailnetExposure::Funnel
+    } else if is_magic_dns {
+        TailnetExposure::Unknown
+    } else {
+        TailnetExposure::None
+    };
+
+    let recommended_auth = match exposure {
+        TailnetExposure::Serve => AuthMode::Tailscale,
+        TailnetExposure::Funnel => AuthMode::Password,
+        TailnetExposure::Direct | TailnetExposure::None => AuthMode::Token,
+        // Nothing to base a recommendation on; keep what is configured.
+        TailnetExposure::Unknown => auth_mode,
+    };
+
+    let mut warnings = Vec::new();
+    if exposure == TailnetExposure::Funnel && auth_mode != AuthMode::Password {
+        warnings.push(
+            "This gateway is exposed publicly through Tailscale Funnel without password auth"
+                .to_string(),
+        );
+    }
+    if matches!(
+        exposure,
+        TailnetExposure::Serve | TailnetExposure::Funnel | TailnetExposure::Unknown
+    ) && !tls
+    {
+        warnings.push("Tailscale Serve and Funnel only accept wss:// connections".to_string());
+    }
+    if auth_mode == AuthMode::Tailscale
+        && !matches!(exposure, TailnetExposure::Serve | TailnetExposure::Unknown)
+    {
+        warnings.push("Tailscale identity auth only works through Tailscale Serve".to_string());
+    }
+    if exposure == TailnetExposure::Direct && !running {
+        warnings.push("Tailscale is not running on this device".to_string());
+    }
+
+    // Offer the MagicDNS name when the URL uses a raw tailnet IP or short name.
+    let magic_dns_url = node
+        .map(NodeStatus::magic_dns_name)
+        .filter(|name| !name.is_empty() && *name != host)
+        .and_then(|name| {
+            let mut magic = parsed.clone();
+            magic.set_host(Some(name)).ok()?;
+            Some(magic.to_string().trim_end_matches('/').to_string())
+        });
+
+    Ok(TailnetAssessment {
+        exposure,
+        tailscale_running: running,
+        tailnet_name: status
+            .and_then(|s| s.current_tailnet.as_ref())
+            .map(|t| t.name.clone())
+            .filter(|name| !name.is_empty()),
+        magic_dns_url,
+        recommended_auth,
+        warnings,
+    })
+}
+
+/// Query tailscaled (if present) and classify `url`.
+pub async fn assess(url: &str, auth_mode: AuthMode) -> Result<TailnetAssessment, String> {
+    let status = match LocalApi::discover() {
+        Some(api) => api.status().await.ok(),
+        None => None,
+    };
+    assess_with(url, auth_mode, status.as_ref())
+}
+
+/// Warning for a connected gateway whose own reported auth mode is not
+/// "password" while it is reachable via Funnel.
+pub fn server_auth_warning(
+    exposure: TailnetExposure,
+    server_auth_mode: Option<&str>,
+) -> Option<String> {
+    match (exposure, server_auth_mode) {
+        (TailnetExposure::Funnel, Some(mode)) if mode != "password" => Some(format!(
+            "Gateway reports auth mode \"{}\" while exposed through Tailscale Funnel",
+            mode
+        )),
+        _ => None,
+    }
+}
+
+// ---- Tauri Commands -------------------------------------------------------
+
+/// Tailnet classification, MagicDNS URL and auth recommendation for a URL.
+#[tauri::command]
+pub async fn gateway_tailscale_assess(
+    url: String,
+    auth_mode: Option<AuthMode>,
+) -> Result<TailnetAssessment, String> {
+    assess(&url, auth_mode.unwrap_or_default()).await
+}
+
+// ---- Tests ----------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const STATUS: &str = r#"{
+        "BackendState": "Running",
+        "Self": {
+            "DNSName": "laptop.tail1234.ts.net.",
+            "HostName": "laptop",
+            "TailscaleIPs": ["100.101.102.1"]
+        },
+        "CurrentTailnet": { "Name": "example.com", "MagicDNSSuffix": "tail1234.ts.net" },
+        "Peer": {
+            "nodekey:abc": {
+                "DNSName": "gateway.tail1234.ts.net.",
+                "HostName": "gateway",
+                "TailscaleIPs": ["100.101.102.103", "fd7a:115c:a1e0::1"]
+            }
+        }
+    }"#;
+
+    fn status() -> TailscaleStatus {
+        serde_json::from_str(STATUS).unwrap()
+    }
+
+    /// Serve one canned HTTP response on a Unix socket, like tailscaled's
+    /// LocalAPI, and return the socket path.
+    #[cfg(unix)]
+    async fn fake_local_api(name: &str, response: String) -> PathBuf {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let path = std::env::temp_dir().join(format!(
+            "fireplace-tailscaled-{}-{}.sock",
+            std::process::id(),
+            name
+        ));
+        let _ = std::fs::remove_file(&path);
+        let listener = tokio::net::UnixListener::bind(&path).unwrap();
+        tokio::spawn(async move {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let mut request = [0u8; 1024];
+            let _ = stream.read(&mut request).await;
+            stream.write_all(response.as_bytes()).await.unwrap();
+            let _ = stream.shutdown().await;
+        });
+        path
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn local_api_reads_status_from_socket() {
+        let response = format!(
+            "HTTP/1.0 200 OK\r\nContent-Type: application/json\r\n\r\n{}",
+            STATUS
+        );
+        let socket = fake_local_api("status", response).await;
+        let status = LocalApi::at(&socket).status().await.unwrap();
+        let _ = std::fs::remove_file(&socket);
+
+        assert!(status.is_running());
+        assert_eq!(status.peer.len(), 1);
+        let assessment = assess_with(
+            "wss://gateway.tail1234.ts.net",
+            AuthMode::Token,
+            Some(&status),
+        )
+        .unwrap();
+        assert_eq!(assessment.exposure, TailnetExposure::Serve);
+        assert_eq!(assessment.tailnet_name.as_deref(), Some("example.com"));
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn local_api_reports_http_errors() {
+        let response = "HTTP/1.0 403 Forbidden\r\n\r\naccess denied".to_string();
+        let socket = fake_local_api("forbidden", response).await;
+        let err = LocalApi::at(&socket).status().await.unwrap_err();
+        let _ = std::fs::remove_file(&socket);
+        assert!(err.contains("403"), "{}", err);
+    }
+
+    #[tokio::test]
+    async fn local_api_without_socket_is_an_error() {
+        let socket = std::env::temp_dir().join("fireplace-tailscaled-missing.sock");
+        assert!(LocalApi::at(socket).status().await.is_err());
+    }
+
+    #[test]
+    fn serve_peer_recommends_tailscale_auth() {
+        let assessment = assess_with(
+            "wss://gateway.tail1234.ts.net",
+            AuthMode::Token,
+            Some(&status()),
+        )
+        .unwrap();
+        assert_eq!(assessment.exposure, TailnetExposure::Serve);
+        assert_eq!(assessment.recommended_auth, AuthMode::Tailscale);
+        assert!(assessment.warnings.is_empty());
+    }
+
+    #[test]
+    fn unknown_ts_net_host_is_funnel() {
+        let assessment = assess_with(
+            "wss://public.other.ts.net",
+            AuthMode::Token,
+            Some(&status()),
+        )
+        .unwrap();
+        assert_eq!(assessment.exposure, TailnetExposure::Funnel);
+        assert_eq!(assessment.recommended_auth, AuthMode::Password);
+        assert_eq!(assessment.warnings.len(), 1);
+    }
+
+    #[test]
+    fn ts_net_host_without_status_is_unknown() {
+        let assessment =
+            assess_with("wss://gateway.tail1234.ts.net", AuthMode::Token, None).unwrap();
+        assert_eq!(assessment.exposure, TailnetExposure::Unknown);
+        assert_eq!(assessment.recommended_auth, AuthMode::Token);
+        assert!(assessment.warnings.is_empty());
+        assert!(!assessment.tailscale_running);
+    }
+
+    #[test]
+    fn ts_net_host_with_stopped_tailscale_is_unknown() {
+        let mut status = status();
+        status.backend_state = "Stopped".to_string();
+        let assessment = assess_with(
+            "wss://gateway.tail1234.ts.net",
+            AuthMode::Password,
+            Some(&status),
+        )
+        .unwrap();
+        assert_eq!(assessment.exposure, TailnetExposure::Unknown);
+        assert_eq!(assessment.recommended_auth, AuthMode::Password);
+    }
+
+    #[test]
+    fn tailnet_ip_is_direct_and_offers_magic_dns() {
+        let assessment = assess_with(
+            "ws://100.101.102.103:18789",
+            AuthMode::Token,
+            Some(&status()),
+        )
+        .unwrap();
+        assert_eq!(assessment.exposure, TailnetExposure::Direct);
+        assert_eq!(assessment.recommended_auth, AuthMode::Token);
+        assert_eq!(
+            assessment.magic_dns_url.as_deref(),
+            Some("ws://gateway.tail1234.ts.net:18789")
+        );
+    }
+
+    #[test]
+    fn lan_host_is_not_on_the_tailnet() {
+        let assessment =
+            assess_with("ws://192.168.1.20:18789", AuthMode::Token, Some(&status())).unwrap();
+        assert_eq!(assessment.exposure, TailnetExposure::None);
+        assert!(assessment.magic_dns_url.is_none());
+    }
+
+    #[test]
+    fn funnel_warns_about_server_auth_mode() {
+        assert!(server_auth_warning(TailnetExposure::Funnel, Some("token")).is_some());
+        assert!(server_auth_warning(TailnetExposure::Funnel, Some("password")).is_none());
+        assert!(server_auth_warning(TailnetExposure::Unknown, Some("token")).is_none());
+    }
+}
@@ -0,0 +1,450 @@
+// ---------------------------------------------------------------------------
+// Window State Hub
+// ---------------------------------------------------------------------------
+//
+// One backend-owned hub shared by every window. Gateway sessions publish
+// their events here, and the hub fans each one out to the windows
+// subscribed to it, so a popped-out chat only hears its own session and the
+// logs window only hears logs. The main window subscribes to everything.
+//
+// Subscriptions are keyed by window label and dropped when the window is
+// destroyed. A window sets its own filters with `hub_subscribe`; pop-outs
+// start with the filters of what they show. State snapshots (session list,
+// running turns, agent health) are small and still go to every window.
+//
+// The main window's own gateway connection lives in its webview, so it is
+// relayed: while a pop-out is subscribed, the main window publishes that
+// connection's events here under `MAIN_CONNECTION_ID`, and requests from
+// pop-outs are handed to it and answered through `hub_respond`. Pop-outs
+// therefore never open a connection of their own.
+
+use crate::gateway::manager::GatewayManager;
+use crate::gateway::protocol::{self, GatewayError};
+use crate::gateway::scheduler::Priority;
+use crate::gateway::session::EVENT_GATEWAY_EVENT;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::sync::{Mutex, MutexGuard};
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Manager};
+use tokio::sync::oneshot;
+
+/// Label of the main window, subscribed to every event.
+const MAIN_WINDOW: &str = "main";
+
+/// Gateway ID the main window's own connection is relayed under.
+pub const MAIN_CONNECTION_ID: &str = "main";
+
+/// Event emitted to the main window with whether any pop-out wants its
+/// connection's events relayed.
+pub const EVENT_HUB_RELAY: &str = "hub://relay";
+
+/// Event emitted to the main window with a pop-out's request for its
+/// connection.
+pub const EVENT_HUB_REQUEST: &str = "hub://request";
+
+/// How long a pop-out's request waits for the main window to answer.
+const RELAY_TIMEOUT: Duration = Duration::from_secs(60);
+
+// ---- Filters --------------------------------------------------------------
+
+/// Which gateway events a window receives. A filter with a `gateway_id`
+/// only matches that gateway's events.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+pub enum EventFilter {
+    /// Every event.
+    All,
+    /// `chat` and `agent` events of one session.
+    #[serde(rename_all = "camelCase")]
+    Session {
+        #[serde(default)]
+        gateway_id: Option<String>,
+        session_key: String,
+    },
+    /// `exec.approval.*` events.
+    #[serde(rename_all = "camelCase")]
+    Approvals {
+        #[serde(default)]
+        gateway_id: Option<String>,
+    },
+    /// Log events (`log`, `logs.*`).
+    #[serde(rename_all = "camelCase")]
+    Logs {
+        #[serde(default)]
+        gateway_id: Option<String>,
+    },
+}
+
+impl EventFilter {
+    fn gateway_id(&self) -> Option<&str> {
+        match self {
+            EventFilter::All => None,
+            EventFilter::Session { gateway_id, .. }
+            | EventFilter::Approvals { gateway_id }
+            | EventFilter::Logs { gateway_id } => gateway_id.as_deref(),
+        }
+    }
+
+    /// Whether `event` from `gateway_id` passes the filter.
+    pub fn matches(&self, gateway_id: &str, event: &str, payload: Option<&Value>) -> bool {
+        if self.gateway_id().is_some_and(|id| id != gateway_id) {
+            return false;
+        }
+        match self {
+            EventFilter::All => true,
+            EventFilter::Session { session_key, .. } => {
+                matches!(event, "chat" | "agent")
+                    && payload
+                        .and_then(|p| p.get("sessionKey"))
+                        .and_then(Value::as_str)
+                        == Some(session_key.as_str())
+            }
+            EventFilter::Approvals { .. } => event.starts_with("exec.approval."),
+            EventFilter::Logs { .. } => event == "log" || event.starts_with("logs."),
+        }
+    }
+}
+
+// ---- Hub ------------------------------------------------------------------
+
+type RelayReply = oneshot::Sender<Result<Value, GatewayError>>;
+
+/// Event filters by window label, and the pop-out requests the main window
+/// has yet to answer.
+#[derive(Default)]
+pub struct StateHub {
+    windows: Mutex<HashMap<String, Vec<EventFilter>>>,
+    relayed: Mutex<HashMap<String, RelayReply>>,
+}
+
+impl StateHub {
+    fn lock(&self) -> MutexGuard<'_, HashMap<String, Vec<EventFilter>>> {
+        self.windows.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn lock_relayed(&self) -> MutexGuard<'_, HashMap<String, RelayReply>> {
+        self.relayed.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Replace a window's filters; an empty list receives nothing.
+    pub fn subscribe(&self, window: &str, filters: Vec<EventFilter>) {
+        self.lock().insert(window.to_string(), filters);
+    }
+
+    pub fn unsubscribe(&self, window: &str) {
+        self.lock().remove(window);
+    }
+
+    /// Labels of the windows that receive `event` from `gateway_id`.
+    pub fn recipients(
+        &self,
+        gateway_id: &str,
+        event: &str,
+        payload: Option<&Value>,
+    ) -> Vec<String> {
+        self.lock()
+            .iter()
+            .filter(|(_, filters)| {
+                filters
+                    .iter()
+                    .any(|filter| filter.matches(gateway_id, event, payload))
+            })
+            .map(|(label, _)| label.clone())
+            .collect()
+    }
+
+    /// Whether any other window wants events of the main window's
+    /// connection.
+    pub fn relaying(&self) -> bool {
+        self.lock().iter().any(|(label, filters)| {
+            label != MAIN_WINDOW
+                && filters
+                    .iter()
+                    .any(|f| f.gateway_id().is_none_or(|id| id == MAIN_CONNECTION_ID))
+        })
+    }
+
+    /// The gateway a window's requests go to: the one its filters name, or
+    /// the main window's connection.
+    pub fn gateway_of(&self, window: &str) -> String {
+        self.lock()
+            .get(window)
+            .and_then(|filters| filters.iter().find_map(EventFilter::gateway_id))
+            .unwrap_or(MAIN_CONNECTION_ID)
+            .to_string()
+    }
+}
+
+/// Subscribe the main window to every event.
+pub fn setup(app: &AppHandle) {
+    app.state::<StateHub>()
+        .subscribe(MAIN_WINDOW, vec![EventFilter::All]);
+}
+
+/// Replace a window's filters, telling the main window whether to relay.
+pub fn subscribe(app: &AppHandle, window: &str, filters: Vec<EventFilter>) {
+    let hub = app.state::<StateHub>();
+    hub.subscribe(window, filters);
+    let _ = app.emit_to(MAIN_WINDOW, EVENT_HUB_RELAY, hub.relaying());
+}
+
+/// Drop a window's filters, telling the main window whether to relay.
+pub fn unsubscribe(app: &AppHandle, window: &str) {
+    let hub = app.state::<StateHub>();
+    hub.unsubscribe(window);
+    let _ = app.emit_to(MAIN_WINDOW, EVENT_HUB_RELAY, hub.relaying());
+}
+
+/// Labels of the windows that receive `event` from `gateway_id`; none
+/// before the hub is managed.
+pub fn recipients(
+    app: &AppHandle,
+    gateway_id: &str,
+    event: &str,
+    payload: Option<&Value>,
+) -> Vec<String> {
+    app.try_state::<StateHub>()
+        .map(|hub| hub.recipients(gateway_id, event, payload))
+        .unwrap_or_default()
+}
+
+/// A window was destroyed; drop its subscription.
+pub fn window_destroyed(app: &AppHandle, window: &str) {
+    if app.try_state::<StateHub>().is_some() {
+        unsubscribe(app, window);
+    }
+}
+
+/// An event of the main window's connection, as pop-outs receive it; the
+/// shape of a backend session's `gateway://event`.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct RelayedEvent<'a> {
+    gateway_id: &'a str,
+    event: &'a str,
+    payload: &'a Option<Value>,
+    seq: Option<u64>,
+}
+
+/// A pop-out's request, handed to the main window.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct RelayedRequest<'a> {
+    id: &'a str,
+    method: &'a str,
+    params: &'a Option<Value>,
+    priority: Priority,
+}
+
+// ---- Tauri Commands -------------------------------------------------------
+
+/// Set the calling window's event filters.
+#[tauri::command]
+pub fn hub_subscribe(app: AppHandle, window: tauri::WebviewWindow, filters: Vec<EventFilter>) {
+    subscribe(&app, window.label(), filters);
+}
+
+/// The gateway the calling window follows; it drops events from any other.
+#[tauri::command]
+pub fn hub_gateway(window: tauri::WebviewWindow, hub: tauri::State<'_, StateHub>) -> String {
+    hub.gateway_of(window.label())
+}
+
+/// Whether the main window should publish its connection's events.
+#[tauri::command]
+pub fn hub_relaying(hub: tauri::State<'_, StateHub>) -> bool {
+    hub.relaying()
+}
+
+/// Publish an event of the main window's connection to the pop-outs
+/// subscribed to it.
+#[tauri::command]
+pub fn hub_publish(
+    app: AppHandle,
+    window: tauri::WebviewWindow,
+    hub: tauri::State<'_, StateHub>,
+    event: String,
+    payload: Option<Value>,
+    seq: Option<u64>,
+) -> Result<(), String> {
+    if window.label() != MAIN_WINDOW {
+        return Err("Only the main window relays its connection".to_string());
+    }
+    let envelope = RelayedEvent {
+        gateway_id: MAIN_CONNECTION_ID,
+        event: &event,
+        payload: &payload,
+        seq,
+    };
+    for label in hub.recipients(MAIN_CONNECTION_ID, &event, payload.as_ref()) {
+        if label != MAIN_WINDOW {
+            let _ = app.emit_to(label.as_str(), EVENT_GATEWAY_EVENT, &envelope);
+        }
+    }
+    Ok(())
+}
+
+/// Send a request for the calling window: on the backend gateway session
+/// its filters name, or on the main window's connection, which is asked
+/// through `hub://request` and answers through `hub_respond`.
+#[tauri::command]
+pub async fn hub_request(
+    app: AppHandle,
+    window: tauri::WebviewWindow,
+    method: String,
+    params: Option<Value>,
+    priority: Option<Priority>,
+) -> Result<Value, GatewayError> {
+    let priority = priority.unwrap_or_default();
+    let hub = app.state::<StateHub>();
+    let gateway_id = hub.gateway_of(window.label());
+    if gateway_id != MAIN_CONNECTION_ID {
+        let session = app
+            .state::<GatewayManager>()
+            .session(&gateway_id)
+            .ok_or_else(|| {
+                GatewayError::local(
+                    "UNKNOWN_GATEWAY",
+                    format!("Unknown gateway: {}", gateway_id),
+                )
+            })?;
+        return session.request(&method, params, priority).await;
+    }
+
+    let id = protocol::generate_request_id();
+    let (reply, answer) = oneshot::channel();
+    hub.lock_relayed().insert(id.clone(), reply);
+    let request = RelayedRequest {
+        id: &id,
+        method: &method,
+        params: &params,
+        priority,
+    };
+    if let Err(e) = app.emit_to(MAIN_WINDOW, EVENT_HUB_REQUEST, &request) {
+        hub.lock_relayed().remove(&id);
+        return Err(GatewayError::local("RELAY_FAILED", e.to_string()));
+    }
+    match tokio::time::timeout(RELAY_TIMEOUT, answer).await {
+        Ok(Ok(result)) => result,
+        Ok(Err(_)) => Err(GatewayError::local(
+            "RELAY_FAILED",
+            "The main window dropped the request",
+        )),
+        Err(_) => {
+            hub.lock_relayed().remove(&id);
+            Err(GatewayError::local(
+                "TIMEOUT",
+                format!(
+                    "The main window did not answer {} within {} s",
+                    method,
+                    RELAY_TIMEOUT.as_secs()
+                ),
+            ))
+        }
+    }
+}
+
+/// The main window's answer to a request relayed by `hub_request`.
+#[tauri::command]
+pub fn hub_respond(
+    window: tauri::WebviewWindow,
+    hub: tauri::State<'_, StateHub>,
+    id: String,
+    result: Option<Value>,
+    error: Option<GatewayError>,
+) -> Result<(), String> {
+    if window.label() != MAIN_WINDOW {
+        return Err("Only the main window answers relayed requests".to_string());
+    }
+    if let Some(reply) = hub.lock_relayed().remove(&id) {
+        let _ = reply.send(match error {
+            Some(error) => Err(error),
+            None => Ok(result.unwrap_or(Value::Null)),
+        });
+    }
+    Ok(())
+}
+
+// ---- Tests ----------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn session(gateway_id: Option<&str>, session_key: &str) -> EventFilter {
+        EventFilter::Session {
+            gateway_id: gateway_id.map(str::to_string),
+            session_key: session_key.to_string(),
+        }
+    }
+
+    #[test]
+    fn session_filter_matches_its_session_on_its_gateway() {
+        let filter = session(Some("home"), "agent:main");
+        let payload = json!({ "sessionKey": "agent:main" });
+        assert!(filter.matches("home", "chat", Some(&payload)));
+        assert!(filter.matches("home", "agent", Some(&payload)));
+        assert!(!filter.matches("work", "chat", Some(&payload)));
+        assert!(!filter.matches("home", "presence", Some(&payload)));
+        let other = json!({ "sessionKey": "agent:other" });
+        assert!(!filter.matches("home", "chat", Some(&other)));
+    }
+
+    #[test]
+    fn recipients_follow_filters() {
+        let hub = StateHub::default();
+        hub.subscribe(MAIN_WINDOW, vec![EventFilter::All]);
+        hub.subscribe(
+            "logs",
+            vec![EventFilter::Logs {
+                gateway_id: Some("home".to_string()),
+            }],
+        );
+        let mut labels = hub.recipients("home", "log", None);
+        labels.sort();
+        assert_eq!(labels, ["logs", "main"]);
+        assert_eq!(hub.recipients("work", "log", None), ["main"]);
+    }
+
+    #[test]
+    fn two_pop_outs_hear_only_their_own_session() {
+        let hub = StateHub::default();
+        hub.subscribe(MAIN_WINDOW, vec![EventFilter::All]);
+        hub.subscribe("popout-a", vec![session(Some("home"), "agent:main")]);
+        hub.subscribe("popout-b", vec![session(Some("work"), "agent:main")]);
+        let payload = json!({ "sessionKey": "agent:main" });
+
+        let mut home = hub.recipients("home", "chat", Some(&payload));
+        home.sort();
+        assert_eq!(home, ["main", "popout-a"]);
+        let mut work = hub.recipients("work", "chat", Some(&payload));
+        work.sort();
+        assert_eq!(work, ["main", "popout-b"]);
+    }
+
+    #[test]
+    fn relaying_only_for_pop_outs_on_the_main_connection() {
+        let hub = StateHub::default();
+        hub.subscribe(MAIN_WINDOW, vec![EventFilter::All]);
+        assert!(!hub.relaying());
+
+        hub.subscribe("chat", vec![session(Some("home"), "agent:main")]);
+        assert!(!hub.relaying());
+        assert_eq!(hub.gateway_of("chat"), "home");
+
+        hub.subscribe(
+            "approvals",
+            vec![EventFilter::Approvals {
+                gateway_id: Some(MAIN_CONNECTION_ID.to_string()),
+            }],
+        );
+        assert!(hub.relaying());
+        assert_eq!(hub.gateway_of("approvals"), MAIN_CONNECTION_ID);
+
+        hub.unsubscribe("approvals");
+        assert!(!hub.relaying());
+    }
+}
@@ -8,6 +8,8 @@
 //
 // Device tokens are stored with a unique key scoped to the gateway URL and
 // device ID to support multiple device registrations across different gateways.
+// TLS certificate pins and shared passwords for a gateway are stored in the
+// same service, keyed by the gateway URL.
 
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
@@ -62,23 +64,61 @@ pub struct StoredDeviceToken {
     pub scopes: Vec<String>,
 }
 
+// ---- Certificate Pins -----------------------------------------------------
+
+/// TLS certificate pins for one gateway, stored next to its device token.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StoredCertificatePins {
+    /// Gateway URL the pins apply to
+    pub gateway_url: String,
+
+    /// Base64 SHA-256 digests of the pinned SubjectPublicKeyInfo
+    pub spki_sha256: Vec<String>,
+
+    /// Unix timestamp (ms) when the pins were last changed
+    pub pinned_at_ms: i64,
+}
+
 // ---- Keychain Storage Key -------------------------------------------------
 
 const KEYCHAIN_SERVICE_NAME: &str = "com.openclaw.the-fireplace";
 const KEYCHAIN_ACCOUNT_PREFIX: &str = "device-token";
+#[cfg(any(target_os = "macos", target_os = "ios"))]
+const KEYCHAIN_PIN_PREFIX: &str = "tls-pin";
+#[cfg(any(target_os = "macos", target_os = "ios"))]
+const KEYCHAIN_PASSWORD_PREFIX: &str = "gateway-password";
+
+/// Normalize a gateway URL by removing protocol and trailing slashes, so
+/// `ws://host:port/` and `wss://host:port` map to the same token.
+pub fn normalize_gateway_url(gateway_url: &str) -> &str {
+    gateway_url
+        .trim_start_matches("ws://")
+        .trim_start_matches("wss://")
+        .trim_end_matches('/')
+}
 
 /// Build the keychain account name for a given device ID and gateway URL.
 /// Format: device-token:{device_id}:{normalized_gateway_url}
 fn build_keychain_key(device_id: &str, gateway_url: &str) -> String {
-    // Normalize the gateway URL by removing protocol and trailing slashes
-    let normalized = gateway_url
-        .trim_start_matches("ws://")
-        .trim_start_matches("wss://")
-        .trim_end_matches('/');
+    let normalized = normalize_gateway_url(gateway_url);
 
     format!("{}:{}:{}", KEYCHAIN_ACCOUNT_PREFIX, device_id, normalized)
 }
 
+/// Build the keychain account name for a gateway's certificate pins.
+/// Format: tls-pin:{normalized_gateway_url}
+#[cfg(any(target_os = "macos", target_os = "ios"))]
+fn build_pin_key(gateway_url: &str) -> String {
+    format!("{}:{}", KEYCHAIN_PIN_PREFIX, normalize_gateway_url(gateway_url))
+}
+
+/// Build the keychain account name for a gateway's shared password.
+/// Format: gateway-password:{normalized_gateway_url}
+#[cfg(any(target_os = "macos", target_os = "ios"))]
+fn build_password_key(gateway_url: &str) -> String {
+    format!("{}:{}", KEYCHAIN_PASSWORD_PREFIX, normalize_gateway_url(gateway_url))
+}
+
 // ---- Platform-Specific Implementations ------------------------------------
 
 #[cfg(any(target_os = "macos", target_os = "ios"))]
@@ -137,6 +177,64 @@ mod platform {
         // but that requires additional FFI work beyond the security-framework crate.
         Ok(vec![])
     }
+
+    pub fn store_pins_impl(pins: &StoredCertificatePins) -> Result<(), KeychainError> {
+        let key = build_pin_key(&pins.gateway_url);
+        let value = serde_json::to_string(pins)
+            .map_err(|e| KeychainError::InvalidData(format!("Failed to serialize pins: {}", e)))?;
+
+        set_generic_password(KEYCHAIN_SERVICE_NAME, &key, value.as_bytes())
+            .map_err(|e| KeychainError::AccessDenied(format!("Failed to store pins: {}", e)))?;
+
+        Ok(())
+    }
+
+    pub fn retrieve_pins_impl(gateway_url: &str) -> Result<StoredCertificatePins, KeychainError> {
+        let key = build_pin_key(gateway_url);
+
+        let data = get_generic_password(KEYCHAIN_SERVICE_NAME, &key)
+            .map_err(|_| KeychainError::NotFound)?;
+
+        serde_json::from_slice(&data)
+            .map_err(|e| KeychainError::InvalidData(format!("Failed to parse pins: {}", e)))
+    }
+
+    pub fn delete_pins_impl(gateway_url: &str) -> Result<(), KeychainError> {
+        let key = build_pin_key(gateway_url);
+
+        delete_generic_password(KEYCHAIN_SERVICE_NAME, &key)
+            .map_err(|_| KeychainError::NotFound)?;
+
+        Ok(())
+    }
+
+    pub fn store_password_impl(gateway_url: &str, password: &str) -> Result<(), KeychainError> {
+        let key = build_password_key(gateway_url);
+
+        set_generic_password(KEYCHAIN_SERVICE_NAME, &key, password.as_bytes())
+            .map_err(|e| KeychainError::AccessDenied(format!("Failed to store password: {}", e)))?;
+
+        Ok(())
+    }
+
+    pub fn retrieve_password_impl(gateway_url: &str) -> Result<String, KeychainError> {
+        let key = build_password_key(gateway_url);
+
+        let data = get_generic_password(KEYCHAIN_SERVICE_NAME, &key)
+            .map_err(|_| KeychainError::NotFound)?;
+
+        String::from_utf8(data)
+            .map_err(|e| KeychainError::InvalidData(format!("Failed to parse password: {}", e)))
+    }
+
+    pub fn delete_password_impl(gateway_url: &str) -> Result<(), KeychainError> {
+        let key = build_password_key(gateway_url);
+
+        delete_generic_password(KEYCHAIN_SERVICE_NAME, &key)
+            .map_err(|_| KeychainError::NotFound)?;
+
+        Ok(())
+    }
 }
 
 #[cfg(not(any(target_os = "macos", target_os = "ios")))]
@@ -168,10 +266,40 @@ mod platform {
     pub fn list_tokens_impl() -> Result<Vec<StoredDeviceToken>, KeychainError> {
         Err(KeychainError::UnsupportedPlatform)
     }
+
+    pub fn store_pins_impl(_pins: &StoredCertificatePins) -> Result<(), KeychainError> {
+        Err(KeychainError::UnsupportedPlatform)
+    }
+
+    pub fn retrieve_pins_impl(_gateway_url: &str) -> Result<StoredCertificatePins, KeychainError> {
+        Err(KeychainError::UnsupportedPlatform)
+    }
+
+    pub fn delete_pins_impl(_gateway_url: &str) -> Result<(), KeychainError> {
+        Err(KeychainError::UnsupportedPlatform)
+    }
+
+    pub fn store_password_impl(_gateway_url: &str, _password: &str) -> Result<(), KeychainError> {
+        Err(KeychainError::UnsupportedPlatform)
+    }
+
+    pub fn retrieve_password_impl(_gateway_url: &str) -> Result<String, KeychainError> {
+        Err(KeychainError::UnsupportedPlatform)
+    }
+
+    pub fn delete_password_impl(_gateway_url: &str) -> Result<(), KeychainError> {
+        Err(KeychainError::UnsupportedPlatform)
+    }
 }
 
 // ---- Public API -----------------------------------------------------------
 
+/// Whether this platform has a keychain; every call below fails with
+/// `UnsupportedPlatform` where it does not.
+pub fn is_supported() -> bool {
+    cfg!(any(target_os = "macos", target_os = "ios"))
+}
+
 /// Store a device token in the platform keychain.
 pub fn store_token(
     device_id: &str,
@@ -202,6 +330,36 @@ pub fn list_tokens() -> Result<Vec<StoredDeviceToken>, KeychainError> {
     platform::list_tokens_impl()
 }
 
+/// Store the certificate pins for a gateway.
+pub fn store_pins(pins: &StoredCertificatePins) -> Result<(), KeychainError> {
+    platform::store_pins_impl(pins)
+}
+
+/// Retrieve the certificate pins for a gateway.
+pub fn retrieve_pins(gateway_url: &str) -> Result<StoredCertificatePins, KeychainError> {
+    platform::retrieve_pins_impl(gateway_url)
+}
+
+/// Delete the certificate pins for a gateway.
+pub fn delete_pins(gateway_url: &str) -> Result<(), KeychainError> {
+    platform::delete_pins_impl(gateway_url)
+}
+
+/// Store the shared password for a gateway using password auth.
+pub fn store_password(gateway_url: &str, password: &str) -> Result<(), KeychainError> {
+    platform::store_password_impl(gateway_url, password)
+}
+
+/// Retrieve the shared password for a gateway.
+pub fn retrieve_password(gateway_url: &str) -> Result<String, KeychainError> {
+    platform::retrieve_password_impl(gateway_url)
+}
+
+/// Delete the shared password for a gateway.
+pub fn delete_password(gateway_url: &str) -> Result<(), KeychainError> {
+    platform::delete_password_impl(gateway_url)
+}
+
 // ---- Tauri Commands -------------------------------------------------------
 
 #[tauri::command]
@@ -1,10 +1,18 @@
 // Prevents additional console window on Windows in release builds
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
+mod deep_link;
+mod gateway;
+mod hub;
 mod keychain;
+mod navigation;
 mod notifications;
-#[cfg(target_os = "macos")]
+#[cfg(desktop)]
+mod shortcuts;
+#[cfg(desktop)]
 mod tray;
+#[cfg(desktop)]
+mod windows;
 
 use tauri::Manager;
 
@@ -144,9 +152,31 @@ fn get_device_id() -> Result<String, String> {
 
 // ---------------------------------------------------------------------------
 
+/// Dev builds open the inspector only when `FIREPLACE_DEVTOOLS` is set,
+/// since it steals focus and crowds the window on every launch.
+#[cfg(debug_assertions)]
+fn open_devtools_if_requested(window: &tauri::WebviewWindow) {
+    if std::env::var_os("FIREPLACE_DEVTOOLS").is_some_and(|v| !v.is_empty() && v != "0") {
+        window.open_devtools();
+    }
+}
+
+// ---------------------------------------------------------------------------
+
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
-    tauri::Builder::default()
+    let builder = tauri::Builder::default();
+
+    // A second launch (how Windows and Linux deliver fireplace:// links)
+    // hands its arguments to this instance and exits; registered first so
+    // it runs before anything else starts
+    #[cfg(desktop)]
+    let builder = builder.plugin(tauri_plugin_single_instance::init(|app, args, _cwd| {
+        navigation::show_main_window(app);
+        deep_link::open_from_args(app, args);
+    }));
+
+    builder
         .plugin(tauri_plugin_shell::init())
         .plugin(tauri_plugin_os::init())
         .plugin(tauri_plugin_notification::init())
@@ -155,10 +185,31 @@ pub fn run() {
         .plugin(tauri_plugin_global_shortcut::Builder::new().build())
         .plugin(tauri_plugin_dialog::init())
         .plugin(tauri_plugin_fs::init())
+        .manage(gateway::scheduler::RequestScheduler::default())
+        .manage(gateway::manager::GatewayManager::default())
+        .manage(notifications::manager::NotificationManager::default())
+        .manage(notifications::history::NotificationHistory::default())
+        .manage(navigation::NavigationQueue::default())
+        .manage(hub::StateHub::default())
         .invoke_handler(tauri::generate_handler![
             greet,
             get_platform,
+            navigation::navigation_ready,
+            navigation::app_navigate,
+            hub::hub_subscribe,
+            hub::hub_gateway,
+            hub::hub_relaying,
+            hub::hub_publish,
+            hub::hub_request,
+            hub::hub_respond,
             notifications::send_notification,
+            notifications::rules::notification_rules,
+            notifications::rules::notification_set_rules,
+            notifications::rules::notification_flush_digest,
+            notifications::history::notification_history,
+            notifications::history::notification_unread_count,
+            notifications::history::notification_mark_read,
+            notifications::history::notification_clear,
             keychain::keychain_store_token,
             keychain::keychain_retrieve_token,
             keychain::keychain_delete_token,
@@ -166,24 +217,82 @@ pub fn run() {
             sign_payload,
             get_device_public_key,
             get_device_id,
-            #[cfg(target_os = "macos")]
+            gateway::scheduler::gateway_acquire_slot,
+            gateway::scheduler::gateway_release_slot,
+            gateway::scheduler::gateway_report_backoff,
+            gateway::scheduler::gateway_scheduler_metrics,
+            gateway::manager::gateway_list_sessions,
+            gateway::manager::gateway_save_profile,
+            gateway::manager::gateway_remove_profile,
+            gateway::manager::gateway_connect,
+            gateway::manager::gateway_disconnect,
+            gateway::manager::gateway_set_active,
+            gateway::manager::gateway_forget_device_token,
+            gateway::manager::gateway_request,
+            gateway::chunks::gateway_request_chunked,
+            gateway::manager::gateway_pending_approvals,
+            gateway::manager::gateway_session_scheduler_metrics,
+            gateway::manager::gateway_certificate_pins,
+            gateway::manager::gateway_trust_certificate,
+            gateway::manager::gateway_clear_certificate_pins,
+            gateway::manager::gateway_set_password,
+            gateway::discovery::gateway_discover,
+            gateway::diagnostics::diagnose_gateway,
+            gateway::tailscale::gateway_tailscale_assess,
+            gateway::metrics::gateway_metrics,
+            #[cfg(desktop)]
             tray::update_tray_status,
+            #[cfg(desktop)]
+            shortcuts::shortcut_bindings,
+            #[cfg(desktop)]
+            shortcuts::shortcut_set_bindings,
+            #[cfg(desktop)]
+            windows::window_pop_out,
         ])
+        .on_page_load(|webview, payload| {
+            if webview.label() == "main" && payload.event() == tauri::webview::PageLoadEvent::Started {
+                navigation::page_loading(webview.app_handle());
+            }
+        })
+        .on_window_event(|window, event| {
+            if let tauri::WindowEvent::Destroyed = event {
+                hub::window_destroyed(window.app_handle(), window.label());
+            }
+        })
         .setup(|app| {
-            // System tray — macOS only
-            #[cfg(target_os = "macos")]
+            // Event routing for the main window (pop-outs subscribe as they open)
+            hub::setup(app.handle());
+
+            // System tray and global shortcuts — desktop only
+            #[cfg(desktop)]
             {
                 tray::setup_tray(app.handle())?;
+                app.manage(shortcuts::ShortcutManager::default());
+                shortcuts::restore(app.handle());
             }
 
+            // Reconnect backend gateway sessions saved with autoConnect
+            gateway::manager::restore(app.handle());
+
+            // A fireplace:// link the app was launched with (after the
+            // gateways, which connect links look up)
+            deep_link::open_from_args(app.handle(), std::env::args());
+
+            // Notification rules (quiet hours, focus) and the digest timer
+            notifications::rules::restore(app.handle());
+            notifications::manager::spawn_digest_task(app.handle());
+
+            // Notification inbox (after the tray, which shows its unread count)
+            notifications::history::restore(app.handle());
+
             #[cfg(debug_assertions)]
-            {
-                let window = app.get_webview_window("main").unwrap();
-                window.open_devtools();
+            if let Some(window) = app.get_webview_window("main") {
+                open_devtools_if_requested(&window);
             }
 
             Ok(())
         })
-        .run(tauri::generate_context!())
-        .expect("error while running tauri application");
+        .build(tauri::generate_context!())
+        .expect("error while running tauri application")
+        .run(deep_link::on_run_event);
 }
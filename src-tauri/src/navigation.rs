@@ -0,0 +1,481 @@
+// ---------------------------------------------------------------------------
+// App Navigation
+// ---------------------------------------------------------------------------
+//
+// Every view of the app as a typed `Route`, and the one way the backend
+// moves the main window to it: a `navigate` event carrying the route and
+// its path. The tray, notifications, global shortcuts and deep links all go
+// through `navigate`; nothing formats JavaScript.
+//
+// Routes are validated before they are sent, and routes parsed from outside
+// (deep links) are checked strictly: unknown views, unknown or repeated
+// parameters and malformed IDs are rejected.
+//
+// Navigation is queued until the webview reports (`navigation_ready`) that
+// its listener is attached, so a notification clicked or a link opened
+// during startup or a reload is not lost.
+
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::sync::{Mutex, MutexGuard};
+use tauri::{AppHandle, Emitter, Manager};
+
+/// Event emitted to the main window with a `NavigateEvent`.
+pub const EVENT_NAVIGATE: &str = "app://navigate";
+
+/// Label of the window navigation targets.
+const MAIN_WINDOW: &str = "main";
+
+/// Navigations held while the webview loads; the oldest are dropped first.
+const QUEUE_CAPACITY: usize = 16;
+
+/// Longest accepted route parameter (session keys, IDs, cursors).
+const MAX_PARAM_LEN: usize = 256;
+
+// ---- Error Types ----------------------------------------------------------
+
+#[derive(Debug, thiserror::Error)]
+pub enum RouteError {
+    #[error("Unknown view: {0}")]
+    UnknownView(String),
+
+    #[error("Unknown parameter for {view}: {param}")]
+    UnknownParam { view: String, param: String },
+
+    #[error("Parameter {0} given more than once")]
+    RepeatedParam(String),
+
+    #[error("Invalid {param}: {reason}")]
+    InvalidParam { param: String, reason: String },
+}
+
+// ---- Routes ---------------------------------------------------------------
+
+/// A view of the app, with the item it should show where it has one.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "view", rename_all = "camelCase")]
+pub enum Route {
+    /// The chat view, optionally switched to a session.
+    #[serde(rename_all = "camelCase")]
+    Chat {
+        #[serde(default)]
+        session_key: Option<String>,
+        #[serde(default)]
+        gateway_id: Option<String>,
+    },
+    Sessions {
+        #[serde(default)]
+        key: Option<String>,
+    },
+    Channels,
+    Agents,
+    Config,
+    #[serde(rename_all = "camelCase")]
+    Approvals {
+        #[serde(default)]
+        id: Option<String>,
+        #[serde(default)]
+        gateway_id: Option<String>,
+    },
+    #[serde(rename_all = "camelCase")]
+    Cron {
+        #[serde(default)]
+        job_id: Option<String>,
+    },
+    Skills,
+    Devices,
+    /// The log view, optionally at a position in the gateway log stream.
+    Logs {
+        #[serde(default)]
+        cursor: Option<String>,
+    },
+    Models,
+    Usage,
+    More,
+    DemonChat,
+    DemonHealth,
+    DemonTasks,
+    DemonObservability,
+    DemonMemory,
+}
+
+impl Route {
+    /// Frontend path and its query parameters (name, value).
+    fn parts(&self) -> (&'static str, Vec<(&'static str, &str)>) {
+        fn params<'a>(
+            pairs: &[(&'static str, &'a Option<String>)],
+        ) -> Vec<(&'static str, &'a str)> {
+            pairs
+                .iter()
+                .filter_map(|(name, value)| value.as_deref().map(|v| (*name, v)))
+                .collect()
+        }
+        match self {
+            Route::Chat {
+                session_key,
+                gateway_id,
+            } => (
+                "/",
+                params(&[("session", session_key), ("gateway", gateway_id)]),
+            ),
+            Route::Sessions { key } => ("/sessions", params(&[("key", key)])),
+            Route::Channels => ("/channels", Vec::new()),
+            Route::Agents => ("/agents", Vec::new()),
+            Route::Config => ("/config", Vec::new()),
+            Route::Approvals { id, gateway_id } => {
+                ("/approvals", params(&[("id", id), ("gateway", gateway_id)]))
+            }
+            Route::Cron { job_id } => ("/cron", params(&[("job", job_id)])),
+            Route::Skills => ("/skills", Vec::new()),
+            Route::Devices => ("/devices", Vec::new()),
+            Route::Logs { cursor } => ("/logs", params(&[("cursor", cursor)])),
+            Route::Models => ("/models", Vec::new()),
+            Route::Usage => ("/usage", Vec::new()),
+            Route::More => ("/more", Vec::new()),
+            Route::DemonChat => ("/demon-chat", Vec::new()),
+            Route::DemonHealth => ("/demon-health", Vec::new()),
+            Route::DemonTasks => ("/demon-tasks", Vec::new()),
+            Route::DemonObservability => ("/demon-observability", Vec::new()),
+            Route::DemonMemory => ("/demon-memory", Vec::new()),
+        }
+    }
+
+    /// Frontend path with the item in the query string.
+    pub fn path(&self) -> String {
+        let (path, params) = self.parts();
+        if params.is_empty() {
+            return path.to_string();
+        }
+        let query = url::form_urlencoded::Serializer::new(String::new())
+            .extend_pairs(params)
+            .finish();
+        format!("{}?{}", path, query)
+    }
+
+    /// Check every parameter the route carries.
+    pub fn validate(&self) -> Result<(), RouteError> {
+        let (_, params) = self.parts();
+        for (name, value) in params {
+            validate_param(name, value)?;
+        }
+        Ok(())
+    }
+
+    /// Parse and validate a frontend path such as `/sessions?key=main`.
+    pub fn parse(path: &str) -> Result<Self, RouteError> {
+        let (path, query) = path.split_once('?').unwrap_or((path, ""));
+        let mut params: Vec<(String, String)> = Vec::new();
+        for (name, value) in url::form_urlencoded::parse(query.as_bytes()) {
+            if params.iter().any(|(n, _)| *n == name) {
+                return Err(RouteError::RepeatedParam(name.into_owned()));
+            }
+            params.push((name.into_owned(), value.into_owned()));
+        }
+        let mut take = |name: &str| {
+            params
+                .iter()
+                .position(|(n, _)| n == name)
+                .map(|i| params.remove(i).1)
+        };
+
+        let route = match path.trim_end_matches('/') {
+            "" => Route::Chat {
+                session_key: take("session"),
+                gateway_id: take("gateway"),
+            },
+            "/sessions" => Route::Sessions { key: take("key") },
+            "/channels" => Route::Channels,
+            "/agents" => Route::Agents,
+            "/config" => Route::Config,
+            "/approvals" => Route::Approvals {
+                id: take("id"),
+                gateway_id: take("gateway"),
+            },
+            "/cron" => Route::Cron {
+                job_id: take("job"),
+            },
+            "/skills" => Route::Skills,
+            "/devices" => Route::Devices,
+            "/logs" => Route::Logs {
+                cursor: take("cursor"),
+            },
+            "/models" => Route::Models,
+            "/usage" => Route::Usage,
+            "/more" => Route::More,
+            "/demon-chat" => Route::DemonChat,
+            "/demon-health" => Route::DemonHealth,
+            "/demon-tasks" => Route::DemonTasks,
+            "/demon-observability" => Route::DemonObservability,
+            "/demon-memory" => Route::DemonMemory,
+            other => return Err(RouteError::UnknownView(other.to_string())),
+        };
+        if let Some((param, _)) = params.into_iter().next() {
+            return Err(RouteError::UnknownParam {
+                view: route.parts().0.to_string(),
+                param,
+            });
+        }
+        route.validate()?;
+        Ok(route)
+    }
+}
+
+/// Route parameters are opaque IDs: non-empty, bounded, and free of
+/// whitespace and control characters.
+fn validate_param(param: &str, value: &str) -> Result<(), RouteError> {
+    let invalid = |reason: &str| RouteError::InvalidParam {
+        param: param.to_string(),
+        reason: reason.to_string(),
+    };
+    if value.is_empty() {
+        return Err(invalid("must not be empty"));
+    }
+    if value.len() > MAX_PARAM_LEN {
+        return Err(invalid(&format!("longer than {} bytes", MAX_PARAM_LEN)));
+    }
+    if value.chars().any(|c| c.is_control() || c.is_whitespace()) {
+        return Err(invalid("contains whitespace or control characters"));
+    }
+    Ok(())
+}
+
+/// Payload of `EVENT_NAVIGATE`.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NavigateEvent {
+    /// Frontend path to navigate to.
+    pub path: String,
+    pub route: Route,
+}
+
+// ---- Queue ----------------------------------------------------------------
+
+#[derive(Default)]
+struct QueueInner {
+    /// Whether the webview is listening for navigate events.
+    ready: bool,
+    pending: VecDeque<Route>,
+}
+
+/// Holds navigations until the main webview is ready for them.
+#[derive(Default)]
+pub struct NavigationQueue {
+    inner: Mutex<QueueInner>,
+}
+
+impl NavigationQueue {
+    fn lock(&self) -> MutexGuard<'_, QueueInner> {
+        self.inner.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Hand back `route` to send now if the webview is ready; otherwise
+    /// hold it.
+    fn offer(&self, route: Route) -> Option<Route> {
+        let mut inner = self.lock();
+        if inner.ready {
+            return Some(route);
+        }
+        if inner.pending.len() == QUEUE_CAPACITY {
+            inner.pending.pop_front();
+        }
+        inner.pending.push_back(route);
+        None
+    }
+
+    /// The webview is listening; take everything held, oldest first.
+    fn ready(&self) -> Vec<Route> {
+        let mut inner = self.lock();
+        inner.ready = true;
+        inner.pending.drain(..).collect()
+    }
+
+    fn loading(&self) {
+        self.lock().ready = false;
+    }
+}
+
+fn emit(app: &AppHandle, route: Route) {
+    let _ = app.emit_to(
+        MAIN_WINDOW,
+        EVENT_NAVIGATE,
+        NavigateEvent {
+            path: route.path(),
+            route,
+        },
+    );
+}
+
+/// Show, unminimize and focus the main window.
+pub fn show_main_window(app: &AppHandle) {
+    if let Some(window) = app.get_webview_window(MAIN_WINDOW) {
+        let _ = window.show();
+        let _ = window.unminimize();
+        let _ = window.set_focus();
+    }
+}
+
+/// Show the main window at `route`, or queue the route until the webview
+/// is ready.
+pub fn navigate(app: &AppHandle, route: Route) -> Result<(), RouteError> {
+    route.validate()?;
+    show_main_window(app);
+    if let Some(route) = app.state::<NavigationQueue>().offer(route) {
+        emit(app, route);
+    }
+    Ok(())
+}
+
+/// The main webview started loading a page; hold navigation until it is
+/// ready again.
+pub fn page_loading(app: &AppHandle) {
+    if let Some(queue) = app.try_state::<NavigationQueue>() {
+        queue.loading();
+    }
+}
+
+// ---- Tauri Commands -------------------------------------------------------
+
+/// Navigate the main window to a frontend path, validated as a `Route`.
+/// Lets other windows and in-app callers use the same path as the backend.
+#[tauri::command]
+pub fn app_navigate(app: AppHandle, path: String) -> Result<(), String> {
+    let route = Route::parse(&path).map_err(|e| e.to_string())?;
+    navigate(&app, route).map_err(|e| e.to_string())
+}
+
+/// Called by the webview once it listens for navigate events; delivers
+/// everything queued in the meantime.
+#[tauri::command]
+pub fn navigation_ready(app: AppHandle, queue: tauri::State<'_, NavigationQueue>) {
+    for route in queue.ready() {
+        emit(&app, route);
+    }
+}
+
+// ---- Tests ----------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn session(key: &str) -> Route {
+        Route::Sessions {
+            key: Some(key.to_string()),
+        }
+    }
+
+    #[test]
+    fn paths_parse_back_into_their_routes() {
+        let routes = [
+            Route::Chat {
+                session_key: Some("agent:main:main".to_string()),
+                gateway_id: Some("home".to_string()),
+            },
+            Route::Chat {
+                session_key: None,
+                gateway_id: None,
+            },
+            session("a/b&c=d"),
+            Route::Approvals {
+                id: Some("req-1".to_string()),
+                gateway_id: None,
+            },
+            Route::Cron {
+                job_id: Some("nightly".to_string()),
+            },
+            Route::Logs { cursor: None },
+            Route::DemonObservability,
+        ];
+        for route in routes {
+            assert_eq!(Route::parse(&route.path()).unwrap(), route);
+        }
+    }
+
+    #[test]
+    fn parse_accepts_trailing_slashes_and_encoded_values() {
+        assert_eq!(Route::parse("/agents/").unwrap(), Route::Agents);
+        assert_eq!(Route::parse("/").unwrap().path(), "/");
+        assert_eq!(
+            Route::parse("/sessions?key=agent%3Amain").unwrap(),
+            session("agent:main")
+        );
+    }
+
+    #[test]
+    fn parse_rejects_unknown_views_and_params() {
+        assert!(matches!(
+            Route::parse("/settings"),
+            Err(RouteError::UnknownView(view)) if view == "/settings"
+        ));
+        assert!(matches!(
+            Route::parse("/agents?id=main"),
+            Err(RouteError::UnknownParam { view, param }) if view == "/agents" && param == "id"
+        ));
+        assert!(matches!(
+            Route::parse("/sessions?key=a&key=b"),
+            Err(RouteError::RepeatedParam(param)) if param == "key"
+        ));
+    }
+
+    #[test]
+    fn parse_rejects_malformed_ids() {
+        let too_long = format!("/sessions?key={}", "k".repeat(MAX_PARAM_LEN + 1));
+        for path in [
+            "/sessions?key=",
+            "/sessions?key=a%20b",
+            "/cron?job=%0A",
+            &too_long,
+        ] {
+            assert!(
+                matches!(Route::parse(path), Err(RouteError::InvalidParam { .. })),
+                "{} was accepted",
+                path
+            );
+        }
+    }
+
+    #[test]
+    fn validate_checks_routes_built_in_code() {
+        assert!(session("main").validate().is_ok());
+        assert!(Route::Skills.validate().is_ok());
+        assert!(session("two words").validate().is_err());
+        assert!(Route::Approvals {
+            id: None,
+            gateway_id: Some(String::new()),
+        }
+        .validate()
+        .is_err());
+    }
+
+    #[test]
+    fn queue_holds_routes_until_the_webview_is_ready() {
+        let queue = NavigationQueue::default();
+        assert_eq!(queue.offer(session("a")), None);
+        assert_eq!(queue.offer(session("b")), None);
+
+        assert_eq!(queue.ready(), vec![session("a"), session("b")]);
+        assert_eq!(queue.offer(session("c")), Some(session("c")));
+        assert!(queue.ready().is_empty());
+
+        // A reload holds routes again
+        queue.loading();
+        assert_eq!(queue.offer(session("d")), None);
+        assert_eq!(queue.ready(), vec![session("d")]);
+    }
+
+    #[test]
+    fn full_queue_drops_the_oldest_route() {
+        let queue = NavigationQueue::default();
+        for n in 0..=QUEUE_CAPACITY {
+            queue.offer(session(&n.to_string()));
+        }
+
+        let held = queue.ready();
+        assert_eq!(held.len(), QUEUE_CAPACITY);
+        assert_eq!(held[0], session("1"));
+        assert_eq!(
+            held[QUEUE_CAPACITY - 1],
+            session(&QUEUE_CAPACITY.to_string())
+        );
+    }
+}
@@ -0,0 +1,505 @@
+// ---------------------------------------------------------------------------
+// Notification Backends
+// ---------------------------------------------------------------------------
+//
+// The OS notification APIs behind one trait, so the rest of the bridge only
+// deals in `NotificationRequest`s and `Interaction`s:
+//
+// - macOS: mac-notification-sys (alerts with an actions dropdown and a
+//   close button). Plain banners go through tauri-plugin-notification.
+// - Linux: freedesktop notifications over D-Bus (notify-rust) with urgency
+//   hints, actions, icons, replacement by ID and close reasons.
+// - Elsewhere: unsupported; callers fall back to the plugin.
+//
+// `RecordingBackend` stands in for the platform in tests: it records every
+// request and answers with scripted interactions.
+
+use super::rules::Urgency;
+use super::NotificationId;
+use std::sync::Arc;
+use std::time::Duration;
+use tauri::AppHandle;
+
+// ---- Requests & Responses -------------------------------------------------
+
+/// A button on a notification.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NotificationAction {
+    /// Reported back in `Interaction::Acted`.
+    pub id: String,
+    pub label: String,
+    /// Taken by the close button where the platform has one (macOS).
+    pub cancel: bool,
+}
+
+/// Everything needed to put one notification on screen.
+#[derive(Debug, Clone, Default)]
+pub struct NotificationRequest {
+    pub title: String,
+    pub body: String,
+    pub urgency: Urgency,
+    /// Icon name or path; the app icon when `None`.
+    pub icon: Option<String>,
+    pub actions: Vec<NotificationAction>,
+    /// Title of the dropdown grouping the actions (macOS).
+    pub actions_label: Option<String>,
+    /// Report a click on the notification body.
+    pub clickable: bool,
+    /// Notification to update in place.
+    pub replaces: Option<NotificationId>,
+    /// How long it stays on screen; the server default when `None`.
+    pub timeout: Option<Duration>,
+}
+
+impl NotificationRequest {
+    pub fn new(title: &str, body: &str, urgency: Urgency) -> Self {
+        Self {
+            title: title.to_string(),
+            body: body.to_string(),
+            urgency,
+            ..Self::default()
+        }
+    }
+
+    fn sound(&self) -> bool {
+        self.urgency == Urgency::Critical
+    }
+}
+
+/// Why a notification left the screen without being acted on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CloseReason {
+    /// Its timeout ran out.
+    Expired,
+    /// The user closed it.
+    Dismissed,
+    /// The app closed or replaced it.
+    Closed,
+    Unknown,
+}
+
+/// How the user responded to a notification.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Interaction {
+    /// An action button, by action ID.
+    Acted(String),
+    Clicked,
+    Closed(CloseReason),
+}
+
+/// What the platforms show about the app sending a notification.
+#[derive(Debug, Clone, Default)]
+pub struct AppInfo {
+    pub name: String,
+    /// Icon name used when a request has none.
+    pub icon: String,
+    /// Bundle identifier.
+    #[cfg(target_os = "macos")]
+    pub identifier: String,
+}
+
+impl AppInfo {
+    pub fn of(app: &AppHandle) -> Self {
+        Self {
+            name: app.package_info().name.clone(),
+            icon: app.package_info().crate_name.to_string(),
+            #[cfg(target_os = "macos")]
+            identifier: app.config().identifier.clone(),
+        }
+    }
+}
+
+// ---- Backend Trait --------------------------------------------------------
+
+pub trait NotificationBackend: Send + Sync {
+    /// Show a notification. Returns its ID where the platform can replace it.
+    fn show(
+        &self,
+        app: &AppInfo,
+        request: &NotificationRequest,
+    ) -> Result<Option<NotificationId>, String>;
+
+    /// Show a notification and block until it is acted on, clicked or
+    /// closed. `on_shown` receives its ID once it is on screen.
+    fn show_and_wait(
+        &self,
+        app: &AppInfo,
+        request: &NotificationRequest,
+        on_shown: &mut dyn FnMut(NotificationId),
+    ) -> Result<Interaction, String>;
+}
+
+/// The backend for the current platform.
+pub fn platform() -> Arc<dyn NotificationBackend> {
+    Arc::new(PlatformBackend)
+}
+
+// ---- macOS ----------------------------------------------------------------
+
+#[cfg(target_os = "macos")]
+struct PlatformBackend;
+
+#[cfg(target_os = "macos")]
+impl PlatformBackend {
+    /// How long a response is waited for when the request has no timeout.
+    const RESPONSE_WAIT: Duration = Duration::from_secs(600);
+}
+
+#[cfg(target_os = "macos")]
+impl NotificationBackend for PlatformBackend {
+    /// Plain notifications use the plugin; delivered ones cannot be replaced.
+    fn show(
+        &self,
+        _app: &AppInfo,
+        _request: &NotificationRequest,
+    ) -> Result<Option<NotificationId>, String> {
+        Err("Replaceable notifications are not supported on macOS".to_string())
+    }
+
+    /// Alert-style notification with the actions in a dropdown and the
+    /// cancel action on the close button. macOS cannot expire a delivered
+    /// notification, so once the timeout passes any later click is ignored.
+    fn show_and_wait(
+        &self,
+        app: &AppInfo,
+        request: &NotificationRequest,
+        _on_shown: &mut dyn FnMut(NotificationId),
+    ) -> Result<Interaction, String> {
+        use mac_notification_sys::{MainButton, Notification, NotificationResponse};
+        use std::sync::mpsc;
+
+        let _ = mac_notification_sys::set_application(&app.identifier);
+
+        let (tx, rx) = mpsc::channel();
+        let thread_request = request.clone();
+        std::thread::spawn(move || {
+            let request = thread_request;
+            let labels: Vec<&str> = request
+                .actions
+                .iter()
+                .filter(|a| !a.cancel)
+                .map(|a| a.label.as_str())
+                .collect();
+            let menu = request.actions_label.as_deref().unwrap_or("Actions");
+            let mut notification = Notification::new();
+            notification
+                .title(&request.title)
+                .message(&request.body)
+                .wait_for_click(request.clickable);
+            match labels.as_slice() {
+                [] => {}
+                [label] => {
+                    notification.main_button(MainButton::SingleAction(label));
+                }
+                labels => {
+                    notification.main_button(MainButton::DropdownActions(menu, labels));
+                }
+            }
+            if let Some(cancel) = request.actions.iter().find(|a| a.cancel) {
+                notification.close_button(&cancel.label);
+            }
+            if request.sound() {
+                notification.default_sound();
+            }
+            let _ = tx.send(notification.send().map_err(|e| e.to_string()));
+        });
+
+        let acted = |label: &str, cancel: bool| {
+            request
+                .actions
+                .iter()
+                .find(|a| a.label == label || (cancel && a.cancel))
+                .map(|a| Interaction::Acted(a.id.clone()))
+                .unwrap_or(Interaction::Closed(CloseReason::Unknown))
+        };
+        match rx.recv_timeout(request.timeout.unwrap_or(Self::RESPONSE_WAIT)) {
+            Ok(Ok(NotificationResponse::ActionButton(label))) => Ok(acted(&label, false)),
+            Ok(Ok(NotificationResponse::CloseButton(label))) => Ok(acted(&label, true)),
+            Ok(Ok(NotificationResponse::Click)) => Ok(Interaction::Clicked),
+            Ok(Ok(_)) => Ok(Interaction::Closed(CloseReason::Dismissed)),
+            Err(_) => Ok(Interaction::Closed(CloseReason::Expired)),
+            Ok(Err(err)) => Err(err),
+        }
+    }
+}
+
+// ---- Linux (freedesktop) --------------------------------------------------
+
+#[cfg(target_os = "linux")]
+struct PlatformBackend;
+
+#[cfg(target_os = "linux")]
+impl PlatformBackend {
+    /// Action ID the notification server sends for a click on the body.
+    const DEFAULT_ACTION: &'static str = "default";
+
+    fn build(app: &AppInfo, request: &NotificationRequest) -> notify_rust::Notification {
+        use notify_rust::{Notification, Timeout};
+
+        let mut notification = Notification::new();
+        notification
+            .appname(&app.name)
+            .summary(&request.title)
+            .body(&request.body)
+            .icon(request.icon.as_deref().unwrap_or(&app.icon))
+            .urgency(match request.urgency {
+                Urgency::Low => notify_rust::Urgency::Low,
+                Urgency::Normal => notify_rust::Urgency::Normal,
+                Urgency::Critical => notify_rust::Urgency::Critical,
+            });
+        if request.sound() {
+            notification.sound_name("message-new-instant");
+        }
+        if request.clickable {
+            notification.action(Self::DEFAULT_ACTION, "Open");
+        }
+        for action in &request.actions {
+            notification.action(&action.id, &action.label);
+        }
+        if let Some(id) = request.replaces {
+            notification.id(id);
+        }
+        if let Some(timeout) = request.timeout {
+            notification.timeout(Timeout::Milliseconds(timeout.as_millis() as u32));
+        }
+        notification
+    }
+
+    /// What the notification server's response means to the bridge.
+    fn interaction(response: &notify_rust::ActionResponse) -> Interaction {
+        use notify_rust::{ActionResponse, CloseReason as Reason};
+
+        match response {
+            ActionResponse::Custom(Self::DEFAULT_ACTION) => Interaction::Clicked,
+            ActionResponse::Custom(action) => Interaction::Acted(action.to_string()),
+            ActionResponse::Closed(reason) => Interaction::Closed(match reason {
+                Reason::Expired => CloseReason::Expired,
+                Reason::Dismissed => CloseReason::Dismissed,
+                Reason::CloseAction => CloseReason::Closed,
+                Reason::Other(_) => CloseReason::Unknown,
+            }),
+        }
+    }
+}
+
+#[cfg(target_os = "linux")]
+impl NotificationBackend for PlatformBackend {
+    fn show(
+        &self,
+        app: &AppInfo,
+        request: &NotificationRequest,
+    ) -> Result<Option<NotificationId>, String> {
+        let handle = Self::build(app, request)
+            .show()
+            .map_err(|e| e.to_string())?;
+        Ok(Some(handle.id()))
+    }
+
+    fn show_and_wait(
+        &self,
+        app: &AppInfo,
+        request: &NotificationRequest,
+        on_shown: &mut dyn FnMut(NotificationId),
+    ) -> Result<Interaction, String> {
+        let handle = Self::build(app, request)
+            .show()
+            .map_err(|e| e.to_string())?;
+        let id = handle.id();
+        on_shown(id);
+
+        let mut interaction = Interaction::Closed(CloseReason::Unknown);
+        notify_rust::handle_action(id, |response: &notify_rust::ActionResponse| {
+            interaction = Self::interaction(response);
+        });
+        Ok(interaction)
+    }
+}
+
+// ---- Unsupported Platforms ------------------------------------------------
+
+#[cfg(not(any(target_os = "macos", target_os = "linux")))]
+struct PlatformBackend;
+
+#[cfg(not(any(target_os = "macos", target_os = "linux")))]
+impl NotificationBackend for PlatformBackend {
+    fn show(
+        &self,
+        _app: &AppInfo,
+        _request: &NotificationRequest,
+    ) -> Result<Option<NotificationId>, String> {
+        Err("Replaceable notifications are not supported on this platform".to_string())
+    }
+
+    fn show_and_wait(
+        &self,
+        _app: &AppInfo,
+        _request: &NotificationRequest,
+        _on_shown: &mut dyn FnMut(NotificationId),
+    ) -> Result<Interaction, String> {
+        Err("Notification actions are not supported on this platform".to_string())
+    }
+}
+
+// ---- Recording Fake -------------------------------------------------------
+
+/// Backend that records requests instead of showing them. Each waiting
+/// notification takes the next scripted interaction, or expires if none is
+/// queued.
+#[cfg(test)]
+#[derive(Default)]
+pub struct RecordingBackend {
+    requests: std::sync::Mutex<Vec<NotificationRequest>>,
+    responses: std::sync::Mutex<std::collections::VecDeque<Interaction>>,
+    next_id: std::sync::atomic::AtomicU32,
+}
+
+#[cfg(test)]
+impl RecordingBackend {
+    /// Queue the answer for the next waiting notification.
+    pub fn respond(&self, interaction: Interaction) {
+        self.responses
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .push_back(interaction);
+    }
+
+    /// Every request shown so far, oldest first.
+    pub fn requests(&self) -> Vec<NotificationRequest> {
+        self.requests
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone()
+    }
+
+    fn record(&self, request: &NotificationRequest) -> NotificationId {
+        self.requests
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .push(request.clone());
+        match request.replaces {
+            Some(id) => id,
+            None => {
+                self.next_id
+                    .fetch_add(1, std::sync::atomic::Ordering::Relaxed)
+                    + 1
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+impl NotificationBackend for RecordingBackend {
+    fn show(
+        &self,
+        _app: &AppInfo,
+        request: &NotificationRequest,
+    ) -> Result<Option<NotificationId>, String> {
+        Ok(Some(self.record(request)))
+    }
+
+    fn show_and_wait(
+        &self,
+        _app: &AppInfo,
+        request: &NotificationRequest,
+        on_shown: &mut dyn FnMut(NotificationId),
+    ) -> Result<Interaction, String> {
+        on_shown(self.record(request));
+        Ok(self
+            .responses
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .pop_front()
+            .unwrap_or(Interaction::Closed(CloseReason::Expired)))
+    }
+}
+
+// ---- Tests ----------------------------------------------------------------
+
+#[cfg(all(test, target_os = "linux"))]
+mod tests {
+    use super::*;
+    use notify_rust::{ActionResponse, CloseReason as Reason, Hint, Timeout};
+
+    fn app() -> AppInfo {
+        AppInfo {
+            name: "The Fireplace".to_string(),
+            icon: "the-fireplace".to_string(),
+        }
+    }
+
+    #[test]
+    fn freedesktop_request_carries_urgency_icon_and_timeout() {
+        let mut request = NotificationRequest::new("Title", "Body", Urgency::Critical);
+        request.timeout = Some(Duration::from_secs(5));
+        let notification = PlatformBackend::build(&app(), &request);
+
+        assert_eq!(notification.appname, "The Fireplace");
+        assert_eq!(notification.summary, "Title");
+        assert_eq!(notification.body, "Body");
+        assert_eq!(notification.icon, "the-fireplace");
+        assert!(notification
+            .hints
+            .contains(&Hint::Urgency(notify_rust::Urgency::Critical)));
+        // Critical notices play a sound
+        assert!(notification
+            .hints
+            .contains(&Hint::SoundName("message-new-instant".to_string())));
+        assert_eq!(notification.timeout, Timeout::Milliseconds(5000));
+
+        request.urgency = Urgency::Low;
+        request.icon = Some("dialog-error".to_string());
+        let notification = PlatformBackend::build(&app(), &request);
+        assert!(notification
+            .hints
+            .contains(&Hint::Urgency(notify_rust::Urgency::Low)));
+        assert_eq!(notification.icon, "dialog-error");
+    }
+
+    #[test]
+    fn freedesktop_actions_follow_the_click_action() {
+        let mut request = NotificationRequest::new("Title", "Body", Urgency::Normal);
+        request.clickable = true;
+        request.actions = vec![NotificationAction {
+            id: "deny".to_string(),
+            label: "Deny".to_string(),
+            cancel: true,
+        }];
+        let notification = PlatformBackend::build(&app(), &request);
+        // Identifier and label pairs, the body click first
+        assert_eq!(notification.actions, ["default", "Open", "deny", "Deny"]);
+    }
+
+    #[test]
+    fn freedesktop_replacement_reuses_the_id() {
+        let mut request = NotificationRequest::new("Title", "Body", Urgency::Normal);
+        let fresh = format!("{:?}", PlatformBackend::build(&app(), &request));
+        assert!(fresh.contains("id: None"), "{}", fresh);
+
+        request.replaces = Some(42);
+        let replacing = format!("{:?}", PlatformBackend::build(&app(), &request));
+        assert!(replacing.contains("id: Some(42)"), "{}", replacing);
+    }
+
+    #[test]
+    fn freedesktop_responses_become_interactions() {
+        assert_eq!(
+            PlatformBackend::interaction(&ActionResponse::Custom("default")),
+            Interaction::Clicked
+        );
+        assert_eq!(
+            PlatformBackend::interaction(&ActionResponse::Custom("allow-once")),
+            Interaction::Acted("allow-once".to_string())
+        );
+        for (reason, expected) in [
+            (Reason::Expired, CloseReason::Expired),
+            (Reason::Dismissed, CloseReason::Dismissed),
+            (Reason::CloseAction, CloseReason::Closed),
+            (Reason::Other(9), CloseReason::Unknown),
+        ] {
+            assert_eq!(
+                PlatformBackend::interaction(&ActionResponse::Closed(reason)),
+                Interaction::Closed(expected)
+            );
+        }
+    }
+}
@@ -0,0 +1,421 @@
+// ---------------------------------------------------------------------------
+// Notification History (Inbox)
+// ---------------------------------------------------------------------------
+//
+// Every notification submitted to the `NotificationManager` is recorded with
+// its source event, its actions and what became of it (clicked, dismissed,
+// acted on, suppressed by a rule, ...). The list is persisted with
+// tauri-plugin-store so the UI can show an inbox after the banner is gone,
+// and the unread count is mirrored into the tray menu.
+
+use super::manager::{Category, Notice};
+use super::rules::Urgency;
+use super::target::NotificationTarget;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::VecDeque;
+use std::sync::{Mutex, MutexGuard};
+use tauri::{AppHandle, Emitter, Manager};
+use tauri_plugin_store::StoreExt;
+
+const HISTORY_STORE_PATH: &str = "notification-history.json";
+const HISTORY_KEY: &str = "entries";
+
+/// Entries kept; the oldest are dropped first.
+const HISTORY_CAPACITY: usize = 500;
+
+/// Event emitted with the unread count whenever the history changes.
+pub const EVENT_NOTIFICATION_HISTORY: &str = "notifications://history";
+
+// ---- Entries --------------------------------------------------------------
+
+/// What happened to a notification.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum Outcome {
+    /// Shown (alone or in a summary); no interaction reported yet.
+    Delivered,
+    /// Dropped by a rule.
+    Suppressed,
+    /// Held for a digest by a rule.
+    Held,
+    Clicked,
+    Dismissed,
+    /// Its lifetime ran out without interaction.
+    Expired,
+    /// An action button was pressed, or the item was answered from the
+    /// tray or a shortcut.
+    Acted {
+        action: String,
+    },
+    /// The underlying item was resolved elsewhere.
+    Resolved,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HistoryEntry {
+    pub id: String,
+    pub at_ms: i64,
+    pub category: Category,
+    pub thread: String,
+    pub title: String,
+    pub body: String,
+    pub urgency: Urgency,
+    #[serde(default)]
+    pub gateway_id: Option<String>,
+    #[serde(default)]
+    pub agent_id: Option<String>,
+    #[serde(default)]
+    pub item_id: Option<String>,
+    /// Payload of the gateway event that caused the notification.
+    #[serde(default)]
+    pub source: Option<Value>,
+    /// Action labels offered on the banner.
+    #[serde(default)]
+    pub actions: Vec<String>,
+    /// Item opened by clicking the notification.
+    #[serde(default)]
+    pub target: Option<NotificationTarget>,
+    pub outcome: Outcome,
+    #[serde(default)]
+    pub read: bool,
+}
+
+// ---- History --------------------------------------------------------------
+
+#[derive(Default)]
+pub struct NotificationHistory {
+    entries: Mutex<VecDeque<HistoryEntry>>,
+}
+
+impl NotificationHistory {
+    fn lock(&self) -> MutexGuard<'_, VecDeque<HistoryEntry>> {
+        self.entries.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    pub fn unread_count(&self) -> usize {
+        self.lock().iter().filter(|e| !e.read).count()
+    }
+
+    /// Entries, newest first.
+    pub fn entries(&self) -> Vec<HistoryEntry> {
+        self.lock().iter().rev().cloned().collect()
+    }
+
+    /// Record a submitted notice; returns the entry ID.
+    pub fn record(&self, app: &AppHandle, notice: &Notice, outcome: Outcome) -> String {
+        let id = self.insert(notice, outcome);
+        self.changed(app);
+        id
+    }
+
+    /// Add an entry for `notice` without persisting it.
+    pub(super) fn insert(&self, notice: &Notice, outcome: Outcome) -> String {
+        let id = crate::gateway::protocol::generate_request_id();
+        let actions = match &notice.approval {
+            Some(_) => super::ApprovalDecision::ALL
+                .iter()
+                .map(|d| d.label().to_string())
+                .collect(),
+            None => Vec::new(),
+        };
+        let entry = HistoryEntry {
+            id: id.clone(),
+            at_ms: crate::gateway::now_ms(),
+            category: notice.category,
+            thread: notice.thread.clone(),
+            title: notice.title.clone(),
+            body: notice.body.clone(),
+            urgency: notice.urgency,
+            gateway_id: notice.gateway_id.clone(),
+            agent_id: notice.agent_id.clone(),
+            item_id: notice.item_id.clone(),
+            source: notice.approval.as_ref().map(|a| a.payload.clone()),
+            actions,
+            target: notice.target.clone(),
+            // Suppressed notices never reach the screen
+            read: outcome == Outcome::Suppressed,
+            outcome,
+        };
+        {
+            let mut entries = self.lock();
+            while entries.len() >= HISTORY_CAPACITY {
+                entries.pop_front();
+            }
+            entries.push_back(entry);
+        }
+        id
+    }
+
+    /// Update what became of an entry. Later interactions win, except that
+    /// an action or a resolution is never overwritten.
+    pub fn set_outcome(&self, app: &AppHandle, id: &str, outcome: Outcome) {
+        if self.update_outcome(id, outcome) {
+            self.changed(app);
+        }
+    }
+
+    /// `set_outcome` without persisting; whether the entry changed.
+    pub(super) fn update_outcome(&self, id: &str, outcome: Outcome) -> bool {
+        let mut entries = self.lock();
+        let Some(entry) = entries.iter_mut().find(|e| e.id == id) else {
+            return false;
+        };
+        if matches!(entry.outcome, Outcome::Acted { .. } | Outcome::Resolved) {
+            return false;
+        }
+        // Interacting with a banner counts as reading it
+        if matches!(outcome, Outcome::Clicked | Outcome::Acted { .. }) {
+            entry.read = true;
+        }
+        entry.outcome = outcome;
+        true
+    }
+
+    /// Give entries for `item_id` that saw no interaction `outcome`: the
+    /// item was resolved elsewhere, or answered here outside its banner.
+    pub fn settle_item(&self, app: &AppHandle, item_id: &str, outcome: Outcome) {
+        if self.update_item(item_id, outcome) {
+            self.changed(app);
+        }
+    }
+
+    /// `settle_item` without persisting; whether any entry changed.
+    pub(super) fn update_item(&self, item_id: &str, outcome: Outcome) -> bool {
+        let ids: Vec<_> = self
+            .lock()
+            .iter()
+            .filter(|e| e.item_id.as_deref() == Some(item_id))
+            .filter(|e| matches!(e.outcome, Outcome::Delivered | Outcome::Held))
+            .map(|e| e.id.clone())
+            .collect();
+        let mut changed = false;
+        for id in ids {
+            changed |= self.update_outcome(&id, outcome.clone());
+        }
+        changed
+    }
+
+    /// Mark entries read; with no IDs, mark everything read.
+    fn mark_read(&self, ids: Option<&[String]>) {
+        for entry in self.lock().iter_mut() {
+            if ids.is_none_or(|ids| ids.contains(&entry.id)) {
+                entry.read = true;
+            }
+        }
+    }
+
+    /// Persist and tell the UI and tray about the new unread count.
+    pub(super) fn changed(&self, app: &AppHandle) {
+        let (value, unread) = {
+            let entries = self.lock();
+            (
+                serde_json::to_value(&*entries).ok(),
+                entries.iter().filter(|e| !e.read).count(),
+            )
+        };
+        if let (Some(value), Ok(store)) = (value, app.store(HISTORY_STORE_PATH)) {
+            store.set(HISTORY_KEY, value);
+            let _ = store.save();
+        }
+        let _ = app.emit(EVENT_NOTIFICATION_HISTORY, unread);
+
+        #[cfg(desktop)]
+        crate::tray::set_unread_notifications(app, unread);
+    }
+}
+
+/// Load the saved history.
+pub fn restore(app: &AppHandle) {
+    let entries: Vec<HistoryEntry> = app
+        .store(HISTORY_STORE_PATH)
+        .ok()
+        .and_then(|store| store.get(HISTORY_KEY))
+        .and_then(|value| serde_json::from_value(value).ok())
+        .unwrap_or_default();
+    let history = app.state::<NotificationHistory>();
+    *history.lock() = entries.into();
+
+    #[cfg(desktop)]
+    crate::tray::set_unread_notifications(app, history.unread_count());
+}
+
+// ---- Tauri Commands -------------------------------------------------------
+
+/// Inbox entries, newest first.
+#[tauri::command]
+pub fn notification_history(
+    history: tauri::State<'_, NotificationHistory>,
+    limit: Option<usize>,
+    unread_only: Option<bool>,
+) -> Vec<HistoryEntry> {
+    let unread_only = unread_only.unwrap_or(false);
+    history
+        .entries()
+        .into_iter()
+        .filter(|e| !unread_only || !e.read)
+        .take(limit.unwrap_or(usize::MAX))
+        .collect()
+}
+
+#[tauri::command]
+pub fn notification_unread_count(history: tauri::State<'_, NotificationHistory>) -> usize {
+    history.unread_count()
+}
+
+/// Mark entries read; with no IDs, mark everything read.
+#[tauri::command]
+pub fn notification_mark_read(
+    app: AppHandle,
+    history: tauri::State<'_, NotificationHistory>,
+    ids: Option<Vec<String>>,
+) {
+    history.mark_read(ids.as_deref());
+    history.changed(&app);
+}
+
+/// Delete entries; with no IDs, clear the whole history.
+#[tauri::command]
+pub fn notification_clear(
+    app: AppHandle,
+    history: tauri::State<'_, NotificationHistory>,
+    ids: Option<Vec<String>>,
+) {
+    match ids {
+        Some(ids) => history.lock().retain(|e| !ids.contains(&e.id)),
+        None => history.lock().clear(),
+    }
+    history.changed(&app);
+}
+
+// ---- Tests ----------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn notice(item_id: &str) -> Notice {
+        Notice {
+            category: Category::Info,
+            thread: "agent:main".to_string(),
+            item_id: Some(item_id.to_string()),
+            title: "Title".to_string(),
+            body: item_id.to_string(),
+            urgency: Urgency::Normal,
+            agent_id: Some("main".to_string()),
+            gateway_id: Some("home".to_string()),
+            approval: None,
+            target: None,
+            history_id: None,
+        }
+    }
+
+    fn outcome_of(history: &NotificationHistory, id: &str) -> Outcome {
+        history
+            .entries()
+            .into_iter()
+            .find(|e| e.id == id)
+            .unwrap()
+            .outcome
+    }
+
+    #[test]
+    fn entries_are_capped_dropping_the_oldest() {
+        let history = NotificationHistory::default();
+        let first = history.insert(&notice("first"), Outcome::Delivered);
+        for i in 0..HISTORY_CAPACITY {
+            history.insert(&notice(&i.to_string()), Outcome::Delivered);
+        }
+
+        let entries = history.entries();
+        assert_eq!(entries.len(), HISTORY_CAPACITY);
+        assert!(entries.iter().all(|e| e.id != first));
+        // Newest first
+        assert_eq!(entries[0].body, (HISTORY_CAPACITY - 1).to_string());
+        assert_eq!(entries[HISTORY_CAPACITY - 1].body, "0");
+        assert_eq!(history.unread_count(), HISTORY_CAPACITY);
+    }
+
+    #[test]
+    fn suppressed_notices_start_read() {
+        let history = NotificationHistory::default();
+        history.insert(&notice("a"), Outcome::Suppressed);
+        history.insert(&notice("b"), Outcome::Held);
+        history.insert(&notice("c"), Outcome::Delivered);
+        assert_eq!(history.unread_count(), 2);
+    }
+
+    #[test]
+    fn interacting_with_a_banner_reads_it() {
+        let history = NotificationHistory::default();
+        let clicked = history.insert(&notice("a"), Outcome::Delivered);
+        let dismissed = history.insert(&notice("b"), Outcome::Delivered);
+        let expired = history.insert(&notice("c"), Outcome::Delivered);
+        let acted = history.insert(&notice("d"), Outcome::Delivered);
+
+        assert!(history.update_outcome(&clicked, Outcome::Clicked));
+        assert!(history.update_outcome(&dismissed, Outcome::Dismissed));
+        assert!(history.update_outcome(&expired, Outcome::Expired));
+        let deny = Outcome::Acted {
+            action: "deny".to_string(),
+        };
+        assert!(history.update_outcome(&acted, deny));
+
+        let unread: Vec<_> = history
+            .entries()
+            .into_iter()
+            .filter(|e| !e.read)
+            .map(|e| e.id)
+            .collect();
+        assert_eq!(unread, [expired, dismissed]);
+        assert!(!history.update_outcome("missing", Outcome::Clicked));
+    }
+
+    #[test]
+    fn actions_and_resolutions_are_final() {
+        let history = NotificationHistory::default();
+        let acted = history.insert(&notice("a"), Outcome::Delivered);
+        let deny = Outcome::Acted {
+            action: "deny".to_string(),
+        };
+        history.update_outcome(&acted, deny.clone());
+        // The banner closing after the action does not overwrite it
+        assert!(!history.update_outcome(&acted, Outcome::Dismissed));
+        assert_eq!(outcome_of(&history, &acted), deny);
+
+        let resolved = history.insert(&notice("b"), Outcome::Delivered);
+        assert!(history.update_item("b", Outcome::Resolved));
+        assert!(!history.update_outcome(&resolved, Outcome::Expired));
+        assert_eq!(outcome_of(&history, &resolved), Outcome::Resolved);
+    }
+
+    #[test]
+    fn settling_an_item_skips_entries_with_an_interaction() {
+        let history = NotificationHistory::default();
+        let held = history.insert(&notice("a"), Outcome::Held);
+        let clicked = history.insert(&notice("a"), Outcome::Delivered);
+        history.update_outcome(&clicked, Outcome::Clicked);
+        let other = history.insert(&notice("b"), Outcome::Delivered);
+
+        assert!(history.update_item("a", Outcome::Resolved));
+        assert_eq!(outcome_of(&history, &held), Outcome::Resolved);
+        assert_eq!(outcome_of(&history, &clicked), Outcome::Clicked);
+        assert_eq!(outcome_of(&history, &other), Outcome::Delivered);
+        // Nothing left to settle
+        assert!(!history.update_item("a", Outcome::Resolved));
+    }
+
+    #[test]
+    fn marking_read_by_id_or_all() {
+        let history = NotificationHistory::default();
+        let a = history.insert(&notice("a"), Outcome::Delivered);
+        history.insert(&notice("b"), Outcome::Delivered);
+        history.insert(&notice("c"), Outcome::Delivered);
+
+        history.mark_read(Some(&[a]));
+        assert_eq!(history.unread_count(), 2);
+        history.mark_read(None);
+        assert_eq!(history.unread_count(), 0);
+    }
+}
@@ -0,0 +1,970 @@
+// ---------------------------------------------------------------------------
+// Notification Manager
+// ---------------------------------------------------------------------------
+//
+// Every native notification goes through here. Notifications are grouped by
+// category and thread (a gateway, agent or session); a burst on one thread
+// is collapsed into a single summary ("5 approvals pending"), and each
+// category has a token bucket so a noisy agent cannot flood the desktop.
+// When the underlying item is resolved (`exec.approval.resolved`) its banner,
+// or the summary counting it, is replaced: with what was chosen when it was
+// answered from this app, or "resolved elsewhere" otherwise. User rules (quiet
+// hours, focus) run first and may suppress, hold or escalate a notice.
+
+use super::backend::{self, AppInfo, NotificationBackend, NotificationRequest};
+use super::history::{NotificationHistory, Outcome};
+use super::rules::{self, Clock, NotificationRule, RuleAction, RuleInput, SystemClock, Urgency};
+use super::target::NotificationTarget;
+use super::NotificationId;
+use crate::gateway::session::PendingApproval;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Manager};
+
+// ---- Constants ------------------------------------------------------------
+
+/// Notifications on a thread within this window of the last banner are
+/// collected and shown together when it ends.
+const COALESCE_WINDOW: Duration = Duration::from_secs(3);
+
+/// Number of queued item lines listed in a summary body.
+const SUMMARY_LINES: usize = 3;
+
+/// How often held digest notices are checked for release.
+const DIGEST_CHECK_INTERVAL: Duration = Duration::from_secs(60);
+
+// ---- Types ----------------------------------------------------------------
+
+/// Rate-limit and wording category.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Category {
+    Approval,
+    Error,
+    Info,
+}
+
+impl Category {
+    /// Burst size and sustained rate (tokens per minute).
+    fn limits(self) -> (f64, f64) {
+        match self {
+            Category::Approval => (3.0, 6.0),
+            Category::Error => (2.0, 2.0),
+            Category::Info => (4.0, 10.0),
+        }
+    }
+
+    fn summary_title(self, count: usize) -> String {
+        if count == 0 {
+            return self.resolved_title().to_string();
+        }
+        match self {
+            Category::Approval => format!("{} approvals pending", count),
+            Category::Error => format!("{} errors", count),
+            Category::Info => format!("{} notifications", count),
+        }
+    }
+
+    fn resolved_title(self) -> &'static str {
+        match self {
+            Category::Approval => "Approval resolved",
+            Category::Error | Category::Info => "Resolved",
+        }
+    }
+
+    /// freedesktop icon name; the app icon when `None`.
+    pub fn icon(self) -> Option<&'static str> {
+        match self {
+            Category::Error => Some("dialog-error"),
+            Category::Approval | Category::Info => None,
+        }
+    }
+}
+
+/// A notification to show.
+#[derive(Debug, Clone)]
+pub struct Notice {
+    pub category: Category,
+    /// Grouping key, e.g. "gateway:home", "agent:main" or "session:abc".
+    pub thread: String,
+    /// ID of the underlying item, so it can be replaced once resolved.
+    pub item_id: Option<String>,
+    pub title: String,
+    pub body: String,
+    /// Critical notices play a sound.
+    pub urgency: Urgency,
+    pub agent_id: Option<String>,
+    pub gateway_id: Option<String>,
+    /// Shown with Approve/Deny actions when delivered on its own.
+    pub approval: Option<PendingApproval>,
+    /// Opened when the notification is clicked.
+    pub target: Option<NotificationTarget>,
+    /// Inbox entry, assigned on submit.
+    pub history_id: Option<String>,
+}
+
+/// An answer to an item given from this app (a banner button, the tray or
+/// a shortcut), kept until the item's resolution comes back.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Answer {
+    /// Action recorded in the history, e.g. "deny".
+    pub action: String,
+    /// Replaces the item's notifications, e.g. "Denied".
+    pub resolution: String,
+}
+
+impl Notice {
+    fn rule_input(&self) -> RuleInput<'_> {
+        RuleInput {
+            urgency: self.urgency,
+            category: self.category,
+            agent_id: self.agent_id.as_deref(),
+            gateway_id: self.gateway_id.as_deref(),
+        }
+    }
+}
+
+// ---- Token Bucket ---------------------------------------------------------
+
+#[derive(Debug, Clone)]
+struct TokenBucket {
+    capacity: f64,
+    per_second: f64,
+    tokens: f64,
+    updated: Instant,
+}
+
+impl TokenBucket {
+    fn new(category: Category, now: Instant) -> Self {
+        let (capacity, per_minute) = category.limits();
+        Self {
+            capacity,
+            per_second: per_minute / 60.0,
+            tokens: capacity,
+            updated: now,
+        }
+    }
+
+    fn refill(&mut self, now: Instant) {
+        let elapsed = now.saturating_duration_since(self.updated).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * self.per_second).min(self.capacity);
+        self.updated = now;
+    }
+
+    fn try_take(&mut self, now: Instant) -> bool {
+        self.refill(now);
+        if self.tokens >= 1.0 {
+            self.tokens -= 1.0;
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Time until the next token is available.
+    fn wait(&self) -> Duration {
+        Duration::from_secs_f64(((1.0 - self.tokens) / self.per_second).max(0.0))
+    }
+}
+
+// ---- Manager --------------------------------------------------------------
+
+type ThreadKey = (Category, String);
+
+#[derive(Default)]
+struct ThreadState {
+    queued: Vec<Notice>,
+    flush_scheduled: bool,
+    last_shown: Option<Instant>,
+    /// Summary banner on screen and the items it still counts.
+    summary: Option<(Option<NotificationId>, Vec<String>)>,
+}
+
+#[derive(Default)]
+struct Inner {
+    rules: Vec<NotificationRule>,
+    /// Notices held by a digest rule.
+    digest: Vec<Notice>,
+    buckets: HashMap<Category, TokenBucket>,
+    threads: HashMap<ThreadKey, ThreadState>,
+    /// Items shown in their own banner, by item ID.
+    shown: HashMap<String, (Category, Option<NotificationId>)>,
+    /// Items answered from this app, by item ID.
+    answered: HashMap<String, Answer>,
+}
+
+impl Inner {
+    fn take_token(&mut self, category: Category, now: Instant) -> bool {
+        self.buckets
+            .entry(category)
+            .or_insert_with(|| TokenBucket::new(category, now))
+            .try_take(now)
+    }
+
+    /// Still queued or on screen, i.e. not resolved yet.
+    fn is_outstanding(&self, item_id: &str) -> bool {
+        self.shown.contains_key(item_id)
+            || self.threads.values().any(|t| {
+                t.queued
+                    .iter()
+                    .any(|n| n.item_id.as_deref() == Some(item_id))
+            })
+    }
+}
+
+/// What to put on screen once the lock is released.
+enum Delivery {
+    Single(Box<Notice>),
+    Summary {
+        key: ThreadKey,
+        title: String,
+        body: String,
+        replaces: Option<NotificationId>,
+    },
+}
+
+/// What `admit` did with a notice.
+enum Admission {
+    Show(Delivery),
+    /// Queued for the thread's summary; `schedule` when no flush was
+    /// pending yet.
+    Queued {
+        schedule: bool,
+    },
+}
+
+/// What a thread's flush produced.
+enum Flush {
+    /// Nothing queued (everything was resolved meanwhile).
+    Empty,
+    /// Out of tokens; try again after this long.
+    Wait(Duration),
+    Show(Delivery),
+}
+
+pub struct NotificationManager {
+    inner: Mutex<Inner>,
+    clock: Box<dyn Clock>,
+    backend: Arc<dyn NotificationBackend>,
+}
+
+impl Default for NotificationManager {
+    fn default() -> Self {
+        Self::new(Box::new(SystemClock), backend::platform())
+    }
+}
+
+impl NotificationManager {
+    /// A manager whose rules see the time of day from `clock` and whose
+    /// notifications are shown by `backend`.
+    pub fn new(clock: Box<dyn Clock>, backend: Arc<dyn NotificationBackend>) -> Self {
+        Self {
+            inner: Mutex::new(Inner::default()),
+            clock,
+            backend,
+        }
+    }
+
+    pub fn backend(&self) -> Arc<dyn NotificationBackend> {
+        self.backend.clone()
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Inner> {
+        self.inner.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    pub fn rules(&self) -> Vec<NotificationRule> {
+        self.lock().rules.clone()
+    }
+
+    pub fn set_rules(&self, rules: Vec<NotificationRule>) {
+        self.lock().rules = rules;
+    }
+
+    /// The action of the first rule matching `notice` at the clock's time.
+    fn rule_action(&self, notice: &Notice) -> Option<RuleAction> {
+        let inner = self.lock();
+        rules::evaluate(&inner.rules, &notice.rule_input(), self.clock.now())
+            .map(|rule| rule.action.clone())
+    }
+
+    /// Apply the user's rules, then show or queue `notice`.
+    pub fn submit(&self, app: &AppHandle, mut notice: Notice) {
+        let action = self.rule_action(&notice);
+        let history = app.state::<NotificationHistory>();
+        match action {
+            Some(RuleAction::Suppress) => {
+                history.record(app, &notice, Outcome::Suppressed);
+                return;
+            }
+            Some(RuleAction::Digest) => {
+                notice.history_id = Some(history.record(app, &notice, Outcome::Held));
+                self.lock().digest.push(notice);
+                return;
+            }
+            Some(RuleAction::Escalate { .. }) => notice.urgency = Urgency::Critical,
+            None => {}
+        }
+        notice.history_id = Some(history.record(app, &notice, Outcome::Delivered));
+        if let Some(RuleAction::Escalate {
+            repeat_after_secs,
+            repeat_count,
+        }) = action
+        {
+            if repeat_count > 0 {
+                schedule_repeat(
+                    app,
+                    notice.clone(),
+                    Duration::from_secs(repeat_after_secs.max(1)),
+                    repeat_count,
+                );
+            }
+        }
+        self.enqueue(app, notice);
+    }
+
+    /// Show `notice` now, or queue it for the thread's next summary.
+    fn enqueue(&self, app: &AppHandle, notice: Notice) {
+        let key = (notice.category, notice.thread.clone());
+        match self.admit(notice, Instant::now()) {
+            Admission::Show(delivery) => self.deliver(app, delivery),
+            Admission::Queued { schedule: true } => schedule_flush(app, key, COALESCE_WINDOW),
+            Admission::Queued { schedule: false } => {}
+        }
+    }
+
+    /// Whether `notice` goes on screen at `now` or waits for its thread's
+    /// summary: it waits during a burst or when its category is out of
+    /// tokens.
+    fn admit(&self, notice: Notice, now: Instant) -> Admission {
+        let key = (notice.category, notice.thread.clone());
+        let mut inner = self.lock();
+        let thread = inner.threads.entry(key.clone()).or_default();
+        let in_burst = thread.flush_scheduled
+            || thread
+                .last_shown
+                .is_some_and(|t| now.duration_since(t) < COALESCE_WINDOW);
+        if !in_burst && inner.take_token(notice.category, now) {
+            inner.threads.entry(key).or_default().last_shown = Some(now);
+            return Admission::Show(Delivery::Single(Box::new(notice)));
+        }
+        let thread = inner.threads.entry(key).or_default();
+        thread.queued.push(notice);
+        Admission::Queued {
+            schedule: !std::mem::replace(&mut thread.flush_scheduled, true),
+        }
+    }
+
+    /// Show what a thread collected during its burst.
+    fn flush(&self, app: &AppHandle, key: ThreadKey) {
+        match self.take_flush(&key, Instant::now()) {
+            Flush::Empty => {}
+            Flush::Wait(wait) => schedule_flush(app, key, wait.max(COALESCE_WINDOW)),
+            Flush::Show(delivery) => self.deliver(app, delivery),
+        }
+    }
+
+    /// What a thread's flush at `now` puts on screen: its one queued notice,
+    /// or a summary counting everything it queued.
+    fn take_flush(&self, key: &ThreadKey, now: Instant) -> Flush {
+        let mut inner = self.lock();
+        let queued = inner.threads.get(key).map(|t| t.queued.len()).unwrap_or(0);
+        if queued == 0 {
+            if let Some(thread) = inner.threads.get_mut(key) {
+                thread.flush_scheduled = false;
+            }
+            return Flush::Empty;
+        }
+        if !inner.take_token(key.0, now) {
+            return Flush::Wait(inner.buckets[&key.0].wait());
+        }
+
+        let Some(thread) = inner.threads.get_mut(key) else {
+            return Flush::Empty;
+        };
+        thread.flush_scheduled = false;
+        thread.last_shown = Some(now);
+        let mut notices = std::mem::take(&mut thread.queued);
+        let (replaces, mut items) = thread.summary.take().unwrap_or_default();
+        if notices.len() == 1 && items.is_empty() {
+            return Flush::Show(Delivery::Single(Box::new(notices.remove(0))));
+        }
+        items.extend(notices.iter().filter_map(|n| n.item_id.clone()));
+        let count = items.len().max(notices.len());
+        let mut lines: Vec<_> = notices
+            .iter()
+            .take(SUMMARY_LINES)
+            .map(|n| n.body.clone())
+            .collect();
+        if notices.len() > SUMMARY_LINES {
+            lines.push(format!("and {} more", notices.len() - SUMMARY_LINES));
+        }
+        thread.summary = Some((replaces, items));
+        Flush::Show(Delivery::Summary {
+            key: key.clone(),
+            title: key.0.summary_title(count),
+            body: lines.join("\n"),
+            replaces,
+        })
+    }
+
+    /// The item was resolved: drop it from queues and replace its banner
+    /// (or the summary counting it). `resolution` is used unless the item
+    /// was answered from this app.
+    pub fn resolve(&self, app: &AppHandle, item_id: &str, resolution: &str) {
+        let info = AppInfo::of(app);
+        let (replacements, outcome) = self.settle(item_id, resolution);
+        for request in replacements {
+            let _ = self.backend.show(&info, &request);
+        }
+        app.state::<NotificationHistory>()
+            .settle_item(app, item_id, outcome);
+    }
+
+    /// Remember that `item_id` was answered from this app, before the
+    /// answer is sent, so its resolution is reported as that answer.
+    pub fn answered(&self, item_id: &str, answer: Answer) {
+        self.lock().answered.insert(item_id.to_string(), answer);
+    }
+
+    /// The answer to `item_id` could not be sent.
+    pub fn forget_answer(&self, item_id: &str) {
+        self.lock().answered.remove(item_id);
+    }
+
+    /// `take_resolution` with the answer given here, if any, and the
+    /// history outcome of the item's entries.
+    pub(super) fn settle(
+        &self,
+        item_id: &str,
+        resolution: &str,
+    ) -> (Vec<NotificationRequest>, Outcome) {
+        let answer = self.lock().answered.remove(item_id);
+        match answer {
+            Some(answer) => (
+                self.take_resolution(item_id, &answer.resolution),
+                Outcome::Acted {
+                    action: answer.action,
+                },
+            ),
+            None => (self.take_resolution(item_id, resolution), Outcome::Resolved),
+        }
+    }
+
+    /// Forget `item_id` everywhere and build the requests replacing its
+    /// banners.
+    pub(super) fn take_resolution(
+        &self,
+        item_id: &str,
+        resolution: &str,
+    ) -> Vec<NotificationRequest> {
+        let mut replacements = Vec::new();
+        let mut inner = self.lock();
+        inner
+            .digest
+            .retain(|n| n.item_id.as_deref() != Some(item_id));
+        for thread in inner.threads.values_mut() {
+            thread
+                .queued
+                .retain(|n| n.item_id.as_deref() != Some(item_id));
+        }
+
+        if let Some((category, Some(id))) = inner.shown.remove(item_id) {
+            replacements.push(super::replacement_request(
+                id,
+                category.resolved_title(),
+                resolution,
+            ));
+        }
+
+        for (key, thread) in inner.threads.iter_mut() {
+            let Some((id, items)) = &mut thread.summary else {
+                continue;
+            };
+            let before = items.len();
+            items.retain(|i| i != item_id);
+            if items.len() == before {
+                continue;
+            }
+            if let Some(id) = *id {
+                replacements.push(super::replacement_request(
+                    id,
+                    &key.0.summary_title(items.len()),
+                    resolution,
+                ));
+            }
+            if items.is_empty() {
+                thread.summary = None;
+            }
+        }
+        replacements
+    }
+
+    fn deliver(&self, app: &AppHandle, delivery: Delivery) {
+        match delivery {
+            Delivery::Single(notice) => {
+                self.mark_shown(&notice);
+                if let (Some(id), Some(item_id)) = (super::show(app, &notice), &notice.item_id) {
+                    self.set_shown_id(item_id, id);
+                }
+            }
+            Delivery::Summary {
+                key,
+                title,
+                body,
+                replaces,
+            } => {
+                let id = super::show_summary(app, &title, &body, replaces);
+                self.set_summary_id(&key, id);
+            }
+        }
+    }
+
+    /// Track a notice shown in its own banner, before the banner exists,
+    /// so a resolution arriving meanwhile is not lost.
+    pub(super) fn mark_shown(&self, notice: &Notice) {
+        if let Some(item_id) = &notice.item_id {
+            self.lock()
+                .shown
+                .insert(item_id.clone(), (notice.category, None));
+        }
+    }
+
+    /// Record the platform ID of a thread's summary banner.
+    fn set_summary_id(&self, key: &ThreadKey, id: Option<NotificationId>) {
+        let mut inner = self.lock();
+        if let Some((summary_id, _)) = inner.threads.get_mut(key).and_then(|t| t.summary.as_mut()) {
+            *summary_id = id.or(*summary_id);
+        }
+    }
+
+    /// Deliver held digest notices whose digest rule no longer applies (or
+    /// all of them with `force`) as one summary.
+    pub fn flush_digest(&self, app: &AppHandle, force: bool) {
+        let released = self.release_digest(force);
+        let history = app.state::<NotificationHistory>();
+        for id in released.iter().filter_map(|n| n.history_id.as_deref()) {
+            history.set_outcome(app, id, Outcome::Delivered);
+        }
+
+        match released.len() {
+            0 => {}
+            1 => {
+                if let Some(notice) = released.into_iter().next() {
+                    self.enqueue(app, notice);
+                }
+            }
+            count => {
+                let mut lines: Vec<_> = released
+                    .iter()
+                    .take(SUMMARY_LINES)
+                    .map(|n| format!("{}: {}", n.title, n.body))
+                    .collect();
+                if count > SUMMARY_LINES {
+                    lines.push(format!("and {} more", count - SUMMARY_LINES));
+                }
+                let title = format!("{} notifications while you were away", count);
+                super::show_summary(app, &title, &lines.join("\n"), None);
+            }
+        }
+    }
+
+    /// Take the held notices whose digest rule no longer applies at the
+    /// clock's time (all of them with `force`).
+    fn release_digest(&self, force: bool) -> Vec<Notice> {
+        let now = self.clock.now();
+        let mut inner = self.lock();
+        let held = std::mem::take(&mut inner.digest);
+        let (keep, release): (Vec<_>, Vec<_>) = held.into_iter().partition(|notice| {
+            !force
+                && matches!(
+                    rules::evaluate(&inner.rules, &notice.rule_input(), now),
+                    Some(rule) if matches!(rule.action, RuleAction::Digest)
+                )
+        });
+        inner.digest = keep;
+        release
+    }
+
+    /// Record the platform ID of an item's banner once it is on screen.
+    pub(super) fn set_shown_id(&self, item_id: &str, id: NotificationId) {
+        if let Some((_, shown)) = self.lock().shown.get_mut(item_id) {
+            *shown = Some(id);
+        }
+    }
+
+    /// The item's banner left the screen when an action was pressed; its
+    /// resolution must not bring it back. The item stays outstanding.
+    pub(super) fn banner_closed(&self, item_id: &str) {
+        if let Some((_, shown)) = self.lock().shown.get_mut(item_id) {
+            *shown = None;
+        }
+    }
+}
+
+/// Periodically release digest notices once quiet hours end.
+pub fn spawn_digest_task(app: &AppHandle) {
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        let mut interval = tokio::time::interval(DIGEST_CHECK_INTERVAL);
+        loop {
+            interval.tick().await;
+            app.state::<NotificationManager>().flush_digest(&app, false);
+        }
+    });
+}
+
+/// Re-show an escalated notice until its item is resolved.
+fn schedule_repeat(app: &AppHandle, notice: Notice, every: Duration, count: u32) {
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        for _ in 0..count {
+            tokio::time::sleep(every).await;
+            let manager = app.state::<NotificationManager>();
+            if let Some(item_id) = &notice.item_id {
+                if !manager.lock().is_outstanding(item_id) {
+                    return;
+                }
+            }
+            manager.enqueue(&app, notice.clone());
+        }
+    });
+}
+
+fn schedule_flush(app: &AppHandle, key: ThreadKey, after: Duration) {
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        tokio::time::sleep(after).await;
+        app.state::<NotificationManager>().flush(&app, key);
+    });
+}
+
+// ---- Tests ----------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::notifications::backend::RecordingBackend;
+    use crate::notifications::rules::{FakeClock, RuleConditions, TimeWindow};
+
+    fn notice(thread: &str, item_id: &str, urgency: Urgency) -> Notice {
+        Notice {
+            category: Category::Info,
+            thread: thread.to_string(),
+            item_id: Some(item_id.to_string()),
+            title: "Title".to_string(),
+            body: item_id.to_string(),
+            urgency,
+            agent_id: None,
+            gateway_id: None,
+            approval: None,
+            target: None,
+            history_id: None,
+        }
+    }
+
+    fn manager(clock: &FakeClock) -> NotificationManager {
+        NotificationManager::new(
+            Box::new(clock.clone()),
+            Arc::new(RecordingBackend::default()),
+        )
+    }
+
+    fn quiet_hours_digest() -> NotificationRule {
+        NotificationRule {
+            id: "quiet".to_string(),
+            name: "Quiet hours".to_string(),
+            enabled: true,
+            when: RuleConditions {
+                urgency: vec![Urgency::Low, Urgency::Normal],
+                hours: Some(TimeWindow {
+                    start: "22:00".to_string(),
+                    end: "07:00".to_string(),
+                    days: Vec::new(),
+                }),
+                ..RuleConditions::default()
+            },
+            action: RuleAction::Digest,
+        }
+    }
+
+    /// What `submit` does with a digest match, without the history store.
+    fn hold(manager: &NotificationManager, notice: Notice) {
+        manager.lock().digest.push(notice);
+    }
+
+    fn shown(admission: Admission) -> Delivery {
+        match admission {
+            Admission::Show(delivery) => delivery,
+            Admission::Queued { .. } => panic!("expected the notice on screen"),
+        }
+    }
+
+    fn queued(admission: Admission) -> bool {
+        match admission {
+            Admission::Queued { schedule } => schedule,
+            Admission::Show(_) => panic!("expected the notice queued"),
+        }
+    }
+
+    fn flushed(flush: Flush) -> Delivery {
+        match flush {
+            Flush::Show(delivery) => delivery,
+            Flush::Empty => panic!("expected a delivery, nothing was queued"),
+            Flush::Wait(wait) => panic!("expected a delivery, told to wait {:?}", wait),
+        }
+    }
+
+    /// Put a single banner on screen as `deliver` does, with platform `id`.
+    fn show_single(manager: &NotificationManager, delivery: Delivery, id: NotificationId) {
+        let Delivery::Single(notice) = delivery else {
+            panic!("expected a single banner");
+        };
+        manager.mark_shown(&notice);
+        manager.set_shown_id(notice.item_id.as_deref().unwrap(), id);
+    }
+
+    fn key(thread: &str) -> ThreadKey {
+        (Category::Info, thread.to_string())
+    }
+
+    #[test]
+    fn burst_on_a_thread_is_coalesced_into_a_summary() {
+        let manager = manager(&FakeClock::at("2026-10-16 12:00"));
+        let start = Instant::now();
+
+        shown(manager.admit(notice("agent:main", "a", Urgency::Normal), start));
+        // Within the window: queued, and only the first schedules a flush
+        assert!(queued(manager.admit(
+            notice("agent:main", "b", Urgency::Normal),
+            start + Duration::from_secs(1)
+        )));
+        assert!(!queued(manager.admit(
+            notice("agent:main", "c", Urgency::Normal),
+            start + Duration::from_secs(2)
+        )));
+        // Other threads are not held back
+        shown(manager.admit(
+            notice("agent:ops", "d", Urgency::Normal),
+            start + Duration::from_secs(2),
+        ));
+
+        let flush_at = start + COALESCE_WINDOW + Duration::from_secs(1);
+        match flushed(manager.take_flush(&key("agent:main"), flush_at)) {
+            Delivery::Summary {
+                title,
+                body,
+                replaces,
+                ..
+            } => {
+                assert_eq!(title, "2 notifications");
+                assert_eq!(body, "b\nc");
+                assert_eq!(replaces, None);
+            }
+            Delivery::Single(_) => panic!("expected a summary"),
+        }
+        assert!(matches!(
+            manager.take_flush(&key("agent:main"), flush_at),
+            Flush::Empty
+        ));
+    }
+
+    #[test]
+    fn lone_queued_notice_flushes_as_its_own_banner() {
+        let manager = manager(&FakeClock::at("2026-10-16 12:00"));
+        let start = Instant::now();
+        shown(manager.admit(notice("agent:main", "a", Urgency::Normal), start));
+        assert!(queued(manager.admit(
+            notice("agent:main", "b", Urgency::Normal),
+            start + Duration::from_secs(1)
+        )));
+
+        let flush_at = start + COALESCE_WINDOW + Duration::from_secs(1);
+        match flushed(manager.take_flush(&key("agent:main"), flush_at)) {
+            Delivery::Single(notice) => assert_eq!(notice.item_id.as_deref(), Some("b")),
+            Delivery::Summary { .. } => panic!("expected a single banner"),
+        }
+    }
+
+    #[test]
+    fn category_bucket_limits_banners() {
+        let manager = manager(&FakeClock::at("2026-10-16 12:00"));
+        let now = Instant::now();
+        let (burst, per_minute) = Category::Info.limits();
+
+        // A full bucket shows a burst across threads, then queues
+        for i in 0..burst as usize {
+            let thread = format!("agent:{}", i);
+            shown(manager.admit(notice(&thread, &thread, Urgency::Normal), now));
+        }
+        assert!(queued(
+            manager.admit(notice("agent:late", "late", Urgency::Normal), now)
+        ));
+
+        // The flush waits for the next token...
+        let wait = match manager.take_flush(&key("agent:late"), now) {
+            Flush::Wait(wait) => wait,
+            _ => panic!("expected to wait for a token"),
+        };
+        let refill = Duration::from_secs_f64(60.0 / per_minute);
+        let slack = Duration::from_millis(10);
+        assert!(
+            wait > Duration::ZERO && wait <= refill + slack,
+            "{:?}",
+            wait
+        );
+
+        // ...and shows the notice once it has refilled
+        match flushed(manager.take_flush(&key("agent:late"), now + wait + slack)) {
+            Delivery::Single(notice) => assert_eq!(notice.item_id.as_deref(), Some("late")),
+            Delivery::Summary { .. } => panic!("expected a single banner"),
+        }
+    }
+
+    #[test]
+    fn resolving_a_shown_item_replaces_its_banner() {
+        let manager = manager(&FakeClock::at("2026-10-16 12:00"));
+        let delivery =
+            shown(manager.admit(notice("agent:main", "a", Urgency::Normal), Instant::now()));
+        show_single(&manager, delivery, 7);
+
+        let replacements = manager.take_resolution("a", "Approved on phone");
+        assert_eq!(replacements.len(), 1);
+        assert_eq!(replacements[0].replaces, Some(7));
+        assert_eq!(replacements[0].title, "Resolved");
+        assert_eq!(replacements[0].body, "Approved on phone");
+
+        // Resolving twice changes nothing
+        assert!(manager.take_resolution("a", "Approved").is_empty());
+        assert!(!manager.lock().is_outstanding("a"));
+    }
+
+    #[test]
+    fn resolving_summarized_items_counts_the_summary_down() {
+        let backend = Arc::new(RecordingBackend::default());
+        let manager =
+            NotificationManager::new(Box::new(FakeClock::at("2026-10-16 12:00")), backend.clone());
+        let app = AppInfo::default();
+        let start = Instant::now();
+        let first = shown(manager.admit(notice("agent:main", "a", Urgency::Normal), start));
+        show_single(&manager, first, 100);
+        for item in ["b", "c"] {
+            queued(manager.admit(
+                notice("agent:main", item, Urgency::Normal),
+                start + Duration::from_secs(1),
+            ));
+        }
+
+        // The summary goes on screen as `deliver` puts it there
+        let flush_at = start + COALESCE_WINDOW + Duration::from_secs(1);
+        let Delivery::Summary {
+            key: summary_key,
+            title,
+            body,
+            replaces,
+        } = flushed(manager.take_flush(&key("agent:main"), flush_at))
+        else {
+            panic!("expected a summary");
+        };
+        let request = crate::notifications::summary_request(&title, &body, replaces);
+        let id = backend.show(&app, &request).unwrap();
+        manager.set_summary_id(&summary_key, id);
+        let summary_id = id.unwrap();
+
+        // Each resolution replaces the summary by its ID, counting down
+        let replacements = manager.take_resolution("b", "Done");
+        assert_eq!(replacements.len(), 1);
+        assert_eq!(replacements[0].replaces, Some(summary_id));
+        assert_eq!(replacements[0].title, "1 notifications");
+        assert_eq!(
+            backend.show(&app, &replacements[0]).unwrap(),
+            Some(summary_id)
+        );
+
+        let replacements = manager.take_resolution("c", "Done");
+        assert_eq!(replacements.len(), 1);
+        assert_eq!(replacements[0].title, "Resolved");
+        assert!(replacements[0].timeout.is_some());
+        assert!(manager.lock().threads[&key("agent:main")].summary.is_none());
+        // The summary and its first replacement
+        assert_eq!(backend.requests().len(), 2);
+    }
+
+    #[test]
+    fn resolving_a_queued_item_drops_it_before_the_flush() {
+        let manager = manager(&FakeClock::at("2026-10-16 12:00"));
+        let start = Instant::now();
+        shown(manager.admit(notice("agent:main", "a", Urgency::Normal), start));
+        queued(manager.admit(
+            notice("agent:main", "b", Urgency::Normal),
+            start + Duration::from_secs(1),
+        ));
+        assert!(manager.lock().is_outstanding("b"));
+
+        // Nothing on screen to replace, and nothing left to flush
+        assert!(manager.take_resolution("b", "Done").is_empty());
+        let flush_at = start + COALESCE_WINDOW + Duration::from_secs(1);
+        assert!(matches!(
+            manager.take_flush(&key("agent:main"), flush_at),
+            Flush::Empty
+        ));
+    }
+
+    #[test]
+    fn digest_holds_during_quiet_hours_and_releases_after() {
+        let clock = FakeClock::at("2026-10-16 23:00");
+        let manager = manager(&clock);
+        manager.set_rules(vec![quiet_hours_digest()]);
+
+        let held = notice("agent:main", "a", Urgency::Normal);
+        assert!(matches!(
+            manager.rule_action(&held),
+            Some(RuleAction::Digest)
+        ));
+        let critical = notice("agent:main", "b", Urgency::Critical);
+        assert!(manager.rule_action(&critical).is_none());
+        hold(&manager, held);
+        hold(&manager, notice("agent:main", "c", Urgency::Low));
+
+        // Still quiet: nothing is released
+        clock.set("2026-10-17 06:30");
+        assert!(manager.release_digest(false).is_empty());
+
+        // Quiet hours over: everything held comes out, oldest first
+        clock.set("2026-10-17 07:00");
+        let released: Vec<_> = manager
+            .release_digest(false)
+            .into_iter()
+            .filter_map(|n| n.item_id)
+            .collect();
+        assert_eq!(released, ["a", "c"]);
+        assert!(manager.release_digest(false).is_empty());
+    }
+
+    #[test]
+    fn forced_digest_flush_releases_during_quiet_hours() {
+        let clock = FakeClock::at("2026-10-16 23:00");
+        let manager = manager(&clock);
+        manager.set_rules(vec![quiet_hours_digest()]);
+        hold(&manager, notice("agent:main", "a", Urgency::Normal));
+
+        assert!(manager.release_digest(false).is_empty());
+        assert_eq!(manager.release_digest(true).len(), 1);
+        assert!(manager.release_digest(true).is_empty());
+    }
+
+    #[test]
+    fn disabling_the_digest_rule_releases_held_notices() {
+        let clock = FakeClock::at("2026-10-16 23:00");
+        let manager = manager(&clock);
+        manager.set_rules(vec![quiet_hours_digest()]);
+        hold(&manager, notice("agent:main", "a", Urgency::Normal));
+
+        let mut rule = quiet_hours_digest();
+        rule.enabled = false;
+        manager.set_rules(vec![rule]);
+        assert_eq!(manager.release_digest(false).len(), 1);
+    }
+}
@@ -0,0 +1,784 @@
+// ---------------------------------------------------------------------------
+// Native Notification Bridge
+// ---------------------------------------------------------------------------
+// Tauri commands that the frontend can invoke to send OS-level notifications.
+// macOS: uses tauri-plugin-notification (UNUserNotificationCenter), and
+//        mac-notification-sys for notifications with actions.
+// Linux: freedesktop notifications over D-Bus, with urgency hints, actions,
+//        icons, replacement by ID and close reasons.
+// iOS: push notification support (requires entitlements).
+// The platform APIs sit behind `backend::NotificationBackend`.
+//
+// Exec approval notifications are sent from Rust with Approve once / Always
+// allow / Deny buttons. The chosen action is resolved through the backend
+// gateway session, so it works while the window is hidden. Everything is
+// routed through the `NotificationManager` for grouping and rate limiting.
+// A notice may carry a `NotificationTarget`; clicking it navigates to that
+// item's view.
+
+pub mod backend;
+pub mod history;
+pub mod manager;
+pub mod rules;
+pub mod target;
+
+use crate::gateway::manager::GatewayManager;
+use crate::gateway::scheduler::Priority;
+use crate::gateway::session::PendingApproval;
+use backend::{
+    AppInfo, CloseReason, Interaction, NotificationAction, NotificationBackend, NotificationRequest,
+};
+use history::{NotificationHistory, Outcome};
+use manager::{Answer, Category, Notice, NotificationManager};
+use rules::Urgency;
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+use target::NotificationTarget;
+use tauri::{AppHandle, Manager};
+use tauri_plugin_notification::NotificationExt;
+
+/// Platform notification ID, where the platform can replace by ID.
+pub type NotificationId = u32;
+
+/// Send a native notification from the frontend.
+///
+/// # Arguments
+/// - `title` — notification title (e.g. "Exec Approval Required")
+/// - `body` — notification body text
+/// - `urgency` — "low" | "normal" | "critical" — maps to notification sound
+/// - `category` — rate-limit category (defaults to "info")
+/// - `thread` — grouping key for coalescing bursts (defaults to "app")
+/// - `target` — item to open when the notification is clicked
+#[tauri::command]
+pub fn send_notification(
+    app: tauri::AppHandle,
+    title: String,
+    body: String,
+    urgency: Option<Urgency>,
+    category: Option<Category>,
+    thread: Option<String>,
+    target: Option<NotificationTarget>,
+) -> Result<(), String> {
+    app.state::<NotificationManager>().submit(
+        &app,
+        Notice {
+            category: category.unwrap_or(Category::Info),
+            thread: thread.unwrap_or_else(|| "app".to_string()),
+            item_id: None,
+            title,
+            body,
+            urgency: urgency.unwrap_or_default(),
+            agent_id: None,
+            gateway_id: None,
+            approval: None,
+            target,
+            history_id: None,
+        },
+    );
+    Ok(())
+}
+
+/// Report a backend error (e.g. a gateway that did not come back).
+pub fn notify_error(app: &AppHandle, gateway_id: &str, title: &str, body: &str) {
+    app.state::<NotificationManager>().submit(
+        app,
+        Notice {
+            category: Category::Error,
+            thread: format!("gateway:{}", gateway_id),
+            item_id: None,
+            title: title.to_string(),
+            body: body.to_string(),
+            urgency: Urgency::Normal,
+            agent_id: None,
+            gateway_id: Some(gateway_id.to_string()),
+            approval: None,
+            target: None,
+            history_id: None,
+        },
+    );
+}
+
+/// Report an app error not tied to a gateway (e.g. a shortcut that could
+/// not be registered).
+pub fn notify_app_error(app: &AppHandle, title: &str, body: &str) {
+    app.state::<NotificationManager>().submit(
+        app,
+        Notice {
+            category: Category::Error,
+            thread: "app".to_string(),
+            item_id: None,
+            title: title.to_string(),
+            body: body.to_string(),
+            urgency: Urgency::Normal,
+            agent_id: None,
+            gateway_id: None,
+            approval: None,
+            target: None,
+            history_id: None,
+        },
+    );
+}
+
+// ---- Exec Approval Notifications ------------------------------------------
+
+/// Decision sent with `exec.approval.resolve`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ApprovalDecision {
+    AllowOnce,
+    AllowAlways,
+    Deny,
+}
+
+impl ApprovalDecision {
+    pub const ALL: [ApprovalDecision; 3] = [
+        ApprovalDecision::AllowOnce,
+        ApprovalDecision::AllowAlways,
+        ApprovalDecision::Deny,
+    ];
+
+    /// Button label.
+    pub fn label(self) -> &'static str {
+        match self {
+            ApprovalDecision::AllowOnce => "Approve once",
+            ApprovalDecision::AllowAlways => "Always allow",
+            ApprovalDecision::Deny => "Deny",
+        }
+    }
+
+    /// Replaces the approval's notifications once it was answered here.
+    pub fn resolved_label(self) -> &'static str {
+        match self {
+            ApprovalDecision::AllowOnce => "Approved once",
+            ApprovalDecision::AllowAlways => "Always allowed",
+            ApprovalDecision::Deny => "Denied",
+        }
+    }
+
+    /// Wire value, also used as the notification action ID.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ApprovalDecision::AllowOnce => "allow-once",
+            ApprovalDecision::AllowAlways => "allow-always",
+            ApprovalDecision::Deny => "deny",
+        }
+    }
+
+    fn from_action(action: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|d| d.as_str() == action || d.label() == action)
+    }
+}
+
+/// Lifetime used when the approval request carries no timeout.
+const DEFAULT_APPROVAL_LIFETIME: Duration = Duration::from_secs(300);
+
+const APPROVAL_TITLE: &str = "Exec Approval Required";
+
+fn approval_body(approval: &PendingApproval) -> String {
+    let command = approval.command.as_deref().unwrap_or("(unknown command)");
+    match (&approval.agent_id, &approval.host) {
+        (Some(agent), Some(host)) => format!("{} on {}: {}", agent, host, command),
+        (Some(agent), None) => format!("{}: {}", agent, command),
+        (None, Some(host)) => format!("{}: {}", host, command),
+        (None, None) => command.to_string(),
+    }
+}
+
+/// Item ID of an approval's notifications. Approval IDs are only unique
+/// per gateway.
+fn approval_item(gateway_id: &str, approval_id: &str) -> String {
+    format!("{}:{}", gateway_id, approval_id)
+}
+
+/// Queue a notification for a new exec approval.
+pub fn notify_exec_approval(app: &AppHandle, approval: &PendingApproval) {
+    app.state::<NotificationManager>()
+        .submit(app, approval_notice(approval));
+}
+
+/// The notice for an exec approval, grouped by session, then agent, then
+/// gateway.
+fn approval_notice(approval: &PendingApproval) -> Notice {
+    let thread = match (&approval.session_key, &approval.agent_id) {
+        (Some(session), _) => format!("session:{}", session),
+        (None, Some(agent)) => format!("agent:{}", agent),
+        (None, None) => format!("gateway:{}", approval.gateway_id),
+    };
+    Notice {
+        category: Category::Approval,
+        thread,
+        item_id: Some(approval_item(&approval.gateway_id, &approval.id)),
+        title: APPROVAL_TITLE.to_string(),
+        body: approval_body(approval),
+        // Agents block until an approval is answered
+        urgency: Urgency::Critical,
+        agent_id: approval.agent_id.clone(),
+        gateway_id: Some(approval.gateway_id.clone()),
+        approval: Some(approval.clone()),
+        target: Some(NotificationTarget::Approval {
+            id: approval.id.clone(),
+            gateway_id: Some(approval.gateway_id.clone()),
+        }),
+        history_id: None,
+    }
+}
+
+/// An approval was resolved (here or by another client): replace its
+/// notification so stale buttons are not left on screen. One answered
+/// here is reported with the decision made here.
+pub fn exec_approval_resolved(
+    app: &AppHandle,
+    gateway_id: &str,
+    approval_id: &str,
+    decision: Option<&str>,
+) {
+    let resolution = match decision {
+        Some(decision) => format!("Resolved elsewhere: {}", decision),
+        None => "Resolved elsewhere".to_string(),
+    };
+    app.state::<NotificationManager>().resolve(
+        app,
+        &approval_item(gateway_id, approval_id),
+        &resolution,
+    );
+}
+
+/// Show an actionable approval notification. It lives as long as the
+/// approval's timeout; a button press resolves the approval on its gateway
+/// and a click opens it.
+fn show_exec_approval(app: &AppHandle, notice: &Notice, approval: &PendingApproval) {
+    let request = approval_request(notice, approval);
+    let approval = approval.clone();
+    show_and_wait(app, notice, request, move |app, interaction| {
+        let Interaction::Acted(action) = interaction else {
+            return;
+        };
+        if let Some(decision) = ApprovalDecision::from_action(action) {
+            spawn_resolve(app, approval, decision);
+        }
+    });
+}
+
+/// Answer a pending approval from outside its notification (e.g. the tray).
+pub fn answer_exec_approval(
+    app: &AppHandle,
+    gateway_id: &str,
+    approval_id: &str,
+    decision: ApprovalDecision,
+) {
+    let approval = app
+        .state::<GatewayManager>()
+        .pending_approvals()
+        .into_iter()
+        .find(|a| a.gateway_id == gateway_id && a.id == approval_id);
+    if let Some(approval) = approval {
+        spawn_resolve(app, approval, decision);
+    }
+}
+
+/// Resolve `approval` in the background, reporting failures.
+fn spawn_resolve(app: &AppHandle, approval: PendingApproval, decision: ApprovalDecision) {
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        if let Err(err) = resolve_approval(&app, &approval, decision).await {
+            notify_error(&app, &approval.gateway_id, "Approval failed", &err);
+        }
+    });
+}
+
+/// Send `exec.approval.resolve` if the approval is still pending. The
+/// answer is recorded first, since the resolution event can arrive before
+/// the response.
+async fn resolve_approval(
+    app: &AppHandle,
+    approval: &PendingApproval,
+    decision: ApprovalDecision,
+) -> Result<(), String> {
+    let manager = app.state::<GatewayManager>();
+    let session = manager
+        .session(&approval.gateway_id)
+        .ok_or_else(|| format!("Unknown gateway: {}", approval.gateway_id))?;
+    // Expired, or already resolved from another client
+    if !session
+        .pending_approvals()
+        .iter()
+        .any(|a| a.id == approval.id)
+    {
+        return Ok(());
+    }
+    let item_id = approval_item(&approval.gateway_id, &approval.id);
+    let notifications = app.state::<NotificationManager>();
+    notifications.answered(
+        &item_id,
+        Answer {
+            action: decision.as_str().to_string(),
+            resolution: decision.resolved_label().to_string(),
+        },
+    );
+    let params = serde_json::json!({ "id": approval.id, "decision": decision.as_str() });
+    let result = session
+        .request("exec.approval.resolve", Some(params), Priority::Interactive)
+        .await
+        .map(|_| ())
+        .map_err(|e| e.to_string());
+    if result.is_err() {
+        notifications.forget_answer(&item_id);
+    }
+    result
+}
+
+// ---- Delivery -------------------------------------------------------------
+
+/// How long a replacement ("resolved elsewhere") stays on screen.
+const REPLACEMENT_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// The platform request for a notice, without actions.
+fn request_for(notice: &Notice) -> NotificationRequest {
+    let mut request = NotificationRequest::new(&notice.title, &notice.body, notice.urgency);
+    request.icon = notice.category.icon().map(str::to_string);
+    request.clickable = notice.target.is_some();
+    request
+}
+
+/// The platform request for an approval notice, with a button per
+/// decision; Deny takes the close button.
+fn approval_request(notice: &Notice, approval: &PendingApproval) -> NotificationRequest {
+    let mut request = request_for(notice);
+    request.actions = ApprovalDecision::ALL
+        .into_iter()
+        .map(|decision| NotificationAction {
+            id: decision.as_str().to_string(),
+            label: decision.label().to_string(),
+            cancel: decision == ApprovalDecision::Deny,
+        })
+        .collect();
+    request.actions_label = Some("Approve".to_string());
+    request.timeout = Some(
+        approval
+            .timeout_ms
+            .map(Duration::from_millis)
+            .unwrap_or(DEFAULT_APPROVAL_LIFETIME),
+    );
+    request
+}
+
+/// The request updating a thread's summary in place.
+fn summary_request(
+    title: &str,
+    body: &str,
+    replaces: Option<NotificationId>,
+) -> NotificationRequest {
+    let mut request = NotificationRequest::new(title, body, Urgency::Normal);
+    request.replaces = replaces;
+    request
+}
+
+/// The request replacing an on-screen notification for a while.
+fn replacement_request(id: NotificationId, title: &str, body: &str) -> NotificationRequest {
+    let mut request = summary_request(title, body, Some(id));
+    request.timeout = Some(REPLACEMENT_TIMEOUT);
+    request
+}
+
+/// History outcome of an interaction; `None` when the app closed the
+/// notification itself.
+fn outcome(interaction: &Interaction) -> Option<Outcome> {
+    match interaction {
+        Interaction::Acted(action) => Some(Outcome::Acted {
+            action: action.clone(),
+        }),
+        Interaction::Clicked => Some(Outcome::Clicked),
+        Interaction::Closed(CloseReason::Expired) => Some(Outcome::Expired),
+        Interaction::Closed(CloseReason::Closed) => None,
+        Interaction::Closed(_) => Some(Outcome::Dismissed),
+    }
+}
+
+/// Put a single notice on screen. Returns its ID if it can be replaced.
+fn show(app: &AppHandle, notice: &Notice) -> Option<NotificationId> {
+    match (&notice.approval, &notice.target) {
+        (Some(approval), _) => {
+            show_exec_approval(app, notice, approval);
+            None
+        }
+        (None, Some(_)) => {
+            show_and_wait(app, notice, request_for(notice), |_, _| {});
+            None
+        }
+        (None, None) => show_now(app, &request_for(notice)),
+    }
+}
+
+/// Show a notice and wait for the response on its own thread, since the
+/// platform APIs block. The response is recorded in the history and a
+/// click opens the notice's target before `on_response` runs.
+fn show_and_wait(
+    app: &AppHandle,
+    notice: &Notice,
+    request: NotificationRequest,
+    on_response: impl FnOnce(&AppHandle, &Interaction) + Send + 'static,
+) {
+    let backend = app.state::<NotificationManager>().backend();
+    let notice = notice.clone();
+    let app = app.clone();
+
+    std::thread::spawn(move || {
+        let history = app.state::<NotificationHistory>();
+        let interaction = match await_response(
+            backend.as_ref(),
+            &AppInfo::of(&app),
+            &app.state::<NotificationManager>(),
+            &history,
+            &notice,
+            &request,
+        ) {
+            Ok(interaction) => interaction,
+            Err(_) => {
+                // No action support: fall back to a plain notification.
+                show_plain(&app, &request);
+                return;
+            }
+        };
+        if notice.history_id.is_some() {
+            history.changed(&app);
+        }
+        if let (Interaction::Clicked, Some(target)) = (&interaction, &notice.target) {
+            target::open(&app, target);
+        }
+        on_response(&app, &interaction);
+    });
+}
+
+/// Show `request` for `notice` on `backend` and block for the response.
+/// The banner's ID reaches the manager once it is on screen, so a
+/// resolution can replace it until an action closes it, and the response
+/// becomes the notice's history outcome (persisted by the caller).
+fn await_response(
+    backend: &dyn NotificationBackend,
+    app: &AppInfo,
+    manager: &NotificationManager,
+    history: &NotificationHistory,
+    notice: &Notice,
+    request: &NotificationRequest,
+) -> Result<Interaction, String> {
+    let mut on_shown = |id| {
+        if let Some(item_id) = &notice.item_id {
+            manager.set_shown_id(item_id, id);
+        }
+    };
+    let interaction = backend.show_and_wait(app, request, &mut on_shown)?;
+    if let (Interaction::Acted(_), Some(item_id)) = (&interaction, &notice.item_id) {
+        manager.banner_closed(item_id);
+    }
+    if let (Some(id), Some(outcome)) = (&notice.history_id, outcome(&interaction)) {
+        history.update_outcome(id, outcome);
+    }
+    Ok(interaction)
+}
+
+/// Show without waiting for a response, through the plugin if the backend
+/// cannot.
+fn show_now(app: &AppHandle, request: &NotificationRequest) -> Option<NotificationId> {
+    app.state::<NotificationManager>()
+        .backend()
+        .show(&AppInfo::of(app), request)
+        .unwrap_or_else(|_| show_plain(app, request))
+}
+
+/// Show (or update in place) a thread's summary notification.
+fn show_summary(
+    app: &AppHandle,
+    title: &str,
+    body: &str,
+    replaces: Option<NotificationId>,
+) -> Option<NotificationId> {
+    show_now(app, &summary_request(title, body, replaces))
+}
+
+/// Plain notification through tauri-plugin-notification (no ID).
+fn show_plain(app: &AppHandle, request: &NotificationRequest) -> Option<NotificationId> {
+    let mut builder = app
+        .notification()
+        .builder()
+        .title(&request.title)
+        .body(&request.body);
+    if request.urgency == Urgency::Critical {
+        builder = builder.sound("default");
+    }
+    let _ = builder.show();
+    None
+}
+
+// ---- Tests ----------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use backend::RecordingBackend;
+    use history::HistoryEntry;
+    use rules::FakeClock;
+    use std::sync::Arc;
+
+    fn approval() -> PendingApproval {
+        PendingApproval {
+            gateway_id: "home".to_string(),
+            id: "ap-1".to_string(),
+            command: Some("rm -rf build".to_string()),
+            agent_id: Some("main".to_string()),
+            host: None,
+            session_key: None,
+            timeout_ms: Some(30_000),
+            received_at_ms: 0,
+            payload: serde_json::json!({ "id": "ap-1" }),
+        }
+    }
+
+    fn answer(decision: ApprovalDecision) -> Answer {
+        Answer {
+            action: decision.as_str().to_string(),
+            resolution: decision.resolved_label().to_string(),
+        }
+    }
+
+    /// The bridge's state with the platform replaced by a recording fake.
+    struct Bridge {
+        backend: Arc<RecordingBackend>,
+        manager: NotificationManager,
+        history: NotificationHistory,
+    }
+
+    impl Bridge {
+        fn new() -> Self {
+            let backend = Arc::new(RecordingBackend::default());
+            Self {
+                manager: NotificationManager::new(
+                    Box::new(FakeClock::at("2026-10-16 12:00")),
+                    backend.clone(),
+                ),
+                backend,
+                history: NotificationHistory::default(),
+            }
+        }
+
+        /// Submit `notice` as delivered and show `request` for it, the user
+        /// answering with `interaction`.
+        fn respond(
+            &self,
+            mut notice: Notice,
+            request: &NotificationRequest,
+            interaction: Interaction,
+        ) -> (Interaction, HistoryEntry) {
+            let history_id = self.history.insert(&notice, Outcome::Delivered);
+            notice.history_id = Some(history_id.clone());
+            self.manager.mark_shown(&notice);
+            self.backend.respond(interaction);
+            let interaction = await_response(
+                self.backend.as_ref(),
+                &AppInfo::default(),
+                &self.manager,
+                &self.history,
+                &notice,
+                request,
+            )
+            .unwrap();
+            let entry = self
+                .history
+                .entries()
+                .into_iter()
+                .find(|e| e.id == history_id)
+                .unwrap();
+            (interaction, entry)
+        }
+    }
+
+    #[test]
+    fn approval_notice_is_critical_with_a_button_per_decision() {
+        let approval = approval();
+        let notice = approval_notice(&approval);
+        assert_eq!(notice.thread, "agent:main");
+        assert_eq!(notice.body, "main: rm -rf build");
+
+        let request = approval_request(&notice, &approval);
+        assert_eq!(request.urgency, Urgency::Critical);
+        assert!(request.clickable);
+        assert_eq!(request.timeout, Some(Duration::from_secs(30)));
+        let ids: Vec<_> = request.actions.iter().map(|a| a.id.as_str()).collect();
+        assert_eq!(ids, ["allow-once", "allow-always", "deny"]);
+        let cancel: Vec<_> = request
+            .actions
+            .iter()
+            .filter(|a| a.cancel)
+            .map(|a| a.id.as_str())
+            .collect();
+        assert_eq!(cancel, ["deny"]);
+    }
+
+    #[test]
+    fn approval_action_reaches_the_manager_and_history() {
+        let bridge = Bridge::new();
+        let approval = approval();
+        let notice = approval_notice(&approval);
+        let request = approval_request(&notice, &approval);
+
+        let (interaction, entry) =
+            bridge.respond(notice, &request, Interaction::Acted("deny".to_string()));
+        assert_eq!(interaction, Interaction::Acted("deny".to_string()));
+        assert_eq!(
+            ApprovalDecision::from_action("deny"),
+            Some(ApprovalDecision::Deny)
+        );
+        assert_eq!(
+            entry.outcome,
+            Outcome::Acted {
+                action: "deny".to_string()
+            }
+        );
+        assert!(entry.read);
+        assert_eq!(entry.actions.len(), ApprovalDecision::ALL.len());
+
+        // What went on screen is what the approval asked for
+        let shown = bridge.backend.requests();
+        assert_eq!(shown.len(), 1);
+        assert_eq!(shown[0].urgency, Urgency::Critical);
+        assert_eq!(shown[0].actions, request.actions);
+
+        // The action closed the banner: its resolution does not bring it
+        // back, and the history keeps the action
+        let item_id = approval_item("home", "ap-1");
+        bridge
+            .manager
+            .answered(&item_id, answer(ApprovalDecision::Deny));
+        let (replacements, outcome) = bridge.manager.settle(&item_id, "Resolved elsewhere");
+        assert!(replacements.is_empty());
+        assert_eq!(
+            outcome,
+            Outcome::Acted {
+                action: "deny".to_string()
+            }
+        );
+    }
+
+    #[test]
+    fn approval_answered_here_is_not_resolved_elsewhere() {
+        let bridge = Bridge::new();
+        let notice = approval_notice(&approval());
+        let item_id = notice.item_id.clone().unwrap();
+        bridge.manager.mark_shown(&notice);
+        bridge.manager.set_shown_id(&item_id, 4);
+
+        // Answered from the tray while the banner is on screen
+        bridge
+            .manager
+            .answered(&item_id, answer(ApprovalDecision::AllowOnce));
+        let (replacements, outcome) = bridge
+            .manager
+            .settle(&item_id, "Resolved elsewhere: allow-once");
+        assert_eq!(replacements.len(), 1);
+        assert_eq!(replacements[0].replaces, Some(4));
+        assert_eq!(replacements[0].title, "Approval resolved");
+        assert_eq!(replacements[0].body, "Approved once");
+        assert_eq!(
+            outcome,
+            Outcome::Acted {
+                action: "allow-once".to_string()
+            }
+        );
+
+        // Answered by another client
+        bridge.manager.mark_shown(&notice);
+        bridge.manager.set_shown_id(&item_id, 5);
+        let (replacements, outcome) = bridge.manager.settle(&item_id, "Resolved elsewhere: deny");
+        assert_eq!(replacements[0].body, "Resolved elsewhere: deny");
+        assert_eq!(outcome, Outcome::Resolved);
+
+        // A failed answer is forgotten
+        bridge
+            .manager
+            .answered(&item_id, answer(ApprovalDecision::Deny));
+        bridge.manager.forget_answer(&item_id);
+        let (_, outcome) = bridge.manager.settle(&item_id, "Resolved elsewhere");
+        assert_eq!(outcome, Outcome::Resolved);
+    }
+
+    #[test]
+    fn approvals_with_the_same_id_on_two_gateways_are_separate_items() {
+        let bridge = Bridge::new();
+        let home = approval();
+        let mut work = approval();
+        work.gateway_id = "work".to_string();
+        let home_notice = approval_notice(&home);
+        let work_notice = approval_notice(&work);
+        assert_ne!(home_notice.item_id, work_notice.item_id);
+
+        bridge.manager.mark_shown(&home_notice);
+        bridge
+            .manager
+            .set_shown_id(&approval_item("home", "ap-1"), 1);
+        bridge.manager.mark_shown(&work_notice);
+        bridge
+            .manager
+            .set_shown_id(&approval_item("work", "ap-1"), 2);
+
+        let (replacements, _) = bridge
+            .manager
+            .settle(&approval_item("work", "ap-1"), "Resolved elsewhere");
+        assert_eq!(replacements.len(), 1);
+        assert_eq!(replacements[0].replaces, Some(2));
+    }
+
+    #[test]
+    fn close_reasons_become_history_outcomes() {
+        let bridge = Bridge::new();
+        for (interaction, outcome, read) in [
+            (Interaction::Clicked, Outcome::Clicked, true),
+            (
+                Interaction::Closed(CloseReason::Expired),
+                Outcome::Expired,
+                false,
+            ),
+            (
+                Interaction::Closed(CloseReason::Dismissed),
+                Outcome::Dismissed,
+                false,
+            ),
+            (
+                Interaction::Closed(CloseReason::Unknown),
+                Outcome::Dismissed,
+                false,
+            ),
+            // Closed by the app itself: nothing happened to it
+            (
+                Interaction::Closed(CloseReason::Closed),
+                Outcome::Delivered,
+                false,
+            ),
+        ] {
+            let mut notice = approval_notice(&approval());
+            notice.approval = None;
+            let request = request_for(&notice);
+            let (answered, entry) = bridge.respond(notice, &request, interaction.clone());
+            assert_eq!(answered, interaction);
+            assert_eq!(entry.outcome, outcome, "{:?}", interaction);
+            assert_eq!(entry.read, read, "{:?}", interaction);
+        }
+    }
+
+    #[test]
+    fn replacement_updates_the_banner_in_place() {
+        let bridge = Bridge::new();
+        let app = AppInfo::default();
+        let id = bridge
+            .backend
+            .show(&app, &summary_request("2 approvals pending", "a\nb", None))
+            .unwrap()
+            .unwrap();
+
+        let request = replacement_request(id, "Approval resolved", "Approved on phone");
+        assert_eq!(request.replaces, Some(id));
+        assert_eq!(request.timeout, Some(REPLACEMENT_TIMEOUT));
+        assert_eq!(bridge.backend.show(&app, &request).unwrap(), Some(id));
+        assert_eq!(bridge.backend.requests()[1].body, "Approved on phone");
+    }
+}
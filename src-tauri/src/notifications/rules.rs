@@ -0,0 +1,462 @@
+// ---------------------------------------------------------------------------
+// Notification Rules (Quiet Hours & Focus)
+// ---------------------------------------------------------------------------
+//
+// User-defined rules that decide what happens to a notification before it
+// is shown. Each rule matches on urgency, category, agent, gateway and time
+// of day, and can suppress the notification, hold it for a digest, or
+// escalate it with a sound and repeats. Rules are evaluated in order; the
+// first enabled match wins. They live in tauri-plugin-store, and evaluation
+// takes the local time from a `Clock` so it can be driven by a fake one.
+
+use super::manager::{Category, NotificationManager};
+use chrono::{Datelike, Local, NaiveDateTime, NaiveTime, Timelike};
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Manager};
+use tauri_plugin_store::StoreExt;
+
+const RULES_STORE_PATH: &str = "notification-rules.json";
+const RULES_KEY: &str = "rules";
+
+// ---- Clock ----------------------------------------------------------------
+
+/// Source of local wall-clock time for rule evaluation.
+pub trait Clock: Send + Sync {
+    fn now(&self) -> NaiveDateTime;
+}
+
+/// The system's local time.
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> NaiveDateTime {
+        Local::now().naive_local()
+    }
+}
+
+/// A clock that stays where it is set; clones share the same time.
+#[cfg(test)]
+#[derive(Clone)]
+pub struct FakeClock(std::sync::Arc<std::sync::Mutex<NaiveDateTime>>);
+
+#[cfg(test)]
+impl FakeClock {
+    /// A clock reading `at`, as "YYYY-MM-DD HH:MM".
+    pub fn at(at: &str) -> Self {
+        Self(std::sync::Arc::new(std::sync::Mutex::new(Self::parse(at))))
+    }
+
+    pub fn set(&self, at: &str) {
+        *self.0.lock().unwrap() = Self::parse(at);
+    }
+
+    fn parse(at: &str) -> NaiveDateTime {
+        NaiveDateTime::parse_from_str(at, "%Y-%m-%d %H:%M").expect("YYYY-MM-DD HH:MM")
+    }
+}
+
+#[cfg(test)]
+impl Clock for FakeClock {
+    fn now(&self) -> NaiveDateTime {
+        *self.0.lock().unwrap()
+    }
+}
+
+// ---- Rule Model -----------------------------------------------------------
+
+/// Notification urgency, as passed to `send_notification`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Urgency {
+    Low,
+    #[default]
+    Normal,
+    Critical,
+}
+
+/// A daily time window in local time, e.g. 22:00–07:00. Windows that end
+/// before they start run past midnight.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TimeWindow {
+    /// "HH:MM"
+    pub start: String,
+    /// "HH:MM"
+    pub end: String,
+    /// Days the window starts on (0 = Monday … 6 = Sunday); empty = every day.
+    #[serde(default)]
+    pub days: Vec<u8>,
+}
+
+impl TimeWindow {
+    fn parse(value: &str) -> Result<NaiveTime, String> {
+        NaiveTime::parse_from_str(value, "%H:%M")
+            .map_err(|_| format!("Invalid time \"{}\" (expected HH:MM)", value))
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        Self::parse(&self.start)?;
+        Self::parse(&self.end)?;
+        match self.days.iter().find(|d| **d > 6) {
+            Some(day) => Err(format!("Invalid day {} (expected 0-6)", day)),
+            None => Ok(()),
+        }
+    }
+
+    fn contains(&self, now: NaiveDateTime) -> bool {
+        let (Ok(start), Ok(end)) = (Self::parse(&self.start), Self::parse(&self.end)) else {
+            return false;
+        };
+        let time = now.time().with_second(0).unwrap_or(now.time());
+        let weekday = now.weekday().num_days_from_monday() as u8;
+        let (inside, start_day) = if start <= end {
+            (start <= time && time < end, weekday)
+        } else if time >= start {
+            (true, weekday)
+        } else {
+            // After midnight: the window started the previous day
+            (time < end, (weekday + 6) % 7)
+        };
+        inside && (self.days.is_empty() || self.days.contains(&start_day))
+    }
+}
+
+/// Conditions a notification must meet; empty lists and `None` match all.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RuleConditions {
+    #[serde(default)]
+    pub urgency: Vec<Urgency>,
+    #[serde(default)]
+    pub category: Vec<Category>,
+    #[serde(default)]
+    pub agent_id: Option<String>,
+    #[serde(default)]
+    pub gateway_id: Option<String>,
+    #[serde(default)]
+    pub hours: Option<TimeWindow>,
+}
+
+/// What a matching rule does.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum RuleAction {
+    /// Drop the notification.
+    Suppress,
+    /// Hold it and deliver one digest once no digest rule matches.
+    Digest,
+    /// Play a sound and repeat until the item is resolved.
+    #[serde(rename_all = "camelCase")]
+    Escalate {
+        #[serde(default = "default_repeat_after_secs")]
+        repeat_after_secs: u64,
+        #[serde(default = "default_repeat_count")]
+        repeat_count: u32,
+    },
+}
+
+fn default_repeat_after_secs() -> u64 {
+    120
+}
+
+fn default_repeat_count() -> u32 {
+    2
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NotificationRule {
+    pub id: String,
+    pub name: String,
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+    #[serde(default)]
+    pub when: RuleConditions,
+    pub action: RuleAction,
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+/// The facts a rule can match on.
+#[derive(Debug, Clone, Copy)]
+pub struct RuleInput<'a> {
+    pub urgency: Urgency,
+    pub category: Category,
+    pub agent_id: Option<&'a str>,
+    pub gateway_id: Option<&'a str>,
+}
+
+impl NotificationRule {
+    fn validate(&self) -> Result<(), String> {
+        if self.id.trim().is_empty() {
+            return Err("Rule ID must not be empty".to_string());
+        }
+        if let Some(hours) = &self.when.hours {
+            hours
+                .validate()
+                .map_err(|e| format!("Rule \"{}\": {}", self.name, e))?;
+        }
+        Ok(())
+    }
+
+    fn matches(&self, input: &RuleInput<'_>, now: NaiveDateTime) -> bool {
+        let when = &self.when;
+        self.enabled
+            && (when.urgency.is_empty() || when.urgency.contains(&input.urgency))
+            && (when.category.is_empty() || when.category.contains(&input.category))
+            && when
+                .agent_id
+                .as_deref()
+                .is_none_or(|agent| input.agent_id == Some(agent))
+            && when
+                .gateway_id
+                .as_deref()
+                .is_none_or(|gateway| input.gateway_id == Some(gateway))
+            && when.hours.as_ref().is_none_or(|hours| hours.contains(now))
+    }
+}
+
+/// The first enabled rule matching `input` at local time `now`.
+pub fn evaluate<'r>(
+    rules: &'r [NotificationRule],
+    input: &RuleInput<'_>,
+    now: NaiveDateTime,
+) -> Option<&'r NotificationRule> {
+    rules.iter().find(|rule| rule.matches(input, now))
+}
+
+// ---- Persistence ----------------------------------------------------------
+
+fn load_rules(app: &AppHandle) -> Vec<NotificationRule> {
+    app.store(RULES_STORE_PATH)
+        .ok()
+        .and_then(|store| store.get(RULES_KEY))
+        .and_then(|value| serde_json::from_value(value).ok())
+        .unwrap_or_default()
+}
+
+fn save_rules(app: &AppHandle, rules: &[NotificationRule]) -> Result<(), String> {
+    let store = app.store(RULES_STORE_PATH).map_err(|e| e.to_string())?;
+    let value = serde_json::to_value(rules).map_err(|e| e.to_string())?;
+    store.set(RULES_KEY, value);
+    store.save().map_err(|e| e.to_string())
+}
+
+/// Load saved rules into the notification manager.
+pub fn restore(app: &AppHandle) {
+    app.state::<NotificationManager>()
+        .set_rules(load_rules(app));
+}
+
+// ---- Tauri Commands -------------------------------------------------------
+
+#[tauri::command]
+pub fn notification_rules(manager: tauri::State<'_, NotificationManager>) -> Vec<NotificationRule> {
+    manager.rules()
+}
+
+/// Replace the rule list (order matters: first match wins).
+#[tauri::command]
+pub fn notification_set_rules(
+    app: AppHandle,
+    manager: tauri::State<'_, NotificationManager>,
+    rules: Vec<NotificationRule>,
+) -> Result<(), String> {
+    for rule in &rules {
+        rule.validate()?;
+    }
+    save_rules(&app, &rules)?;
+    manager.set_rules(rules);
+    Ok(())
+}
+
+/// Deliver held digest notifications now, regardless of rules.
+#[tauri::command]
+pub fn notification_flush_digest(app: AppHandle, manager: tauri::State<'_, NotificationManager>) {
+    manager.flush_digest(&app, true);
+}
+
+// ---- Tests ----------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 2026-10-16 is a Friday (day 4).
+
+    fn window(start: &str, end: &str, days: &[u8]) -> TimeWindow {
+        TimeWindow {
+            start: start.to_string(),
+            end: end.to_string(),
+            days: days.to_vec(),
+        }
+    }
+
+    fn rule(id: &str, when: RuleConditions, action: RuleAction) -> NotificationRule {
+        NotificationRule {
+            id: id.to_string(),
+            name: id.to_string(),
+            enabled: true,
+            when,
+            action,
+        }
+    }
+
+    fn input(urgency: Urgency) -> RuleInput<'static> {
+        RuleInput {
+            urgency,
+            category: Category::Info,
+            agent_id: Some("main"),
+            gateway_id: Some("home"),
+        }
+    }
+
+    fn quiet_hours(start: &str, end: &str, days: &[u8]) -> NotificationRule {
+        rule(
+            "quiet",
+            RuleConditions {
+                hours: Some(window(start, end, days)),
+                ..RuleConditions::default()
+            },
+            RuleAction::Digest,
+        )
+    }
+
+    fn matched(rules: &[NotificationRule], urgency: Urgency, at: &str) -> Option<String> {
+        let clock = FakeClock::at(at);
+        evaluate(rules, &input(urgency), clock.now()).map(|rule| rule.id.clone())
+    }
+
+    #[test]
+    fn quiet_hours_wrap_past_midnight() {
+        let rules = [quiet_hours("22:00", "07:00", &[])];
+        assert_eq!(matched(&rules, Urgency::Normal, "2026-10-16 21:59"), None);
+        assert!(matched(&rules, Urgency::Normal, "2026-10-16 22:00").is_some());
+        assert!(matched(&rules, Urgency::Normal, "2026-10-16 23:30").is_some());
+        assert!(matched(&rules, Urgency::Normal, "2026-10-17 00:00").is_some());
+        assert!(matched(&rules, Urgency::Normal, "2026-10-17 06:59").is_some());
+        assert_eq!(matched(&rules, Urgency::Normal, "2026-10-17 07:00"), None);
+        assert_eq!(matched(&rules, Urgency::Normal, "2026-10-17 12:00"), None);
+    }
+
+    #[test]
+    fn same_day_window_excludes_its_end() {
+        let rules = [quiet_hours("09:00", "17:00", &[])];
+        assert_eq!(matched(&rules, Urgency::Normal, "2026-10-16 08:59"), None);
+        assert!(matched(&rules, Urgency::Normal, "2026-10-16 09:00").is_some());
+        assert!(matched(&rules, Urgency::Normal, "2026-10-16 16:59").is_some());
+        assert_eq!(matched(&rules, Urgency::Normal, "2026-10-16 17:00"), None);
+    }
+
+    #[test]
+    fn days_apply_to_the_day_a_window_starts() {
+        // Friday night only
+        let rules = [quiet_hours("22:00", "07:00", &[4])];
+        assert!(matched(&rules, Urgency::Normal, "2026-10-16 23:00").is_some());
+        // Saturday morning is still Friday's window
+        assert!(matched(&rules, Urgency::Normal, "2026-10-17 03:00").is_some());
+        // Friday morning belongs to Thursday's window
+        assert_eq!(matched(&rules, Urgency::Normal, "2026-10-16 03:00"), None);
+        assert_eq!(matched(&rules, Urgency::Normal, "2026-10-17 23:00"), None);
+    }
+
+    #[test]
+    fn weekday_window_skips_the_weekend() {
+        let rules = [quiet_hours("09:00", "17:00", &[0, 1, 2, 3, 4])];
+        assert!(matched(&rules, Urgency::Normal, "2026-10-16 10:00").is_some());
+        assert_eq!(matched(&rules, Urgency::Normal, "2026-10-17 10:00"), None);
+        assert_eq!(matched(&rules, Urgency::Normal, "2026-10-18 10:00"), None);
+        assert!(matched(&rules, Urgency::Normal, "2026-10-19 10:00").is_some());
+    }
+
+    #[test]
+    fn focus_mode_suppresses_all_but_critical() {
+        let mut focus = rule(
+            "focus",
+            RuleConditions {
+                urgency: vec![Urgency::Low, Urgency::Normal],
+                ..RuleConditions::default()
+            },
+            RuleAction::Suppress,
+        );
+        let rules = [focus.clone()];
+        assert_eq!(
+            matched(&rules, Urgency::Normal, "2026-10-16 10:00").as_deref(),
+            Some("focus")
+        );
+        assert_eq!(matched(&rules, Urgency::Critical, "2026-10-16 10:00"), None);
+
+        // Turning focus off lets everything through
+        focus.enabled = false;
+        assert_eq!(matched(&[focus], Urgency::Normal, "2026-10-16 10:00"), None);
+    }
+
+    #[test]
+    fn first_enabled_match_wins() {
+        let mut escalate = rule(
+            "escalate",
+            RuleConditions {
+                gateway_id: Some("home".to_string()),
+                ..RuleConditions::default()
+            },
+            RuleAction::Escalate {
+                repeat_after_secs: 60,
+                repeat_count: 1,
+            },
+        );
+        let quiet = quiet_hours("22:00", "07:00", &[]);
+        let rules = [escalate.clone(), quiet.clone()];
+        assert_eq!(
+            matched(&rules, Urgency::Normal, "2026-10-16 23:00").as_deref(),
+            Some("escalate")
+        );
+
+        escalate.enabled = false;
+        let rules = [escalate, quiet];
+        assert_eq!(
+            matched(&rules, Urgency::Normal, "2026-10-16 23:00").as_deref(),
+            Some("quiet")
+        );
+    }
+
+    #[test]
+    fn agent_and_gateway_conditions_must_match() {
+        let other_agent = rule(
+            "other-agent",
+            RuleConditions {
+                agent_id: Some("ops".to_string()),
+                ..RuleConditions::default()
+            },
+            RuleAction::Suppress,
+        );
+        let other_gateway = rule(
+            "other-gateway",
+            RuleConditions {
+                gateway_id: Some("lab".to_string()),
+                ..RuleConditions::default()
+            },
+            RuleAction::Suppress,
+        );
+        assert_eq!(
+            matched(
+                &[other_agent, other_gateway],
+                Urgency::Normal,
+                "2026-10-16 10:00"
+            ),
+            None
+        );
+    }
+
+    #[test]
+    fn validation_rejects_bad_times_and_days() {
+        assert!(quiet_hours("22:00", "07:00", &[0, 6]).validate().is_ok());
+        assert!(quiet_hours("25:00", "07:00", &[]).validate().is_err());
+        assert!(quiet_hours("22:00", "7am", &[]).validate().is_err());
+        assert!(quiet_hours("22:00", "07:00", &[7]).validate().is_err());
+        let mut unnamed = quiet_hours("22:00", "07:00", &[]);
+        unnamed.id = " ".to_string();
+        assert!(unnamed.validate().is_err());
+    }
+}
@@ -0,0 +1,58 @@
+// ---------------------------------------------------------------------------
+// Notification Targets (Click-Through Navigation)
+// ---------------------------------------------------------------------------
+//
+// A notification can point at the item it is about. Targets are kept in the
+// notification history; clicking a notification maps its target to an app
+// `Route` and navigates the main window there.
+
+use crate::navigation::{self, Route};
+use serde::{Deserialize, Serialize};
+use tauri::AppHandle;
+
+/// The item a notification is about.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum NotificationTarget {
+    #[serde(rename_all = "camelCase")]
+    Approval {
+        id: String,
+        #[serde(default)]
+        gateway_id: Option<String>,
+    },
+    Session {
+        key: String,
+    },
+    #[serde(rename_all = "camelCase")]
+    CronJob {
+        job_id: String,
+    },
+    /// A position in the gateway log stream.
+    LogCursor {
+        cursor: String,
+    },
+}
+
+impl NotificationTarget {
+    /// The view showing the target.
+    pub fn route(&self) -> Route {
+        match self.clone() {
+            NotificationTarget::Approval { id, gateway_id } => Route::Approvals {
+                id: Some(id),
+                gateway_id,
+            },
+            NotificationTarget::Session { key } => Route::Sessions { key: Some(key) },
+            NotificationTarget::CronJob { job_id } => Route::Cron {
+                job_id: Some(job_id),
+            },
+            NotificationTarget::LogCursor { cursor } => Route::Logs {
+                cursor: Some(cursor),
+            },
+        }
+    }
+}
+
+/// Show the main window and navigate it to `target`.
+pub fn open(app: &AppHandle, target: &NotificationTarget) {
+    let _ = navigation::navigate(app, target.route());
+}
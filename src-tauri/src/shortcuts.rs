@@ -0,0 +1,487 @@
+// ---------------------------------------------------------------------------
+// Global Shortcuts
+// ---------------------------------------------------------------------------
+//
+// System-wide hotkeys, registered from the backend so they keep working
+// while the window is hidden or its webview is not loaded. Showing the
+// window and the command palette have default accelerators; approving,
+// denying and aborting act without looking, so they are off until the user
+// binds them. The user's bindings live in tauri-plugin-store and override
+// the defaults, and a binding without an accelerator turns the action off.
+//
+// Approvals and chat turns held by a backend gateway session are answered
+// and aborted here. When no backend session holds one, the action is sent
+// to the main window, whose own gateway connection holds them.
+//
+// Bindings are checked before they are saved: invalid accelerators and two
+// actions on the same keys are rejected. Registration can still fail at
+// runtime (usually another app holds the keys); every binding's outcome is
+// kept as a `ShortcutStatus`, emitted to the webview, and failures are
+// reported as a notification.
+
+use crate::gateway::manager::GatewayManager;
+use crate::gateway::scheduler::Priority;
+use crate::navigation;
+use crate::notifications::{self, ApprovalDecision};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::str::FromStr;
+use std::sync::{Mutex, MutexGuard};
+use tauri::{AppHandle, Emitter, Manager};
+use tauri_plugin_global_shortcut::{GlobalShortcutExt, Shortcut, ShortcutState};
+use tauri_plugin_store::StoreExt;
+
+const SHORTCUTS_STORE_PATH: &str = "shortcuts.json";
+const BINDINGS_KEY: &str = "bindings";
+
+/// Event emitted with every binding's `ShortcutStatus` after registering.
+pub const EVENT_SHORTCUTS: &str = "shortcuts://status";
+
+/// Event emitted to the main window to open the command palette.
+pub const EVENT_COMMAND_PALETTE: &str = "app://command-palette";
+
+/// Event emitted to the main window with a `ShortcutAction` for its own
+/// gateway connection to carry out.
+pub const EVENT_SHORTCUT_ACTION: &str = "shortcuts://action";
+
+/// Label of the window shortcuts show and hide.
+const MAIN_WINDOW: &str = "main";
+
+// ---- Bindings -------------------------------------------------------------
+
+/// What a global shortcut does.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ShortcutAction {
+    /// Show and focus the main window, or hide it when it has focus.
+    ToggleWindow,
+    /// Approve (once) the oldest pending exec approval.
+    ApproveOldest,
+    /// Deny the oldest pending exec approval.
+    DenyOldest,
+    /// Show the main window with the command palette open.
+    CommandPalette,
+    /// Abort the running chat turns on the active gateway.
+    AbortChat,
+}
+
+impl ShortcutAction {
+    pub const ALL: [ShortcutAction; 5] = [
+        ShortcutAction::ToggleWindow,
+        ShortcutAction::ApproveOldest,
+        ShortcutAction::DenyOldest,
+        ShortcutAction::CommandPalette,
+        ShortcutAction::AbortChat,
+    ];
+
+    fn label(self) -> &'static str {
+        match self {
+            ShortcutAction::ToggleWindow => "Show/Hide Window",
+            ShortcutAction::ApproveOldest => "Approve Oldest",
+            ShortcutAction::DenyOldest => "Deny Oldest",
+            ShortcutAction::CommandPalette => "Command Palette",
+            ShortcutAction::AbortChat => "Abort Chat",
+        }
+    }
+
+    /// Keys bound when the user has saved no binding; `None` for actions
+    /// that are off until bound.
+    fn default_accelerator(self) -> Option<&'static str> {
+        match self {
+            ShortcutAction::ToggleWindow => Some("Ctrl+Shift+Space"),
+            ShortcutAction::CommandPalette => Some("Ctrl+Shift+K"),
+            ShortcutAction::ApproveOldest
+            | ShortcutAction::DenyOldest
+            | ShortcutAction::AbortChat => None,
+        }
+    }
+}
+
+/// The keys bound to an action.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ShortcutBinding {
+    pub action: ShortcutAction,
+    /// Accelerator such as "Ctrl+Shift+A"; `None` turns the action off.
+    pub accelerator: Option<String>,
+}
+
+/// Outcome of registering one binding.
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "state", rename_all = "camelCase")]
+pub enum BindingState {
+    Registered,
+    Disabled,
+    /// The accelerator does not parse.
+    Invalid {
+        reason: String,
+    },
+    /// An earlier binding already uses the same keys.
+    Conflict {
+        with: ShortcutAction,
+    },
+    /// The OS refused the keys, usually because another app holds them.
+    Failed {
+        reason: String,
+    },
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ShortcutStatus {
+    pub action: ShortcutAction,
+    pub accelerator: Option<String>,
+    #[serde(flatten)]
+    pub state: BindingState,
+}
+
+/// Every action with its saved binding, or its default when none is saved.
+fn with_defaults(saved: Vec<ShortcutBinding>) -> Vec<ShortcutBinding> {
+    ShortcutAction::ALL
+        .into_iter()
+        .map(|action| {
+            saved
+                .iter()
+                .find(|b| b.action == action)
+                .cloned()
+                .unwrap_or_else(|| ShortcutBinding {
+                    action,
+                    accelerator: action.default_accelerator().map(str::to_string),
+                })
+        })
+        .collect()
+}
+
+/// Parse every accelerator and find the bindings that share keys. Returns
+/// each binding's parsed shortcut, or the state it fails with.
+fn check(bindings: &[ShortcutBinding]) -> Vec<Result<Shortcut, BindingState>> {
+    let mut checked: Vec<Result<Shortcut, BindingState>> = Vec::new();
+    for binding in bindings {
+        let Some(accelerator) = &binding.accelerator else {
+            checked.push(Err(BindingState::Disabled));
+            continue;
+        };
+        let result = match Shortcut::from_str(accelerator) {
+            Err(e) => Err(BindingState::Invalid {
+                reason: e.to_string(),
+            }),
+            Ok(shortcut) => {
+                let taken_by = bindings
+                    .iter()
+                    .zip(&checked)
+                    .find(|(_, other)| matches!(other, Ok(s) if s.id() == shortcut.id()));
+                match taken_by {
+                    Some((other, _)) => Err(BindingState::Conflict { with: other.action }),
+                    None => Ok(shortcut),
+                }
+            }
+        };
+        checked.push(result);
+    }
+    checked
+}
+
+// ---- Manager --------------------------------------------------------------
+
+/// The registered bindings and their outcomes.
+#[derive(Default)]
+pub struct ShortcutManager {
+    statuses: Mutex<Vec<ShortcutStatus>>,
+    /// Held while bindings are being registered, so two updates cannot
+    /// interleave.
+    applying: Mutex<()>,
+}
+
+impl ShortcutManager {
+    fn lock(&self) -> MutexGuard<'_, Vec<ShortcutStatus>> {
+        self.statuses.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    pub fn statuses(&self) -> Vec<ShortcutStatus> {
+        self.lock().clone()
+    }
+}
+
+/// Replace every registered shortcut with `bindings`. Blocks on the main
+/// thread (where the platform registers hotkeys), so it must not be called
+/// from it.
+fn apply(app: &AppHandle, bindings: &[ShortcutBinding]) -> Vec<ShortcutStatus> {
+    let manager = app.state::<ShortcutManager>();
+    let _applying = manager.applying.lock().unwrap_or_else(|e| e.into_inner());
+    let global_shortcut = app.global_shortcut();
+    let _ = global_shortcut.unregister_all();
+
+    let statuses: Vec<_> = bindings
+        .iter()
+        .zip(check(bindings))
+        .map(|(binding, checked)| {
+            let action = binding.action;
+            let state = match checked {
+                Err(state) => state,
+                Ok(shortcut) => match global_shortcut.on_shortcut(shortcut, move |app, _, event| {
+                    if event.state == ShortcutState::Pressed {
+                        run(app, action);
+                    }
+                }) {
+                    Ok(()) => BindingState::Registered,
+                    Err(e) => BindingState::Failed {
+                        reason: e.to_string(),
+                    },
+                },
+            };
+            ShortcutStatus {
+                action,
+                accelerator: binding.accelerator.clone(),
+                state,
+            }
+        })
+        .collect();
+
+    let problems: Vec<String> = statuses
+        .iter()
+        .filter_map(|status| {
+            let accelerator = status.accelerator.as_deref().unwrap_or_default();
+            match &status.state {
+                BindingState::Registered | BindingState::Disabled => None,
+                BindingState::Invalid { reason } => Some(format!("{}: {}", accelerator, reason)),
+                BindingState::Conflict { with } => Some(format!(
+                    "{}: already bound to {}",
+                    accelerator,
+                    with.label()
+                )),
+                BindingState::Failed { reason } => Some(format!("{}: {}", accelerator, reason)),
+            }
+        })
+        .collect();
+    if !problems.is_empty() {
+        notifications::notify_app_error(
+            app,
+            "Some global shortcuts are not available",
+            &problems.join("\n"),
+        );
+    }
+
+    *manager.lock() = statuses.clone();
+    let _ = app.emit(EVENT_SHORTCUTS, &statuses);
+    statuses
+}
+
+// ---- Actions --------------------------------------------------------------
+
+fn run(app: &AppHandle, action: ShortcutAction) {
+    match action {
+        ShortcutAction::ToggleWindow => toggle_window(app),
+        ShortcutAction::ApproveOldest => answer_oldest(app, action, ApprovalDecision::AllowOnce),
+        ShortcutAction::DenyOldest => answer_oldest(app, action, ApprovalDecision::Deny),
+        ShortcutAction::CommandPalette => {
+            navigation::show_main_window(app);
+            let _ = app.emit_to(MAIN_WINDOW, EVENT_COMMAND_PALETTE, ());
+        }
+        ShortcutAction::AbortChat => abort_chat(app, action),
+    }
+}
+
+/// Hand `action` to the main window, for approvals and turns on its own
+/// gateway connection.
+fn forward(app: &AppHandle, action: ShortcutAction) {
+    let _ = app.emit_to(MAIN_WINDOW, EVENT_SHORTCUT_ACTION, action);
+}
+
+fn toggle_window(app: &AppHandle) {
+    let Some(window) = app.get_webview_window(MAIN_WINDOW) else {
+        return;
+    };
+    let shown = window.is_visible().unwrap_or(false) && window.is_focused().unwrap_or(false);
+    if shown {
+        let _ = window.hide();
+    } else {
+        navigation::show_main_window(app);
+    }
+}
+
+/// Answer the oldest approval a backend session holds, or have the main
+/// window answer its oldest when none does.
+fn answer_oldest(app: &AppHandle, action: ShortcutAction, decision: ApprovalDecision) {
+    let oldest = app
+        .state::<GatewayManager>()
+        .pending_approvals()
+        .into_iter()
+        .next();
+    match oldest {
+        Some(approval) => {
+            notifications::answer_exec_approval(app, &approval.gateway_id, &approval.id, decision)
+        }
+        None => forward(app, action),
+    }
+}
+
+/// Abort the running turns on the active gateway, or on every gateway when
+/// none is active. With no backend turn running, the main window aborts
+/// its own.
+fn abort_chat(app: &AppHandle, action: ShortcutAction) {
+    let gateways = app.state::<GatewayManager>();
+    let active = gateways.active_id();
+    let runs: Vec<_> = gateways
+        .active_runs()
+        .into_iter()
+        .filter(|run| active.as_deref().is_none_or(|id| run.gateway_id == id))
+        .collect();
+    if runs.is_empty() {
+        forward(app, action);
+        return;
+    }
+    for run in runs {
+        let app = app.clone();
+        tauri::async_runtime::spawn(async move {
+            let Some(session) = app.state::<GatewayManager>().session(&run.gateway_id) else {
+                return;
+            };
+            let result = session
+                .request(
+                    "chat.abort",
+                    Some(json!({ "sessionKey": run.session_key })),
+                    Priority::Interactive,
+                )
+                .await;
+            if let Err(e) = result {
+                notifications::notify_error(&app, &run.gateway_id, "Abort failed", &e.to_string());
+            }
+        });
+    }
+}
+
+// ---- Persistence ----------------------------------------------------------
+
+fn load_bindings(app: &AppHandle) -> Vec<ShortcutBinding> {
+    let saved = app
+        .store(SHORTCUTS_STORE_PATH)
+        .ok()
+        .and_then(|store| store.get(BINDINGS_KEY))
+        .and_then(|value| serde_json::from_value(value).ok())
+        .unwrap_or_default();
+    with_defaults(saved)
+}
+
+fn save_bindings(app: &AppHandle, bindings: &[ShortcutBinding]) -> Result<(), String> {
+    let store = app.store(SHORTCUTS_STORE_PATH).map_err(|e| e.to_string())?;
+    let value = serde_json::to_value(bindings).map_err(|e| e.to_string())?;
+    store.set(BINDINGS_KEY, value);
+    store.save().map_err(|e| e.to_string())
+}
+
+/// Register the saved bindings in the background.
+pub fn restore(app: &AppHandle) {
+    let app = app.clone();
+    tauri::async_runtime::spawn_blocking(move || {
+        apply(&app, &load_bindings(&app));
+    });
+}
+
+// ---- Tauri Commands -------------------------------------------------------
+
+/// Every action's binding and whether it is registered.
+#[tauri::command]
+pub fn shortcut_bindings(manager: tauri::State<'_, ShortcutManager>) -> Vec<ShortcutStatus> {
+    manager.statuses()
+}
+
+/// Save and register new bindings. Actions left out keep their default
+/// keys, or stay off. Invalid accelerators and keys bound to two actions are rejected
+/// without saving; keys the OS refuses are reported in the returned status.
+#[tauri::command]
+pub async fn shortcut_set_bindings(
+    app: AppHandle,
+    bindings: Vec<ShortcutBinding>,
+) -> Result<Vec<ShortcutStatus>, String> {
+    for (i, binding) in bindings.iter().enumerate() {
+        if bindings[..i].iter().any(|b| b.action == binding.action) {
+            return Err(format!(
+                "{} is bound more than once",
+                binding.action.label()
+            ));
+        }
+    }
+    let bindings = with_defaults(bindings);
+    for (binding, checked) in bindings.iter().zip(check(&bindings)) {
+        let accelerator = binding.accelerator.as_deref().unwrap_or_default();
+        match checked {
+            Err(BindingState::Invalid { reason }) => {
+                return Err(format!("Invalid shortcut {}: {}", accelerator, reason))
+            }
+            Err(BindingState::Conflict { with }) => {
+                return Err(format!(
+                    "{} is bound to both {} and {}",
+                    accelerator,
+                    with.label(),
+                    binding.action.label()
+                ))
+            }
+            _ => {}
+        }
+    }
+    save_bindings(&app, &bindings)?;
+    tauri::async_runtime::spawn_blocking(move || apply(&app, &bindings))
+        .await
+        .map_err(|e| e.to_string())
+}
+
+// ---- Tests ----------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn binding(action: ShortcutAction, accelerator: Option<&str>) -> ShortcutBinding {
+        ShortcutBinding {
+            action,
+            accelerator: accelerator.map(str::to_string),
+        }
+    }
+
+    #[test]
+    fn actions_that_act_without_looking_are_off_by_default() {
+        let bindings = with_defaults(Vec::new());
+        let bound: Vec<_> = bindings
+            .iter()
+            .filter(|b| b.accelerator.is_some())
+            .map(|b| b.action)
+            .collect();
+        assert_eq!(
+            bound,
+            [ShortcutAction::ToggleWindow, ShortcutAction::CommandPalette]
+        );
+        assert!(check(&bindings)
+            .iter()
+            .all(|checked| matches!(checked, Ok(_) | Err(BindingState::Disabled))));
+    }
+
+    #[test]
+    fn saved_bindings_override_defaults() {
+        let bindings = with_defaults(vec![
+            binding(ShortcutAction::ApproveOldest, Some("Ctrl+Shift+A")),
+            binding(ShortcutAction::ToggleWindow, None),
+        ]);
+        assert_eq!(bindings[0], binding(ShortcutAction::ToggleWindow, None));
+        assert_eq!(
+            bindings[1],
+            binding(ShortcutAction::ApproveOldest, Some("Ctrl+Shift+A"))
+        );
+    }
+
+    #[test]
+    fn check_rejects_invalid_and_shared_keys() {
+        let checked = check(&[
+            binding(ShortcutAction::ToggleWindow, Some("Ctrl+Shift+Space")),
+            binding(ShortcutAction::ApproveOldest, Some("Ctrl+Nope")),
+            binding(ShortcutAction::DenyOldest, Some("Shift+Ctrl+Space")),
+        ]);
+        assert!(checked[0].is_ok());
+        assert!(matches!(checked[1], Err(BindingState::Invalid { .. })));
+        assert!(matches!(
+            checked[2],
+            Err(BindingState::Conflict {
+                with: ShortcutAction::ToggleWindow
+            })
+        ));
+    }
+}
@@ -0,0 +1,163 @@
+// ---------------------------------------------------------------------------
+// Tray Actions
+// ---------------------------------------------------------------------------
+// What a tray menu item does. Every actionable entry in the menu model
+// carries a `TrayAction`, so menu events are dispatched by looking up the
+// clicked entry rather than by matching fixed IDs. Gateway actions run in
+// the background and report failures as error notifications; forgetting a
+// device token asks for confirmation first.
+
+use super::activity;
+use crate::gateway::manager::{self, GatewayManager};
+use crate::gateway::scheduler::Priority;
+use crate::navigation::{self, Route};
+use crate::notifications::{self, ApprovalDecision};
+use serde_json::{json, Value};
+use tauri::{AppHandle, Manager};
+use tauri_plugin_dialog::{DialogExt, MessageDialogButtons, MessageDialogKind};
+
+/// Lines kept when compacting a session, as in the Sessions view.
+const COMPACT_MAX_LINES: u32 = 1000;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TrayAction {
+    ShowWindow,
+    /// Show the window at a view.
+    Navigate(Route),
+    /// `chat.abort` for the session's running turn.
+    AbortTurn {
+        gateway_id: String,
+        session_key: String,
+    },
+    CompactSession {
+        gateway_id: String,
+        key: String,
+    },
+    AnswerApproval {
+        gateway_id: String,
+        approval_id: String,
+        decision: ApprovalDecision,
+    },
+    SetActiveGateway {
+        gateway_id: String,
+    },
+    ConnectGateway {
+        gateway_id: String,
+    },
+    DisconnectGateway {
+        gateway_id: String,
+    },
+    /// Delete the gateway's device token after confirming.
+    ForgetDeviceToken {
+        gateway_id: String,
+    },
+    Quit,
+}
+
+/// Carry out `action`.
+pub fn dispatch(app: &AppHandle, action: TrayAction) {
+    match action {
+        TrayAction::ShowWindow => navigation::show_main_window(app),
+        TrayAction::Navigate(route) => {
+            let _ = navigation::navigate(app, route);
+        }
+        TrayAction::AbortTurn {
+            gateway_id,
+            session_key,
+        } => spawn_request(
+            app,
+            gateway_id,
+            "chat.abort",
+            json!({ "sessionKey": session_key }),
+            "Abort failed",
+        ),
+        TrayAction::CompactSession { gateway_id, key } => spawn_request(
+            app,
+            gateway_id,
+            "sessions.compact",
+            json!({ "key": key, "maxLines": COMPACT_MAX_LINES }),
+            "Compact failed",
+        ),
+        TrayAction::AnswerApproval {
+            gateway_id,
+            approval_id,
+            decision,
+        } => notifications::answer_exec_approval(app, &gateway_id, &approval_id, decision),
+        TrayAction::SetActiveGateway { gateway_id } => {
+            if let Err(err) = manager::set_active(app, Some(&gateway_id)) {
+                notifications::notify_error(app, &gateway_id, "Switching gateway failed", &err);
+            }
+        }
+        TrayAction::ConnectGateway { gateway_id } => {
+            if let Some(session) = app.state::<GatewayManager>().session(&gateway_id) {
+                session.start(app);
+            }
+        }
+        TrayAction::DisconnectGateway { gateway_id } => {
+            if let Some(session) = app.state::<GatewayManager>().session(&gateway_id) {
+                session.stop();
+            }
+        }
+        TrayAction::ForgetDeviceToken { gateway_id } => confirm_forget(app, gateway_id),
+        TrayAction::Quit => app.exit(0),
+    }
+}
+
+/// Ask before forgetting a device token: the device has to be paired with
+/// the gateway again afterwards.
+fn confirm_forget(app: &AppHandle, gateway_id: String) {
+    let Some(session) = app.state::<GatewayManager>().session(&gateway_id) else {
+        return;
+    };
+    let name = session.profile().name;
+    app.dialog()
+        .message(format!(
+            "Forget this device's token for {}? The gateway disconnects, and \
+             the device has to be paired again to reconnect.",
+            name
+        ))
+        .title("Forget Device Token")
+        .kind(MessageDialogKind::Warning)
+        .buttons(MessageDialogButtons::OkCancelCustom(
+            "Forget".to_string(),
+            "Cancel".to_string(),
+        ))
+        .show({
+            let app = app.clone();
+            move |confirmed| {
+                if !confirmed {
+                    return;
+                }
+                if let Err(err) = manager::forget_device_token(&app, &gateway_id) {
+                    notifications::notify_error(&app, &gateway_id, "Forget token failed", &err);
+                }
+            }
+        });
+}
+
+/// Send `method` on a gateway in the background, then refresh the tray's
+/// sessions and agents. Failures are reported under `failure_title`.
+fn spawn_request(
+    app: &AppHandle,
+    gateway_id: String,
+    method: &'static str,
+    params: Value,
+    failure_title: &'static str,
+) {
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        let session = app.state::<GatewayManager>().session(&gateway_id);
+        let result = match session {
+            Some(session) => session
+                .request(method, Some(params), Priority::Interactive)
+                .await
+                .map(|_| ())
+                .map_err(|e| e.to_string()),
+            None => Err(format!("Unknown gateway: {}", gateway_id)),
+        };
+        match result {
+            Ok(()) => activity::spawn_poll(&app),
+            Err(err) => notifications::notify_error(&app, &gateway_id, failure_title, &err),
+        }
+    });
+}
@@ -0,0 +1,310 @@
+// ---------------------------------------------------------------------------
+// Tray Activity
+// ---------------------------------------------------------------------------
+// Recent sessions and agents for the tray's quick actions, fetched from every
+// connected backend gateway with `sessions.list` and `agents.list`. Polled
+// on a background timer and after a quick action changes something, at
+// background priority so it never delays interactive requests.
+
+use crate::gateway::manager::GatewayManager;
+use crate::gateway::scheduler::Priority;
+use crate::gateway::session::{ConnectionState, GatewayProfile, GatewaySession};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+
+/// How often sessions and agents are fetched.
+const POLL_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Sessions shown in the tray, most recently active first.
+const RECENT_SESSIONS: usize = 8;
+
+/// A chat session shown in the tray.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RecentSession {
+    pub gateway_id: String,
+    pub gateway_name: String,
+    pub key: String,
+    pub title: String,
+    pub agent_id: Option<String>,
+    pub last_active: Option<i64>,
+}
+
+/// An agent shown in the tray.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TrayAgent {
+    pub gateway_id: String,
+    pub gateway_name: String,
+    pub id: String,
+    pub name: String,
+}
+
+// ---- Wire Shapes ----------------------------------------------------------
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SessionListItem {
+    key: String,
+    label: Option<String>,
+    derived_title: Option<String>,
+    agent_id: Option<String>,
+    last_active: Option<i64>,
+}
+
+#[derive(Debug, Deserialize)]
+struct SessionsListResult {
+    #[serde(default)]
+    sessions: Vec<SessionListItem>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct AgentIdentity {
+    name: Option<String>,
+    emoji: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct AgentListItem {
+    id: String,
+    name: Option<String>,
+    #[serde(default)]
+    identity: Option<AgentIdentity>,
+}
+
+#[derive(Debug, Deserialize)]
+struct AgentsListResult {
+    #[serde(default)]
+    agents: Vec<AgentListItem>,
+}
+
+// ---- Fetching -------------------------------------------------------------
+
+async fn request<T: serde::de::DeserializeOwned>(
+    session: &GatewaySession,
+    method: &str,
+    params: Value,
+) -> Result<T, String> {
+    let value = session
+        .request(method, Some(params), Priority::Background)
+        .await
+        .map_err(|e| e.to_string())?;
+    serde_json::from_value(value).map_err(|e| e.to_string())
+}
+
+/// Sessions and agents of one connected gateway.
+async fn fetch(session: &GatewaySession) -> Result<(Vec<RecentSession>, Vec<TrayAgent>), String> {
+    let profile = session.profile();
+    let sessions: SessionsListResult = request(
+        session,
+        "sessions.list",
+        json!({ "limit": RECENT_SESSIONS, "includeDerivedTitles": true }),
+    )
+    .await?;
+    let agents: AgentsListResult = request(session, "agents.list", json!({})).await?;
+    Ok((
+        recent_sessions(&profile, sessions),
+        tray_agents(&profile, agents),
+    ))
+}
+
+/// Sessions titled by label, then derived title, then key.
+fn recent_sessions(profile: &GatewayProfile, list: SessionsListResult) -> Vec<RecentSession> {
+    list.sessions
+        .into_iter()
+        .map(|s| RecentSession {
+            gateway_id: profile.id.clone(),
+            gateway_name: profile.name.clone(),
+            title: s
+                .label
+                .or(s.derived_title)
+                .filter(|t| !t.trim().is_empty())
+                .unwrap_or_else(|| s.key.clone()),
+            key: s.key,
+            agent_id: s.agent_id,
+            last_active: s.last_active,
+        })
+        .collect()
+}
+
+/// Agents named by identity, then configured name, then ID, with the
+/// identity's emoji in front.
+fn tray_agents(profile: &GatewayProfile, list: AgentsListResult) -> Vec<TrayAgent> {
+    list.agents
+        .into_iter()
+        .map(|a| {
+            let identity = a.identity.unwrap_or_default();
+            let name = identity.name.or(a.name).unwrap_or_else(|| a.id.clone());
+            TrayAgent {
+                gateway_id: profile.id.clone(),
+                gateway_name: profile.name.clone(),
+                name: match identity.emoji {
+                    Some(emoji) => format!("{} {}", emoji, name),
+                    None => name,
+                },
+                id: a.id,
+            }
+        })
+        .collect()
+}
+
+/// Order what every gateway returned for the tray: the most recently active
+/// sessions across gateways, and agents by name.
+fn arrange(recent: &mut Vec<RecentSession>, agents: &mut [TrayAgent]) {
+    recent.sort_by_key(|s| std::cmp::Reverse(s.last_active));
+    recent.truncate(RECENT_SESSIONS);
+    agents.sort_by_key(|a| a.name.to_lowercase());
+}
+
+/// Fetch from every connected gateway and update the tray. A gateway that
+/// fails to answer is left out until the next poll.
+pub async fn poll(app: &AppHandle) {
+    let Some(manager) = app.try_state::<GatewayManager>() else {
+        return;
+    };
+    let sessions = manager.sessions();
+    let mut recent = Vec::new();
+    let mut agents = Vec::new();
+    for session in sessions
+        .iter()
+        .filter(|s| s.state() == ConnectionState::Connected)
+    {
+        if let Ok((s, a)) = fetch(session).await {
+            recent.extend(s);
+            agents.extend(a);
+        }
+    }
+    arrange(&mut recent, &mut agents);
+    super::set_activity(app, recent, agents);
+}
+
+/// Poll once in the background, e.g. after a quick action.
+pub fn spawn_poll(app: &AppHandle) {
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move { poll(&app).await });
+}
+
+/// Poll every `POLL_INTERVAL` for the lifetime of the app.
+pub fn spawn_poll_task(app: &AppHandle) {
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        loop {
+            poll(&app).await;
+            tokio::time::sleep(POLL_INTERVAL).await;
+        }
+    });
+}
+
+// ---- Tests ----------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn profile(id: &str) -> GatewayProfile {
+        GatewayProfile::new(
+            id.to_string(),
+            format!("Gateway {}", id),
+            format!("wss://{}.example", id),
+        )
+    }
+
+    fn session(gateway_id: &str, key: &str, last_active: Option<i64>) -> RecentSession {
+        RecentSession {
+            gateway_id: gateway_id.to_string(),
+            gateway_name: format!("Gateway {}", gateway_id),
+            key: key.to_string(),
+            title: key.to_string(),
+            agent_id: None,
+            last_active,
+        }
+    }
+
+    #[test]
+    fn sessions_are_titled_by_label_then_derived_title_then_key() {
+        let list: SessionsListResult = serde_json::from_value(json!({
+            "sessions": [
+                { "key": "a", "label": "Deploy", "derivedTitle": "Ignored", "agentId": "ops", "lastActive": 3 },
+                { "key": "b", "derivedTitle": "Fix the build" },
+                { "key": "c", "label": "  " },
+            ]
+        }))
+        .unwrap();
+
+        let sessions = recent_sessions(&profile("home"), list);
+        let titles: Vec<_> = sessions.iter().map(|s| s.title.as_str()).collect();
+        assert_eq!(titles, vec!["Deploy", "Fix the build", "c"]);
+        assert_eq!(sessions[0].gateway_name, "Gateway home");
+        assert_eq!(sessions[0].agent_id.as_deref(), Some("ops"));
+        assert_eq!(sessions[0].last_active, Some(3));
+    }
+
+    #[test]
+    fn agents_are_named_by_identity_then_name_then_id() {
+        let list: AgentsListResult = serde_json::from_value(json!({
+            "agents": [
+                { "id": "main", "name": "Main", "identity": { "name": "Ember", "emoji": "🔥" } },
+                { "id": "ops", "name": "Operations" },
+                { "id": "bare", "identity": {} },
+            ]
+        }))
+        .unwrap();
+
+        let names: Vec<_> = tray_agents(&profile("home"), list)
+            .into_iter()
+            .map(|a| (a.id, a.name))
+            .collect();
+        assert_eq!(
+            names,
+            vec![
+                ("main".to_string(), "🔥 Ember".to_string()),
+                ("ops".to_string(), "Operations".to_string()),
+                ("bare".to_string(), "bare".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn missing_lists_are_empty() {
+        let sessions: SessionsListResult = serde_json::from_value(json!({})).unwrap();
+        let agents: AgentsListResult = serde_json::from_value(json!({})).unwrap();
+        assert!(recent_sessions(&profile("home"), sessions).is_empty());
+        assert!(tray_agents(&profile("home"), agents).is_empty());
+    }
+
+    #[test]
+    fn most_recent_sessions_across_gateways_come_first() {
+        let mut recent: Vec<_> = (0..RECENT_SESSIONS as i64 + 2)
+            .map(|n| {
+                session(
+                    if n % 2 == 0 { "home" } else { "lab" },
+                    &format!("s{}", n),
+                    Some(n),
+                )
+            })
+            .collect();
+        recent.push(session("home", "never", None));
+        let mut agents = Vec::new();
+
+        arrange(&mut recent, &mut agents);
+        assert_eq!(recent.len(), RECENT_SESSIONS);
+        assert_eq!(recent[0].key, format!("s{}", RECENT_SESSIONS + 1));
+        assert_eq!(recent[1].gateway_id, "home");
+        assert!(recent.iter().all(|s| s.key != "never" && s.key != "s0"));
+    }
+
+    #[test]
+    fn agents_are_sorted_by_name_ignoring_case() {
+        let agent = |name: &str| TrayAgent {
+            gateway_id: "home".to_string(),
+            gateway_name: "Gateway home".to_string(),
+            id: name.to_lowercase(),
+            name: name.to_string(),
+        };
+        let mut agents = vec![agent("zed"), agent("Alpha"), agent("beta")];
+
+        arrange(&mut Vec::new(), &mut agents);
+        let names: Vec<_> = agents.iter().map(|a| a.name.as_str()).collect();
+        assert_eq!(names, vec!["Alpha", "beta", "zed"]);
+    }
+}
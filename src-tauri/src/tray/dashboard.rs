@@ -0,0 +1,365 @@
+// ---------------------------------------------------------------------------
+// Tray Dashboard
+// ---------------------------------------------------------------------------
+// Glanceable numbers for the tray menu: today's tokens and estimated cost,
+// active sessions, unhealthy agents and the next cron run. Computed from
+// every connected backend gateway on a timer. Gateway activity (a turn
+// ending, an agent's health changing) asks for an early refresh, throttled
+// so a busy gateway cannot turn into a stream of usage requests.
+
+use crate::gateway::manager::GatewayManager;
+use crate::gateway::scheduler::Priority;
+use crate::gateway::session::{ConnectionState, GatewaySession, UnhealthyAgent};
+use chrono::{Local, TimeZone};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+use tokio::sync::Notify;
+
+/// How often the numbers are refreshed without any activity.
+const REFRESH_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Minimum time between two refreshes, however much activity there is.
+const MIN_REFRESH_SPACING: Duration = Duration::from_secs(10);
+
+/// Unhealthy agents named in the menu row before it says "+N".
+const NAMED_AGENTS: usize = 2;
+
+/// The next enabled cron job to run, across gateways.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NextCronRun {
+    pub name: String,
+    pub at_ms: i64,
+}
+
+/// The dashboard numbers across every connected gateway.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Dashboard {
+    pub tokens_today: u64,
+    pub cost_today_usd: f64,
+    pub active_sessions: u64,
+    pub unhealthy_agents: Vec<UnhealthyAgent>,
+    pub next_cron: Option<NextCronRun>,
+}
+
+impl Dashboard {
+    /// Menu rows as (ID suffix, label).
+    pub fn rows(&self) -> Vec<(&'static str, String)> {
+        let mut rows = vec![
+            (
+                "usage",
+                format!(
+                    "Today: {} tokens · ${:.2}",
+                    format_tokens(self.tokens_today),
+                    self.cost_today_usd
+                ),
+            ),
+            (
+                "sessions",
+                format!("Active Sessions: {}", self.active_sessions),
+            ),
+        ];
+        let agents = &self.unhealthy_agents;
+        rows.push((
+            "health",
+            if agents.is_empty() {
+                "All Agents Healthy".to_string()
+            } else {
+                let mut names = agents
+                    .iter()
+                    .take(NAMED_AGENTS)
+                    .map(|a| a.agent_id.as_str())
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                if agents.len() > NAMED_AGENTS {
+                    names.push_str(&format!(" +{}", agents.len() - NAMED_AGENTS));
+                }
+                format!("Unhealthy Agents: {} ({})", agents.len(), names)
+            },
+        ));
+        rows.push((
+            "cron",
+            match &self.next_cron {
+                Some(next) => format!("Next Cron: {} at {}", next.name, format_time(next.at_ms)),
+                None => "No Cron Runs Scheduled".to_string(),
+            },
+        ));
+        rows
+    }
+}
+
+/// Token count in k / M, e.g. "1.2M".
+fn format_tokens(tokens: u64) -> String {
+    match tokens {
+        0..=999 => tokens.to_string(),
+        1_000..=999_999 => format!("{:.1}k", tokens as f64 / 1_000.0),
+        _ => format!("{:.1}M", tokens as f64 / 1_000_000.0),
+    }
+}
+
+/// Local time of day, with the weekday when it is not today.
+fn format_time(at_ms: i64) -> String {
+    let Some(at) = Local.timestamp_millis_opt(at_ms).single() else {
+        return "?".to_string();
+    };
+    if at.date_naive() == Local::now().date_naive() {
+        at.format("%H:%M").to_string()
+    } else {
+        at.format("%a %H:%M").to_string()
+    }
+}
+
+// ---- Wire Shapes ----------------------------------------------------------
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct UsageResult {
+    total_input_tokens: Option<u64>,
+    total_output_tokens: Option<u64>,
+    total_tokens: Option<u64>,
+    estimated_cost_usd: Option<f64>,
+    active_sessions: Option<u64>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CronJobState {
+    next_run_at_ms: Option<i64>,
+}
+
+#[derive(Debug, Deserialize)]
+struct CronJob {
+    name: Option<String>,
+    id: String,
+    #[serde(default)]
+    enabled: bool,
+    #[serde(default)]
+    state: CronJobState,
+}
+
+#[derive(Debug, Deserialize)]
+struct CronListResult {
+    #[serde(default)]
+    jobs: Vec<CronJob>,
+}
+
+// ---- Refreshing -----------------------------------------------------------
+
+/// Wakes the refresh task early.
+#[derive(Default)]
+pub struct DashboardWake(Notify);
+
+async fn request<T: serde::de::DeserializeOwned>(
+    session: &GatewaySession,
+    method: &str,
+    params: Value,
+) -> Result<T, String> {
+    let value = session
+        .request(method, Some(params), Priority::Background)
+        .await
+        .map_err(|e| e.to_string())?;
+    serde_json::from_value(value).map_err(|e| e.to_string())
+}
+
+/// Today's usage and the cron jobs of one connected gateway.
+async fn fetch(session: &GatewaySession) -> Result<(UsageResult, CronListResult), String> {
+    let today = Local::now().format("%Y-%m-%d").to_string();
+    let usage = request(
+        session,
+        "sessions.usage",
+        json!({ "startDate": today, "endDate": today }),
+    )
+    .await?;
+    let cron = request(session, "cron.list", json!({})).await?;
+    Ok((usage, cron))
+}
+
+/// Compute the dashboard from every connected gateway. A gateway that fails
+/// to answer is left out.
+async fn compute(manager: &GatewayManager) -> Option<Dashboard> {
+    let sessions: Vec<_> = manager
+        .sessions()
+        .into_iter()
+        .filter(|s| s.state() == ConnectionState::Connected)
+        .collect();
+    let mut answers = Vec::with_capacity(sessions.len());
+    for session in &sessions {
+        if let Ok(answer) = fetch(session).await {
+            answers.push(answer);
+        }
+    }
+    combine(answers, manager.unhealthy_agents())
+}
+
+/// Add up the gateways' answers; `None` when none answered, rather than
+/// showing zeros as if nothing had been used.
+fn combine(
+    answers: Vec<(UsageResult, CronListResult)>,
+    unhealthy_agents: Vec<UnhealthyAgent>,
+) -> Option<Dashboard> {
+    if answers.is_empty() {
+        return None;
+    }
+    let mut dashboard = Dashboard {
+        tokens_today: 0,
+        cost_today_usd: 0.0,
+        active_sessions: 0,
+        unhealthy_agents,
+        next_cron: None,
+    };
+    for (usage, cron) in answers {
+        dashboard.tokens_today += usage.total_tokens.unwrap_or(
+            usage.total_input_tokens.unwrap_or(0) + usage.total_output_tokens.unwrap_or(0),
+        );
+        dashboard.cost_today_usd += usage.estimated_cost_usd.unwrap_or(0.0);
+        dashboard.active_sessions += usage.active_sessions.unwrap_or(0);
+
+        let next = cron
+            .jobs
+            .into_iter()
+            .filter(|job| job.enabled)
+            .filter_map(|job| {
+                let at_ms = job.state.next_run_at_ms?;
+                Some(NextCronRun {
+                    name: job.name.unwrap_or(job.id),
+                    at_ms,
+                })
+            })
+            .min_by_key(|next| next.at_ms);
+        if let Some(next) = next {
+            if dashboard
+                .next_cron
+                .as_ref()
+                .is_none_or(|current| next.at_ms < current.at_ms)
+            {
+                dashboard.next_cron = Some(next);
+            }
+        }
+    }
+    Some(dashboard)
+}
+
+/// Ask for an early refresh; throttled by the refresh task.
+pub fn wake(app: &AppHandle) {
+    if let Some(wake) = app.try_state::<DashboardWake>() {
+        wake.0.notify_one();
+    }
+}
+
+/// Refresh every `REFRESH_INTERVAL`, or sooner when woken, for the
+/// lifetime of the app.
+pub fn spawn_refresh_task(app: &AppHandle) {
+    app.manage(DashboardWake::default());
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        loop {
+            if let Some(manager) = app.try_state::<GatewayManager>() {
+                let dashboard = compute(&manager).await;
+                super::set_dashboard(&app, dashboard);
+            }
+            tokio::time::sleep(MIN_REFRESH_SPACING).await;
+            let wake = app.state::<DashboardWake>();
+            let _ = tokio::time::timeout(
+                REFRESH_INTERVAL.saturating_sub(MIN_REFRESH_SPACING),
+                wake.0.notified(),
+            )
+            .await;
+        }
+    });
+}
+
+// ---- Tests ----------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn answer(usage: Value, jobs: Value) -> (UsageResult, CronListResult) {
+        (
+            serde_json::from_value(usage).unwrap(),
+            serde_json::from_value(json!({ "jobs": jobs })).unwrap(),
+        )
+    }
+
+    fn unhealthy(agent_id: &str) -> UnhealthyAgent {
+        UnhealthyAgent {
+            gateway_id: "home".to_string(),
+            agent_id: agent_id.to_string(),
+            status: "error".to_string(),
+        }
+    }
+
+    #[test]
+    fn hidden_when_no_gateway_answered() {
+        // Connected gateways that all failed to answer, or none connected
+        assert_eq!(combine(Vec::new(), vec![unhealthy("main")]), None);
+    }
+
+    #[test]
+    fn answers_are_added_up_across_gateways() {
+        let dashboard = combine(
+            vec![
+                answer(
+                    json!({ "totalTokens": 1_500, "estimatedCostUsd": 0.25, "activeSessions": 2 }),
+                    json!([
+                        { "id": "nightly", "enabled": true, "state": { "nextRunAtMs": 3_000 } },
+                        { "id": "off", "enabled": false, "state": { "nextRunAtMs": 1_000 } },
+                    ]),
+                ),
+                // Without a total, input and output are counted
+                answer(
+                    json!({ "totalInputTokens": 400, "totalOutputTokens": 100 }),
+                    json!([
+                        { "id": "j2", "name": "Digest", "enabled": true, "state": { "nextRunAtMs": 2_000 } },
+                        { "id": "j3", "enabled": true },
+                    ]),
+                ),
+            ],
+            Vec::new(),
+        )
+        .unwrap();
+
+        assert_eq!(dashboard.tokens_today, 2_000);
+        assert_eq!(dashboard.cost_today_usd, 0.25);
+        assert_eq!(dashboard.active_sessions, 2);
+        assert_eq!(
+            dashboard.next_cron,
+            Some(NextCronRun {
+                name: "Digest".to_string(),
+                at_ms: 2_000,
+            })
+        );
+    }
+
+    #[test]
+    fn a_gateway_with_nothing_used_still_shows_zeros() {
+        let dashboard = combine(vec![answer(json!({}), json!([]))], Vec::new()).unwrap();
+        let rows: Vec<_> = dashboard
+            .rows()
+            .into_iter()
+            .map(|(_, label)| label)
+            .collect();
+        assert_eq!(
+            rows,
+            [
+                "Today: 0 tokens · $0.00",
+                "Active Sessions: 0",
+                "All Agents Healthy",
+                "No Cron Runs Scheduled",
+            ]
+        );
+    }
+
+    #[test]
+    fn rows_name_a_few_unhealthy_agents() {
+        let dashboard = combine(
+            vec![answer(json!({ "totalTokens": 1_234_567 }), json!([]))],
+            vec![unhealthy("a"), unhealthy("b"), unhealthy("c")],
+        )
+        .unwrap();
+        let rows = dashboard.rows();
+        assert_eq!(rows[0].1, "Today: 1.2M tokens · $0.00");
+        assert_eq!(rows[2].1, "Unhealthy Agents: 3 (a, b +1)");
+    }
+}
@@ -0,0 +1,35 @@
+// ---------------------------------------------------------------------------
+// Tray Icon
+// ---------------------------------------------------------------------------
+// Platform-specific icon handling. The macOS menu bar shows the flame
+// template image, which follows the light or dark menu bar; Linux
+// (AppIndicator) and Windows show the colored flame. Either way the icon
+// is rendered at runtime from an `IconSpec` (see `render`).
+
+use super::render::{self, IconSpec};
+use tauri::{image::Image, tray::TrayIconBuilder, Wry};
+
+/// Whether the tray icon is a template image.
+const TEMPLATE: bool = cfg!(target_os = "macos");
+
+/// Flame artwork the icon is drawn over.
+fn base() -> tauri::Result<Image<'static>> {
+    if TEMPLATE {
+        Image::from_bytes(include_bytes!("../../icons/tray-icon@2x.png"))
+    } else {
+        Image::from_bytes(include_bytes!("../../icons/32x32.png"))
+    }
+}
+
+/// The tray icon for `spec`.
+pub fn render(spec: IconSpec) -> tauri::Result<Image<'static>> {
+    let base = base()?;
+    let (width, height) = (base.width(), base.height());
+    let rgba = render::render(base.rgba(), width, height, TEMPLATE, spec);
+    Ok(Image::new_owned(rgba, width, height))
+}
+
+/// Set the tray icon for `spec` on `builder`.
+pub fn apply(builder: TrayIconBuilder<Wry>, spec: IconSpec) -> tauri::Result<TrayIconBuilder<Wry>> {
+    Ok(builder.icon(render(spec)?).icon_as_template(TEMPLATE))
+}
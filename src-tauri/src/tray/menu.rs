@@ -0,0 +1,1186 @@
+// ---------------------------------------------------------------------------
+// Tray Menu Model
+// ---------------------------------------------------------------------------
+// The tray menu and tooltip as plain data, derived from `TrayStatus` without
+// touching the platform menu API, so the model can be checked without a
+// display. Actionable entries carry the `TrayAction` they trigger. `build`
+// turns the model into a Tauri menu, and `update` patches a shown menu in
+// place when only labels or submenu contents changed (e.g. approvals
+// arriving and resolving, dashboard numbers ticking) so open menus are not
+// torn down.
+
+use super::actions::TrayAction;
+use super::activity::{RecentSession, TrayAgent};
+use super::dashboard::Dashboard;
+use super::render::{BadgeKind, IconSpec, IconState};
+use crate::gateway::session::{ActiveRun, ConnectionState, PendingApproval, SessionSummary};
+use crate::keychain;
+use crate::navigation::Route;
+use crate::notifications::ApprovalDecision;
+use tauri::{
+    menu::{
+        CheckMenuItemBuilder, IsMenuItem, Menu, MenuItemBuilder, MenuItemKind, PredefinedMenuItem,
+        Submenu,
+    },
+    AppHandle, Wry,
+};
+
+/// ID of the pending approvals submenu.
+pub const APPROVALS_MENU_ID: &str = "approvals";
+
+/// ID of the gateway switcher submenu.
+pub const ACTIVE_GATEWAY_MENU_ID: &str = "active_gateway";
+
+/// ID of the recent sessions submenu.
+pub const SESSIONS_MENU_ID: &str = "sessions";
+
+/// ID of the agents submenu.
+pub const AGENTS_MENU_ID: &str = "agents";
+
+/// Characters of an approval's command shown in its menu label.
+const COMMAND_LABEL_CHARS: usize = 40;
+
+/// Characters of a session title shown in its menu label.
+const SESSION_LABEL_CHARS: usize = 40;
+
+/// Characters of a gateway's last error shown in its submenu.
+const ERROR_LABEL_CHARS: usize = 60;
+
+/// Latest status inputs. The frontend reports its own connection through
+/// `update_tray_status`; backend gateway sessions report through
+/// `set_gateway_summaries`. Both are folded into one menu.
+#[derive(Debug, Clone)]
+pub struct TrayStatus {
+    pub connection_status: String,
+    pub pending_approvals: u32,
+    /// IDs of the frontend's pending approvals, so those a backend session
+    /// also holds are counted once.
+    pub approval_ids: Vec<String>,
+    pub gateways: Vec<SessionSummary>,
+    /// Pending approvals across the backend gateways, oldest first.
+    pub approvals: Vec<PendingApproval>,
+    pub unread_notifications: usize,
+    /// Recent sessions across the backend gateways, most recent first.
+    pub sessions: Vec<RecentSession>,
+    pub agents: Vec<TrayAgent>,
+    /// Chat turns in progress across the backend gateways.
+    pub runs: Vec<ActiveRun>,
+    /// Usage and health numbers; `None` until computed or while no backend
+    /// gateway is connected and answering.
+    pub dashboard: Option<Dashboard>,
+}
+
+impl Default for TrayStatus {
+    fn default() -> Self {
+        Self {
+            connection_status: "Disconnected".to_string(),
+            pending_approvals: 0,
+            approval_ids: Vec::new(),
+            gateways: Vec::new(),
+            approvals: Vec::new(),
+            unread_notifications: 0,
+            sessions: Vec::new(),
+            agents: Vec::new(),
+            runs: Vec::new(),
+            dashboard: None,
+        }
+    }
+}
+
+impl TrayStatus {
+    /// Pending approvals across the frontend connection and every backend
+    /// gateway. One the frontend and a backend session both hold counts once.
+    pub fn total_pending(&self) -> u32 {
+        let shared = self
+            .approval_ids
+            .iter()
+            .filter(|id| self.approvals.iter().any(|a| &a.id == *id))
+            .count() as u32;
+        self.pending_approvals.saturating_sub(shared)
+            + self
+                .gateways
+                .iter()
+                .map(|g| g.pending_approvals as u32)
+                .sum::<u32>()
+    }
+
+    fn is_running(&self, gateway_id: &str, session_key: &str) -> bool {
+        self.runs
+            .iter()
+            .any(|r| r.gateway_id == gateway_id && r.session_key == session_key)
+    }
+}
+
+/// One row of the tray menu.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MenuEntry {
+    /// Disabled when it has no action.
+    Item {
+        id: String,
+        label: String,
+        action: Option<TrayAction>,
+    },
+    /// A check item. The platform toggles it on click; `checked` is put
+    /// back afterwards so the model stays the source of truth.
+    Check {
+        id: String,
+        label: String,
+        checked: bool,
+        action: Option<TrayAction>,
+    },
+    Separator,
+    Submenu {
+        id: String,
+        label: String,
+        entries: Vec<MenuEntry>,
+    },
+}
+
+impl MenuEntry {
+    /// An informational row.
+    fn label(id: impl Into<String>, label: impl Into<String>) -> Self {
+        MenuEntry::Item {
+            id: id.into(),
+            label: label.into(),
+            action: None,
+        }
+    }
+
+    fn action(id: impl Into<String>, label: impl Into<String>, action: TrayAction) -> Self {
+        MenuEntry::Item {
+            id: id.into(),
+            label: label.into(),
+            action: Some(action),
+        }
+    }
+
+    /// Stable ID; separators have none.
+    fn id(&self) -> Option<&str> {
+        match self {
+            MenuEntry::Item { id, .. }
+            | MenuEntry::Check { id, .. }
+            | MenuEntry::Submenu { id, .. } => Some(id),
+            MenuEntry::Separator => None,
+        }
+    }
+}
+
+/// Status dot shown next to a connection label.
+pub fn status_dot(connection_status: &str) -> &'static str {
+    match connection_status {
+        "Connected" => "●",
+        "Connecting" | "Reconnecting" => "◐",
+        _ => "○",
+    }
+}
+
+/// The tray menu for the current status.
+pub fn model(status: &TrayStatus) -> Vec<MenuEntry> {
+    let connection_status = status.connection_status.as_str();
+    let pending_approvals = status.total_pending();
+
+    let mut entries = vec![MenuEntry::label(
+        "status",
+        format!("{} {}", status_dot(connection_status), connection_status),
+    )];
+
+    // Gateway switcher, then one submenu per backend gateway session with
+    // its status and connection actions
+    if !status.gateways.is_empty() {
+        entries.push(MenuEntry::Submenu {
+            id: ACTIVE_GATEWAY_MENU_ID.to_string(),
+            label: "Active Gateway".to_string(),
+            entries: status
+                .gateways
+                .iter()
+                .map(|gateway| MenuEntry::Check {
+                    id: format!("active:{}", gateway.id),
+                    label: gateway.name.clone(),
+                    checked: gateway.active,
+                    action: Some(TrayAction::SetActiveGateway {
+                        gateway_id: gateway.id.clone(),
+                    }),
+                })
+                .collect(),
+        });
+    }
+    entries.extend(status.gateways.iter().map(gateway_entry));
+
+    // Backend approvals can be answered from a submenu; approvals only the
+    // frontend knows about open the window instead
+    let open_approvals = || {
+        TrayAction::Navigate(Route::Approvals {
+            id: None,
+            gateway_id: None,
+        })
+    };
+    if status.approvals.is_empty() {
+        entries.push(if pending_approvals > 0 {
+            MenuEntry::action(
+                "nav_approvals",
+                format!("Pending Approvals ({})", pending_approvals),
+                open_approvals(),
+            )
+        } else {
+            MenuEntry::label("nav_approvals", "No Pending Approvals")
+        });
+    } else {
+        let mut approvals: Vec<_> = status.approvals.iter().map(approval_entry).collect();
+        approvals.extend([
+            MenuEntry::Separator,
+            MenuEntry::action("nav_approvals", "Open Approvals…", open_approvals()),
+        ]);
+        entries.push(MenuEntry::Submenu {
+            id: APPROVALS_MENU_ID.to_string(),
+            label: format!("Pending Approvals ({})", pending_approvals),
+            entries: approvals,
+        });
+    }
+    if status.unread_notifications > 0 {
+        entries.push(MenuEntry::label(
+            "unread_notifications",
+            format!("Unread Notifications ({})", status.unread_notifications),
+        ));
+    }
+
+    if let Some(dashboard) = &status.dashboard {
+        entries.push(MenuEntry::Separator);
+        entries.extend(
+            dashboard
+                .rows()
+                .into_iter()
+                .map(|(id, label)| MenuEntry::label(format!("dashboard:{}", id), label)),
+        );
+    }
+
+    // Quick actions on live sessions and agents
+    let sessions = session_entries(status);
+    if !sessions.is_empty() || !status.agents.is_empty() {
+        entries.push(MenuEntry::Separator);
+    }
+    if !sessions.is_empty() {
+        let running = status.runs.len();
+        entries.push(MenuEntry::Submenu {
+            id: SESSIONS_MENU_ID.to_string(),
+            label: if running > 0 {
+                format!("Recent Sessions ({} running)", running)
+            } else {
+                "Recent Sessions".to_string()
+            },
+            entries: sessions,
+        });
+    }
+    if !status.agents.is_empty() {
+        entries.push(MenuEntry::Submenu {
+            id: AGENTS_MENU_ID.to_string(),
+            label: "Agents".to_string(),
+            entries: status
+                .agents
+                .iter()
+                .map(|agent| agent_entry(status, agent))
+                .collect(),
+        });
+    }
+
+    entries.extend([
+        MenuEntry::Separator,
+        MenuEntry::action("show_window", "Show Window", TrayAction::ShowWindow),
+        MenuEntry::action(
+            "nav_chat",
+            "Chat Room",
+            TrayAction::Navigate(Route::DemonChat),
+        ),
+        MenuEntry::action(
+            "nav_health",
+            "Demon Health",
+            TrayAction::Navigate(Route::DemonHealth),
+        ),
+        MenuEntry::Separator,
+        MenuEntry::action("quit", "Quit The Fireplace", TrayAction::Quit),
+    ]);
+    entries
+}
+
+/// `text` cut to `chars` characters with an ellipsis.
+fn truncate(text: &str, chars: usize) -> String {
+    if text.chars().count() > chars {
+        let truncated: String = text.chars().take(chars - 1).collect();
+        format!("{}…", truncated)
+    } else {
+        text.to_string()
+    }
+}
+
+/// Recent sessions, plus running ones that are not recent, each with Open
+/// Chat, Abort Running Turn and Compact Session entries. Labels name the
+/// gateway when there is more than one.
+fn session_entries(status: &TrayStatus) -> Vec<MenuEntry> {
+    let multiple_gateways = status.gateways.len() > 1;
+    let mut sessions: Vec<(&str, &str, &str)> = status
+        .sessions
+        .iter()
+        .map(|s| (s.gateway_id.as_str(), s.key.as_str(), s.title.as_str()))
+        .collect();
+    for run in &status.runs {
+        let listed = sessions
+            .iter()
+            .any(|(gateway_id, key, _)| *gateway_id == run.gateway_id && *key == run.session_key);
+        if !listed {
+            sessions.push((&run.gateway_id, &run.session_key, &run.session_key));
+        }
+    }
+
+    sessions
+        .into_iter()
+        .map(|(gateway_id, key, title)| {
+            let running = status.is_running(gateway_id, key);
+            let mut label = truncate(title, SESSION_LABEL_CHARS);
+            if multiple_gateways {
+                let gateway = status.gateways.iter().find(|g| g.id == gateway_id);
+                if let Some(gateway) = gateway {
+                    label.push_str(&format!(" — {}", gateway.name));
+                }
+            }
+            if running {
+                label = format!("▶ {}", label);
+            }
+            let id = format!("session:{}:{}", gateway_id, key);
+            let abort = MenuEntry::Item {
+                id: format!("{}:abort", id),
+                label: "Abort Running Turn".to_string(),
+                action: running.then(|| TrayAction::AbortTurn {
+                    gateway_id: gateway_id.to_string(),
+                    session_key: key.to_string(),
+                }),
+            };
+            MenuEntry::Submenu {
+                entries: vec![
+                    MenuEntry::action(
+                        format!("{}:open", id),
+                        "Open Chat",
+                        TrayAction::Navigate(Route::Chat {
+                            session_key: Some(key.to_string()),
+                            gateway_id: Some(gateway_id.to_string()),
+                        }),
+                    ),
+                    abort,
+                    MenuEntry::action(
+                        format!("{}:compact", id),
+                        "Compact Session",
+                        TrayAction::CompactSession {
+                            gateway_id: gateway_id.to_string(),
+                            key: key.to_string(),
+                        },
+                    ),
+                ],
+                id,
+                label,
+            }
+        })
+        .collect()
+}
+
+/// One agent, opening its most recent listed session; disabled when none
+/// is listed.
+fn agent_entry(status: &TrayStatus, agent: &TrayAgent) -> MenuEntry {
+    let mut label = agent.name.clone();
+    if status.gateways.len() > 1 {
+        label.push_str(&format!(" — {}", agent.gateway_name));
+    }
+    let latest = status.sessions.iter().find(|s| {
+        s.gateway_id == agent.gateway_id && s.agent_id.as_deref() == Some(agent.id.as_str())
+    });
+    MenuEntry::Item {
+        id: format!("agent:{}:{}", agent.gateway_id, agent.id),
+        label,
+        action: latest.map(|s| {
+            TrayAction::Navigate(Route::Chat {
+                session_key: Some(s.key.clone()),
+                gateway_id: Some(s.gateway_id.clone()),
+            })
+        }),
+    }
+}
+
+/// One gateway: "● name — N pending" with its connection details and
+/// Connect, Disconnect and (where there is a keychain) Forget Device Token
+/// entries.
+fn gateway_entry(gateway: &SessionSummary) -> MenuEntry {
+    let dot = status_dot(gateway.state.label());
+    let label = if gateway.pending_approvals > 0 {
+        format!(
+            "{} {} — {} pending",
+            dot, gateway.name, gateway.pending_approvals
+        )
+    } else {
+        format!("{} {}", dot, gateway.name)
+    };
+
+    let mut detail = gateway.state.label().to_string();
+    if let Some(version) = &gateway.server_version {
+        detail.push_str(&format!(" · v{}", version));
+    }
+    if let Some(latency_ms) = gateway.latency_ms {
+        detail.push_str(&format!(" · {:.0} ms", latency_ms));
+    }
+    let id = format!("gateway:{}", gateway.id);
+    let mut entries = vec![MenuEntry::label(format!("{}:detail", id), detail)];
+    if let Some(error) = &gateway.last_error {
+        entries.push(MenuEntry::label(
+            format!("{}:error", id),
+            truncate(error, ERROR_LABEL_CHARS),
+        ));
+    }
+
+    let gateway_id = || gateway.id.clone();
+    let disconnected = gateway.state == ConnectionState::Disconnected;
+    entries.extend([
+        MenuEntry::Separator,
+        MenuEntry::Item {
+            id: format!("{}:connect", id),
+            label: "Connect".to_string(),
+            action: disconnected.then(|| TrayAction::ConnectGateway {
+                gateway_id: gateway_id(),
+            }),
+        },
+        MenuEntry::Item {
+            id: format!("{}:disconnect", id),
+            label: "Disconnect".to_string(),
+            action: (!disconnected).then(|| TrayAction::DisconnectGateway {
+                gateway_id: gateway_id(),
+            }),
+        },
+    ]);
+    // Device tokens live in the keychain, so there is nothing to forget
+    // where there is none
+    if keychain::is_supported() {
+        entries.extend([
+            MenuEntry::Separator,
+            MenuEntry::action(
+                format!("{}:forget", id),
+                "Forget Device Token…",
+                TrayAction::ForgetDeviceToken {
+                    gateway_id: gateway_id(),
+                },
+            ),
+        ]);
+    }
+    MenuEntry::Submenu { id, label, entries }
+}
+
+/// One approval: "command — agent on host" with Approve and Deny entries.
+/// Approval IDs are only unique per gateway, so entry IDs carry both.
+fn approval_entry(approval: &PendingApproval) -> MenuEntry {
+    let command = approval.command.as_deref().unwrap_or("(unknown command)");
+    let mut label = truncate(command, COMMAND_LABEL_CHARS);
+    match (&approval.agent_id, &approval.host) {
+        (Some(agent), Some(host)) => label.push_str(&format!(" — {} on {}", agent, host)),
+        (Some(agent), None) => label.push_str(&format!(" — {}", agent)),
+        (None, Some(host)) => label.push_str(&format!(" — {}", host)),
+        (None, None) => {}
+    }
+    MenuEntry::Submenu {
+        id: format!("approval:{}:{}", approval.gateway_id, approval.id),
+        label,
+        entries: [ApprovalDecision::AllowOnce, ApprovalDecision::Deny]
+            .into_iter()
+            .map(|decision| {
+                MenuEntry::action(
+                    format!(
+                        "{}:{}:{}",
+                        decision.as_str(),
+                        approval.gateway_id,
+                        approval.id
+                    ),
+                    match decision {
+                        ApprovalDecision::Deny => "Deny",
+                        _ => "Approve",
+                    },
+                    TrayAction::AnswerApproval {
+                        gateway_id: approval.gateway_id.clone(),
+                        approval_id: approval.id.clone(),
+                        decision,
+                    },
+                )
+            })
+            .collect(),
+    }
+}
+
+/// The action of the entry with `menu_id`, searching submenus.
+pub fn find_action(entries: &[MenuEntry], menu_id: &str) -> Option<TrayAction> {
+    entries.iter().find_map(|entry| match entry {
+        MenuEntry::Item { id, action, .. } | MenuEntry::Check { id, action, .. }
+            if id == menu_id =>
+        {
+            action.clone()
+        }
+        MenuEntry::Submenu { entries, .. } => find_action(entries, menu_id),
+        _ => None,
+    })
+}
+
+/// Icon for the current status. Approvals take the badge over unread
+/// notifications since they block agents.
+pub fn icon_spec(status: &TrayStatus) -> IconSpec {
+    let gateways = &status.gateways;
+    let state = if gateways
+        .iter()
+        .any(|g| g.state == ConnectionState::Disconnected && g.last_error.is_some())
+    {
+        IconState::Error
+    } else if matches!(
+        status.connection_status.as_str(),
+        "Connecting" | "Reconnecting"
+    ) || gateways.iter().any(|g| {
+        !matches!(
+            g.state,
+            ConnectionState::Connected | ConnectionState::Disconnected
+        )
+    }) {
+        IconState::Reconnecting
+    } else if status.connection_status == "Connected"
+        || gateways
+            .iter()
+            .any(|g| g.state == ConnectionState::Connected)
+    {
+        IconState::Connected
+    } else {
+        IconState::Disconnected
+    };
+
+    let pending = status.total_pending();
+    let badge = if pending > 0 {
+        Some((pending, BadgeKind::Approvals))
+    } else if status.unread_notifications > 0 {
+        Some((status.unread_notifications as u32, BadgeKind::Unread))
+    } else {
+        None
+    };
+    IconSpec { state, badge }
+}
+
+/// Tooltip summarizing the current status.
+pub fn tooltip(status: &TrayStatus) -> String {
+    let mut tooltip = format!("The Fireplace — {}", status.connection_status);
+    if !status.gateways.is_empty() {
+        let connected = status
+            .gateways
+            .iter()
+            .filter(|g| g.state == ConnectionState::Connected)
+            .count();
+        tooltip.push_str(&format!(
+            " · {}/{} gateways",
+            connected,
+            status.gateways.len()
+        ));
+    }
+    // Slowest connected gateway, so a lagging one is not hidden by the rest
+    let latency_ms = status
+        .gateways
+        .iter()
+        .filter(|g| g.state == ConnectionState::Connected)
+        .filter_map(|g| g.latency_ms)
+        .fold(None, |max: Option<f64>, ms| {
+            Some(max.map_or(ms, |m| m.max(ms)))
+        });
+    if let Some(latency_ms) = latency_ms {
+        tooltip.push_str(&format!(" · {:.0} ms", latency_ms));
+    }
+    let pending_approvals = status.total_pending();
+    if pending_approvals > 0 {
+        tooltip.push_str(&format!(" · {} pending", pending_approvals));
+    }
+    tooltip
+}
+
+/// Build one platform menu item (recursively for submenus).
+fn build_item(app: &AppHandle, entry: &MenuEntry) -> tauri::Result<Box<dyn IsMenuItem<Wry>>> {
+    Ok(match entry {
+        MenuEntry::Item { id, label, action } => Box::new(
+            MenuItemBuilder::with_id(id.as_str(), label)
+                .enabled(action.is_some())
+                .build(app)?,
+        ),
+        MenuEntry::Check {
+            id,
+            label,
+            checked,
+            action,
+        } => Box::new(
+            CheckMenuItemBuilder::with_id(id.as_str(), label)
+                .checked(*checked)
+                .enabled(action.is_some())
+                .build(app)?,
+        ),
+        MenuEntry::Separator => Box::new(PredefinedMenuItem::separator(app)?),
+        MenuEntry::Submenu { id, label, entries } => {
+            let submenu = Submenu::with_id(app, id.as_str(), label, true)?;
+            for entry in entries {
+                submenu.append(build_item(app, entry)?.as_ref())?;
+            }
+            Box::new(submenu)
+        }
+    })
+}
+
+/// Build the platform menu for `entries`.
+pub fn build(app: &AppHandle, entries: &[MenuEntry]) -> tauri::Result<Menu<Wry>> {
+    let menu = Menu::new(app)?;
+    for entry in entries {
+        menu.append(build_item(app, entry)?.as_ref())?;
+    }
+    Ok(menu)
+}
+
+/// A change `update` can make in place.
+enum Patch<'a> {
+    /// New label for an item whose ID and action are unchanged.
+    Label { id: &'a str, label: &'a str },
+    Submenu {
+        old: &'a MenuEntry,
+        label: &'a str,
+        entries: &'a [MenuEntry],
+    },
+}
+
+/// Patch `menu`, built from `old`, to show `new`. Only item labels and
+/// submenu labels and contents are patched; returns `false` if anything
+/// else changed and the menu must be rebuilt.
+pub fn update(
+    app: &AppHandle,
+    menu: &Menu<Wry>,
+    old: &[MenuEntry],
+    new: &[MenuEntry],
+) -> tauri::Result<bool> {
+    if old.len() != new.len() {
+        return Ok(false);
+    }
+    let mut patches = Vec::new();
+    for (old, new) in old.iter().zip(new) {
+        match (old, new) {
+            _ if old == new => {}
+            (
+                MenuEntry::Item { id, action, .. },
+                MenuEntry::Item {
+                    id: new_id,
+                    label,
+                    action: new_action,
+                },
+            ) if id == new_id && action == new_action => patches.push(Patch::Label { id, label }),
+            (
+                MenuEntry::Submenu { id, .. },
+                MenuEntry::Submenu {
+                    id: new_id,
+                    label,
+                    entries,
+                },
+            ) if id == new_id => patches.push(Patch::Submenu {
+                old,
+                label,
+                entries,
+            }),
+            _ => return Ok(false),
+        }
+    }
+    for patch in patches {
+        match patch {
+            Patch::Label { id, label } => {
+                let Some(item) = menu.get(id).and_then(|i| i.as_menuitem().cloned()) else {
+                    return Ok(false);
+                };
+                item.set_text(label)?;
+            }
+            Patch::Submenu {
+                old,
+                label,
+                entries,
+            } => {
+                let MenuEntry::Submenu {
+                    id,
+                    label: old_label,
+                    entries: old_entries,
+                } = old
+                else {
+                    continue;
+                };
+                let Some(submenu) = menu.get(id.as_str()).and_then(|i| i.as_submenu().cloned())
+                else {
+                    return Ok(false);
+                };
+                if !patch_submenu(app, &submenu, old_entries, entries)? {
+                    return Ok(false);
+                }
+                if old_label != label {
+                    submenu.set_text(label)?;
+                }
+            }
+        }
+    }
+    Ok(true)
+}
+
+/// Remove the children of `submenu` that are gone or changed and insert the
+/// new ones in place. Fails (returns `false`) before touching anything if
+/// separators would move.
+fn patch_submenu(
+    app: &AppHandle,
+    submenu: &Submenu<Wry>,
+    old: &[MenuEntry],
+    new: &[MenuEntry],
+) -> tauri::Result<bool> {
+    let kept = |entry: &MenuEntry, other: &[MenuEntry]| match entry.id() {
+        Some(id) => other.iter().any(|e| e.id() == Some(id) && e == entry),
+        None => true,
+    };
+    let old_kept: Vec<_> = old.iter().filter(|e| kept(e, new)).collect();
+    let new_kept: Vec<_> = new.iter().filter(|e| kept(e, old)).collect();
+    if old_kept != new_kept {
+        return Ok(false);
+    }
+
+    for entry in old.iter().filter(|e| !kept(e, new)) {
+        if let Some(item) = entry.id().and_then(|id| submenu.get(id)) {
+            submenu.remove(&item)?;
+        }
+    }
+    for (position, entry) in new.iter().enumerate() {
+        if !kept(entry, old) {
+            submenu.insert(build_item(app, entry)?.as_ref(), position)?;
+        }
+    }
+    Ok(true)
+}
+
+/// Put the check item `menu_id` back to its modeled state after the
+/// platform toggled it on click.
+pub fn restore_check(menu: &Menu<Wry>, entries: &[MenuEntry], menu_id: &str) -> tauri::Result<()> {
+    restore_check_in(&|id| menu.get(id), entries, menu_id)
+}
+
+fn restore_check_in(
+    get: &dyn Fn(&str) -> Option<MenuItemKind<Wry>>,
+    entries: &[MenuEntry],
+    menu_id: &str,
+) -> tauri::Result<()> {
+    for entry in entries {
+        match entry {
+            MenuEntry::Check { id, checked, .. } if id == menu_id => {
+                if let Some(item) = get(id).and_then(|i| i.as_check_menuitem().cloned()) {
+                    item.set_checked(*checked)?;
+                }
+            }
+            MenuEntry::Submenu { id, entries, .. } => {
+                if let Some(submenu) = get(id).and_then(|i| i.as_submenu().cloned()) {
+                    restore_check_in(&|id| submenu.get(id), entries, menu_id)?;
+                }
+            }
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+// ---- Tests ----------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::Value;
+
+    fn gateway(id: &str, state: ConnectionState) -> SessionSummary {
+        SessionSummary {
+            id: id.to_string(),
+            name: format!("Gateway {}", id),
+            url: format!("wss://{}.example", id),
+            state,
+            server_version: None,
+            last_error: None,
+            pending_approvals: 0,
+            reconnect_attempts: 0,
+            latency_ms: None,
+            warnings: Vec::new(),
+            active: false,
+        }
+    }
+
+    fn approval(gateway_id: &str, id: &str) -> PendingApproval {
+        PendingApproval {
+            gateway_id: gateway_id.to_string(),
+            id: id.to_string(),
+            command: Some("rm -rf build".to_string()),
+            agent_id: Some("builder".to_string()),
+            host: Some("ci".to_string()),
+            session_key: None,
+            timeout_ms: None,
+            received_at_ms: 0,
+            payload: Value::Null,
+        }
+    }
+
+    fn find<'a>(entries: &'a [MenuEntry], menu_id: &str) -> Option<&'a MenuEntry> {
+        entries.iter().find_map(|entry| match entry {
+            _ if entry.id() == Some(menu_id) => Some(entry),
+            MenuEntry::Submenu { entries, .. } => find(entries, menu_id),
+            _ => None,
+        })
+    }
+
+    fn label_of(entries: &[MenuEntry], menu_id: &str) -> String {
+        match find(entries, menu_id) {
+            Some(MenuEntry::Item { label, .. })
+            | Some(MenuEntry::Check { label, .. })
+            | Some(MenuEntry::Submenu { label, .. }) => label.clone(),
+            other => panic!("no labelled entry {}: {:?}", menu_id, other),
+        }
+    }
+
+    #[test]
+    fn model_without_gateways_is_status_and_navigation() {
+        let entries = model(&TrayStatus::default());
+        assert_eq!(label_of(&entries, "status"), "○ Disconnected");
+        assert_eq!(label_of(&entries, "nav_approvals"), "No Pending Approvals");
+        assert_eq!(find_action(&entries, "nav_approvals"), None);
+        assert!(find(&entries, ACTIVE_GATEWAY_MENU_ID).is_none());
+        assert!(find(&entries, SESSIONS_MENU_ID).is_none());
+        assert_eq!(
+            entries.last(),
+            Some(&MenuEntry::action(
+                "quit",
+                "Quit The Fireplace",
+                TrayAction::Quit
+            ))
+        );
+    }
+
+    #[test]
+    fn model_shows_the_dashboard_only_once_computed() {
+        let mut status = TrayStatus {
+            gateways: vec![gateway("a", ConnectionState::Connected)],
+            ..Default::default()
+        };
+        // No gateway answered yet
+        assert!(find(&model(&status), "dashboard:usage").is_none());
+
+        status.dashboard = Some(Dashboard {
+            tokens_today: 1_500,
+            cost_today_usd: 0.5,
+            active_sessions: 1,
+            unhealthy_agents: Vec::new(),
+            next_cron: None,
+        });
+        let entries = model(&status);
+        assert_eq!(
+            label_of(&entries, "dashboard:usage"),
+            "Today: 1.5k tokens · $0.50"
+        );
+        assert_eq!(
+            label_of(&entries, "dashboard:sessions"),
+            "Active Sessions: 1"
+        );
+    }
+
+    #[test]
+    fn model_lists_gateways_with_switcher_and_connection_actions() {
+        let mut active = gateway("a", ConnectionState::Connected);
+        active.active = true;
+        active.server_version = Some("1.2".to_string());
+        active.latency_ms = Some(42.4);
+        active.pending_approvals = 2;
+        let mut failed = gateway("b", ConnectionState::Disconnected);
+        failed.last_error = Some("x".repeat(100));
+        let status = TrayStatus {
+            gateways: vec![active, failed],
+            ..Default::default()
+        };
+        let entries = model(&status);
+
+        assert!(matches!(
+            find(&entries, "active:a"),
+            Some(MenuEntry::Check { checked: true, .. })
+        ));
+        assert!(matches!(
+            find(&entries, "active:b"),
+            Some(MenuEntry::Check { checked: false, .. })
+        ));
+        assert_eq!(label_of(&entries, "gateway:a"), "● Gateway a — 2 pending");
+        assert_eq!(
+            label_of(&entries, "gateway:a:detail"),
+            "Connected · v1.2 · 42 ms"
+        );
+        assert_eq!(label_of(&entries, "gateway:b").as_str(), "○ Gateway b");
+        assert_eq!(
+            label_of(&entries, "gateway:b:error").chars().count(),
+            ERROR_LABEL_CHARS
+        );
+
+        // Connect only while disconnected, disconnect otherwise
+        assert_eq!(find_action(&entries, "gateway:a:connect"), None);
+        assert_eq!(
+            find_action(&entries, "gateway:a:disconnect"),
+            Some(TrayAction::DisconnectGateway {
+                gateway_id: "a".to_string()
+            })
+        );
+        assert_eq!(
+            find_action(&entries, "gateway:b:connect"),
+            Some(TrayAction::ConnectGateway {
+                gateway_id: "b".to_string()
+            })
+        );
+        assert_eq!(find_action(&entries, "gateway:b:disconnect"), None);
+
+        // Only offered where the token can be deleted
+        assert_eq!(
+            find(&entries, "gateway:a:forget").is_some(),
+            keychain::is_supported()
+        );
+    }
+
+    #[test]
+    fn model_offers_backend_approvals_in_a_submenu() {
+        let mut gw = gateway("a", ConnectionState::Connected);
+        gw.pending_approvals = 1;
+        let status = TrayStatus {
+            gateways: vec![gw],
+            approvals: vec![approval("a", "ap1")],
+            ..Default::default()
+        };
+        let entries = model(&status);
+
+        assert_eq!(
+            label_of(&entries, APPROVALS_MENU_ID),
+            "Pending Approvals (1)"
+        );
+        assert_eq!(
+            label_of(&entries, "approval:a:ap1"),
+            "rm -rf build — builder on ci"
+        );
+        assert_eq!(
+            find_action(&entries, "allow-once:a:ap1"),
+            Some(TrayAction::AnswerApproval {
+                gateway_id: "a".to_string(),
+                approval_id: "ap1".to_string(),
+                decision: ApprovalDecision::AllowOnce,
+            })
+        );
+        assert_eq!(
+            find_action(&entries, "deny:a:ap1"),
+            Some(TrayAction::AnswerApproval {
+                gateway_id: "a".to_string(),
+                approval_id: "ap1".to_string(),
+                decision: ApprovalDecision::Deny,
+            })
+        );
+    }
+
+    #[test]
+    fn model_keys_approvals_by_gateway_and_id() {
+        let mut a = gateway("a", ConnectionState::Connected);
+        a.pending_approvals = 1;
+        let mut b = gateway("b", ConnectionState::Connected);
+        b.pending_approvals = 1;
+        let status = TrayStatus {
+            gateways: vec![a, b],
+            approvals: vec![approval("a", "ap1"), approval("b", "ap1")],
+            ..Default::default()
+        };
+        let entries = model(&status);
+
+        assert_eq!(
+            label_of(&entries, APPROVALS_MENU_ID),
+            "Pending Approvals (2)"
+        );
+        for gateway_id in ["a", "b"] {
+            assert!(find(&entries, &format!("approval:{}:ap1", gateway_id)).is_some());
+            assert_eq!(
+                find_action(&entries, &format!("deny:{}:ap1", gateway_id)),
+                Some(TrayAction::AnswerApproval {
+                    gateway_id: gateway_id.to_string(),
+                    approval_id: "ap1".to_string(),
+                    decision: ApprovalDecision::Deny,
+                })
+            );
+        }
+    }
+
+    #[test]
+    fn model_opens_the_window_for_frontend_approvals() {
+        let status = TrayStatus {
+            connection_status: "Connected".to_string(),
+            pending_approvals: 3,
+            ..Default::default()
+        };
+        let entries = model(&status);
+        assert_eq!(label_of(&entries, "status"), "● Connected");
+        assert_eq!(label_of(&entries, "nav_approvals"), "Pending Approvals (3)");
+        assert!(matches!(
+            find_action(&entries, "nav_approvals"),
+            Some(TrayAction::Navigate(Route::Approvals { .. }))
+        ));
+    }
+
+    #[test]
+    fn model_marks_running_sessions_and_enables_abort() {
+        let status = TrayStatus {
+            gateways: vec![gateway("a", ConnectionState::Connected)],
+            runs: vec![ActiveRun {
+                gateway_id: "a".to_string(),
+                session_key: "main".to_string(),
+                run_id: "r1".to_string(),
+            }],
+            ..Default::default()
+        };
+        let entries = model(&status);
+        assert_eq!(
+            label_of(&entries, SESSIONS_MENU_ID),
+            "Recent Sessions (1 running)"
+        );
+        assert_eq!(label_of(&entries, "session:a:main"), "▶ main");
+        assert_eq!(
+            find_action(&entries, "session:a:main:abort"),
+            Some(TrayAction::AbortTurn {
+                gateway_id: "a".to_string(),
+                session_key: "main".to_string(),
+            })
+        );
+    }
+
+    #[test]
+    fn model_opens_an_agents_latest_session() {
+        let agent = |id: &str| TrayAgent {
+            gateway_id: "a".to_string(),
+            gateway_name: "Gateway a".to_string(),
+            id: id.to_string(),
+            name: id.to_uppercase(),
+        };
+        let status = TrayStatus {
+            gateways: vec![gateway("a", ConnectionState::Connected)],
+            sessions: vec![RecentSession {
+                gateway_id: "a".to_string(),
+                gateway_name: "Gateway a".to_string(),
+                key: "agent:ops:main".to_string(),
+                title: "Deploy".to_string(),
+                agent_id: Some("ops".to_string()),
+                last_active: Some(1),
+            }],
+            agents: vec![agent("ops"), agent("idle")],
+            ..Default::default()
+        };
+        let entries = model(&status);
+        assert_eq!(label_of(&entries, "agent:a:ops"), "OPS");
+        assert_eq!(
+            find_action(&entries, "agent:a:ops"),
+            Some(TrayAction::Navigate(Route::Chat {
+                session_key: Some("agent:ops:main".to_string()),
+                gateway_id: Some("a".to_string()),
+            }))
+        );
+        assert_eq!(find_action(&entries, "agent:a:idle"), None);
+    }
+
+    #[test]
+    fn find_action_ignores_unknown_and_disabled_entries() {
+        let entries = vec![
+            MenuEntry::label("info", "Info"),
+            MenuEntry::Separator,
+            MenuEntry::Submenu {
+                id: "sub".to_string(),
+                label: "Sub".to_string(),
+                entries: vec![MenuEntry::action("show", "Show", TrayAction::ShowWindow)],
+            },
+        ];
+        assert_eq!(find_action(&entries, "show"), Some(TrayAction::ShowWindow));
+        assert_eq!(find_action(&entries, "info"), None);
+        assert_eq!(find_action(&entries, "sub"), None);
+        assert_eq!(find_action(&entries, "missing"), None);
+    }
+
+    #[test]
+    fn shared_approvals_count_once() {
+        let mut gw = gateway("a", ConnectionState::Connected);
+        gw.pending_approvals = 2;
+        let status = TrayStatus {
+            pending_approvals: 2,
+            approval_ids: vec!["ap1".to_string(), "front".to_string()],
+            gateways: vec![gw],
+            approvals: vec![approval("a", "ap1"), approval("a", "ap2")],
+            ..Default::default()
+        };
+        assert_eq!(status.total_pending(), 3);
+    }
+
+    #[test]
+    fn icon_spec_prefers_error_then_reconnecting() {
+        let mut failed = gateway("a", ConnectionState::Disconnected);
+        failed.last_error = Some("refused".to_string());
+        let status = TrayStatus {
+            connection_status: "Connected".to_string(),
+            gateways: vec![failed, gateway("b", ConnectionState::Reconnecting)],
+            ..Default::default()
+        };
+        assert_eq!(icon_spec(&status).state, IconState::Error);
+
+        let status = TrayStatus {
+            connection_status: "Connected".to_string(),
+            gateways: vec![gateway("b", ConnectionState::Authenticating)],
+            ..Default::default()
+        };
+        assert_eq!(icon_spec(&status).state, IconState::Reconnecting);
+
+        let status = TrayStatus {
+            gateways: vec![gateway("b", ConnectionState::Connected)],
+            ..Default::default()
+        };
+        assert_eq!(icon_spec(&status).state, IconState::Connected);
+        assert_eq!(
+            icon_spec(&TrayStatus::default()),
+            IconSpec {
+                state: IconState::Disconnected,
+                badge: None,
+            }
+        );
+    }
+
+    #[test]
+    fn icon_spec_badges_approvals_over_unread() {
+        let status = TrayStatus {
+            pending_approvals: 2,
+            unread_notifications: 5,
+            ..Default::default()
+        };
+        assert_eq!(icon_spec(&status).badge, Some((2, BadgeKind::Approvals)));
+
+        let status = TrayStatus {
+            unread_notifications: 5,
+            ..Default::default()
+        };
+        assert_eq!(icon_spec(&status).badge, Some((5, BadgeKind::Unread)));
+    }
+
+    #[test]
+    fn tooltip_summarizes_gateways_latency_and_pending() {
+        assert_eq!(
+            tooltip(&TrayStatus::default()),
+            "The Fireplace — Disconnected"
+        );
+
+        let mut fast = gateway("a", ConnectionState::Connected);
+        fast.latency_ms = Some(12.0);
+        let mut slow = gateway("b", ConnectionState::Connected);
+        slow.latency_ms = Some(80.2);
+        slow.pending_approvals = 1;
+        // Latency of a gateway that is not connected is stale
+        let mut down = gateway("c", ConnectionState::Disconnected);
+        down.latency_ms = Some(900.0);
+        let status = TrayStatus {
+            connection_status: "Connected".to_string(),
+            gateways: vec![fast, slow, down],
+            ..Default::default()
+        };
+        assert_eq!(
+            tooltip(&status),
+            "The Fireplace — Connected · 2/3 gateways · 80 ms · 1 pending"
+        );
+    }
+}
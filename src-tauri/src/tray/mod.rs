@@ -0,0 +1,252 @@
+// ---------------------------------------------------------------------------
+// System Tray
+// ---------------------------------------------------------------------------
+// Persistent tray icon with quick status, pending approvals, quick actions
+// on recent sessions and agents, a usage and health dashboard, and nav. The
+// menu is modeled in `menu`, its items' actions live in `actions`, sessions
+// and agents are polled by `activity`, the dashboard is computed by
+// `dashboard`, the icon is drawn by `render`, and the platform icon handling
+// lives in `icon`; this module owns the status state and wires them to the
+// tray.
+
+mod actions;
+mod activity;
+mod dashboard;
+mod icon;
+mod menu;
+mod render;
+
+use crate::gateway::session::{ActiveRun, PendingApproval, SessionSummary};
+use activity::{RecentSession, TrayAgent};
+use dashboard::Dashboard;
+use menu::{MenuEntry, TrayStatus};
+use render::IconSpec;
+use std::sync::Mutex;
+use tauri::{menu::Menu, tray::TrayIconBuilder, AppHandle, Manager, Wry};
+
+/// Tray icon ID used for lookups when rebuilding the menu.
+const TRAY_ID: &str = "main-tray";
+
+type TrayState = Mutex<TrayStatus>;
+
+/// The menu on screen, the model it was built from, and the icon shown.
+#[derive(Clone)]
+struct ShownMenu {
+    menu: Menu<Wry>,
+    entries: Vec<MenuEntry>,
+    icon: IconSpec,
+}
+
+type ShownMenuState = Mutex<Option<ShownMenu>>;
+
+/// Build and register the system tray icon with its context menu.
+pub fn setup_tray(app: &AppHandle) -> Result<(), Box<dyn std::error::Error>> {
+    let status = TrayStatus::default();
+    let entries = menu::model(&status);
+    let icon = menu::icon_spec(&status);
+    let menu = menu::build(app, &entries)?;
+    app.manage::<TrayState>(Mutex::new(status));
+    app.manage::<ShownMenuState>(Mutex::new(Some(ShownMenu {
+        menu: menu.clone(),
+        entries,
+        icon,
+    })));
+
+    let _tray = icon::apply(TrayIconBuilder::with_id(TRAY_ID), icon)?
+        .tooltip("The Fireplace — Mission Control for OpenClaw")
+        .menu(&menu)
+        .on_menu_event(|app, event| {
+            let id = event.id().as_ref();
+            if let Some(action) = shown_action(app, id) {
+                actions::dispatch(app, action);
+            }
+            restore_check(app, id);
+        })
+        // Not emitted on Linux, where a click opens the menu instead
+        .on_tray_icon_event(|tray, event| {
+            if let tauri::tray::TrayIconEvent::Click { .. } = event {
+                crate::navigation::show_main_window(tray.app_handle());
+            }
+        })
+        .build(app)?;
+
+    activity::spawn_poll_task(app);
+    dashboard::spawn_refresh_task(app);
+    Ok(())
+}
+
+/// The menu on screen, copied out so no lock is held while the platform
+/// menu is touched or an action runs; both may wait on the main thread.
+fn shown_menu(app: &AppHandle) -> Option<ShownMenu> {
+    let shown_state = app.state::<ShownMenuState>();
+    let shown = shown_state.lock().ok()?;
+    shown.clone()
+}
+
+/// The action behind a menu ID in the menu on screen.
+fn shown_action(app: &AppHandle, menu_id: &str) -> Option<actions::TrayAction> {
+    menu::find_action(&shown_menu(app)?.entries, menu_id)
+}
+
+/// Undo the platform's toggle of a clicked check item; the action above has
+/// already updated the model if the selection changed.
+fn restore_check(app: &AppHandle, menu_id: &str) {
+    if let Some(shown) = shown_menu(app) {
+        let _ = menu::restore_check(&shown.menu, &shown.entries, menu_id);
+    }
+}
+
+/// Update the menu and tooltip from the current status inputs. Runs on the
+/// main thread, so updates apply one at a time and in order.
+fn refresh(app: &AppHandle) -> Result<(), String> {
+    let handle = app.clone();
+    app.run_on_main_thread(move || {
+        let _ = apply_status(&handle);
+    })
+    .map_err(|e| e.to_string())
+}
+
+/// Show the current status inputs. The model is computed under the state
+/// locks and they are released before the platform menu is touched. Label
+/// and submenu changes are patched into the shown menu; anything else
+/// rebuilds it.
+fn apply_status(app: &AppHandle) -> Result<(), String> {
+    let Some(tray) = app.tray_by_id(TRAY_ID) else {
+        return Ok(());
+    };
+    let (entries, icon, tooltip) = {
+        let state = app.state::<TrayState>();
+        let status = state.lock().map_err(|e| e.to_string())?;
+        (
+            menu::model(&status),
+            menu::icon_spec(&status),
+            menu::tooltip(&status),
+        )
+    };
+
+    let current = shown_menu(app);
+    let patched = match &current {
+        Some(current) => menu::update(app, &current.menu, &current.entries, &entries)
+            .map_err(|e| e.to_string())?,
+        None => false,
+    };
+    let icon_changed = current.as_ref().is_none_or(|current| current.icon != icon);
+    let menu = match current {
+        Some(current) if patched => current.menu,
+        _ => {
+            let menu = menu::build(app, &entries).map_err(|e| e.to_string())?;
+            tray.set_menu(Some(menu.clone())).map_err(|e| e.to_string())?;
+            menu
+        }
+    };
+    if let Ok(mut shown) = app.state::<ShownMenuState>().lock() {
+        *shown = Some(ShownMenu {
+            menu,
+            entries,
+            icon,
+        });
+    }
+
+    if icon_changed {
+        let image = icon::render(icon).map_err(|e| e.to_string())?;
+        tray.set_icon(Some(image)).map_err(|e| e.to_string())?;
+    }
+    tray.set_tooltip(Some(&tooltip))
+        .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+/// Replace the backend gateway sessions and their pending approvals shown
+/// in the tray.
+pub fn set_gateway_summaries(
+    app: &AppHandle,
+    gateways: Vec<SessionSummary>,
+    approvals: Vec<PendingApproval>,
+) {
+    let Some(state) = app.try_state::<TrayState>() else {
+        return;
+    };
+    if let Ok(mut status) = state.lock() {
+        status.gateways = gateways;
+        status.approvals = approvals;
+    }
+    let _ = refresh(app);
+}
+
+/// Replace the recent sessions and agents shown for quick actions.
+fn set_activity(app: &AppHandle, sessions: Vec<RecentSession>, agents: Vec<TrayAgent>) {
+    let Some(state) = app.try_state::<TrayState>() else {
+        return;
+    };
+    if let Ok(mut status) = state.lock() {
+        if status.sessions == sessions && status.agents == agents {
+            return;
+        }
+        status.sessions = sessions;
+        status.agents = agents;
+    }
+    let _ = refresh(app);
+}
+
+/// Replace the running chat turns, which enable Abort Running Turn. A turn
+/// starting or ending changes usage, so the dashboard is refreshed too.
+pub fn set_active_runs(app: &AppHandle, runs: Vec<ActiveRun>) {
+    let Some(state) = app.try_state::<TrayState>() else {
+        return;
+    };
+    if let Ok(mut status) = state.lock() {
+        status.runs = runs;
+    }
+    let _ = refresh(app);
+    dashboard::wake(app);
+}
+
+/// Replace the dashboard numbers.
+fn set_dashboard(app: &AppHandle, dashboard: Option<Dashboard>) {
+    let Some(state) = app.try_state::<TrayState>() else {
+        return;
+    };
+    if let Ok(mut status) = state.lock() {
+        if status.dashboard == dashboard {
+            return;
+        }
+        status.dashboard = dashboard;
+    }
+    let _ = refresh(app);
+}
+
+/// Recompute the dashboard soon, e.g. after an agent's health changed.
+pub fn refresh_dashboard(app: &AppHandle) {
+    dashboard::wake(app);
+}
+
+/// Update the unread notification count shown next to pending approvals.
+pub fn set_unread_notifications(app: &AppHandle, count: usize) {
+    let Some(state) = app.try_state::<TrayState>() else {
+        return;
+    };
+    if let Ok(mut status) = state.lock() {
+        if status.unread_notifications == count {
+            return;
+        }
+        status.unread_notifications = count;
+    }
+    let _ = refresh(app);
+}
+
+/// Tauri command called from the frontend to update tray menu dynamically.
+#[tauri::command]
+pub fn update_tray_status(
+    app: AppHandle,
+    connection_status: String,
+    pending_approvals: u32,
+    approval_ids: Option<Vec<String>>,
+) -> Result<(), String> {
+    if let Some(state) = app.try_state::<TrayState>() {
+        let mut status = state.lock().map_err(|e| e.to_string())?;
+        status.connection_status = connection_status;
+        status.pending_approvals = pending_approvals;
+        status.approval_ids = approval_ids.unwrap_or_default();
+    }
+    refresh(&app)
+}
@@ -0,0 +1,382 @@
+// ---------------------------------------------------------------------------
+// Tray Icon Rendering
+// ---------------------------------------------------------------------------
+// Composes the tray icon from the flame artwork: the flame is dimmed or
+// marked by connection state, and a numeric badge is drawn over the top
+// right corner. Works on raw RGBA so it needs no display.
+//
+// Template mode (macOS) only has alpha to work with: marks are drawn opaque
+// and badge digits are knocked out. Color mode uses a status color and a
+// filled badge with white digits.
+
+/// Connection state shown by the icon.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IconState {
+    Connected,
+    Reconnecting,
+    Disconnected,
+    /// A connection failed and is not being retried.
+    Error,
+}
+
+/// What the badge counts; only affects its color.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BadgeKind {
+    Approvals,
+    Unread,
+}
+
+/// Everything that determines the rendered icon.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct IconSpec {
+    pub state: IconState,
+    /// Count and kind; no badge when `None`.
+    pub badge: Option<(u32, BadgeKind)>,
+}
+
+const AMBER: [u8; 3] = [0xf5, 0x9e, 0x0b];
+const RED: [u8; 3] = [0xdc, 0x26, 0x26];
+const BLUE: [u8; 3] = [0x25, 0x63, 0xeb];
+const WHITE: [u8; 3] = [0xff, 0xff, 0xff];
+const BLACK: [u8; 3] = [0x00, 0x00, 0x00];
+
+/// 3x5 glyphs for the badge, one row per byte (bit 2 = left column).
+const GLYPHS: [(char, [u8; 5]); 11] = [
+    ('0', [0b111, 0b101, 0b101, 0b101, 0b111]),
+    ('1', [0b010, 0b110, 0b010, 0b010, 0b111]),
+    ('2', [0b111, 0b001, 0b111, 0b100, 0b111]),
+    ('3', [0b111, 0b001, 0b111, 0b001, 0b111]),
+    ('4', [0b101, 0b101, 0b111, 0b001, 0b001]),
+    ('5', [0b111, 0b100, 0b111, 0b001, 0b111]),
+    ('6', [0b111, 0b100, 0b111, 0b101, 0b111]),
+    ('7', [0b111, 0b001, 0b001, 0b001, 0b001]),
+    ('8', [0b111, 0b101, 0b111, 0b101, 0b111]),
+    ('9', [0b111, 0b101, 0b111, 0b001, 0b111]),
+    ('+', [0b000, 0b010, 0b111, 0b010, 0b000]),
+];
+
+/// An RGBA image being drawn on.
+struct Canvas {
+    width: u32,
+    height: u32,
+    rgba: Vec<u8>,
+    template: bool,
+}
+
+impl Canvas {
+    fn pixel(&mut self, x: i64, y: i64) -> Option<&mut [u8]> {
+        if x < 0 || y < 0 || x >= self.width as i64 || y >= self.height as i64 {
+            return None;
+        }
+        let offset = ((y as usize) * self.width as usize + x as usize) * 4;
+        Some(&mut self.rgba[offset..offset + 4])
+    }
+
+    /// Paint a pixel; template mode ignores the color.
+    fn set(&mut self, x: i64, y: i64, color: [u8; 3], alpha: u8) {
+        let color = if self.template { BLACK } else { color };
+        if let Some(px) = self.pixel(x, y) {
+            px.copy_from_slice(&[color[0], color[1], color[2], alpha]);
+        }
+    }
+
+    fn clear(&mut self, x: i64, y: i64) {
+        if let Some(px) = self.pixel(x, y) {
+            px[3] = 0;
+        }
+    }
+
+    /// Scale every pixel's alpha by `factor`.
+    fn fade(&mut self, factor: f32) {
+        for px in self.rgba.chunks_exact_mut(4) {
+            px[3] = (px[3] as f32 * factor).round() as u8;
+        }
+    }
+
+    /// Replace colors with their luminance.
+    fn desaturate(&mut self) {
+        for px in self.rgba.chunks_exact_mut(4) {
+            let luma =
+                (0.299 * px[0] as f32 + 0.587 * px[1] as f32 + 0.114 * px[2] as f32).round() as u8;
+            px[..3].copy_from_slice(&[luma, luma, luma]);
+        }
+    }
+
+    /// Apply `paint` to every pixel within `radius` of the center `(cx, cy)`.
+    fn disc(&mut self, cx: f32, cy: f32, radius: f32, mut paint: impl FnMut(&mut Self, i64, i64)) {
+        let (x0, x1) = ((cx - radius).floor() as i64, (cx + radius).ceil() as i64);
+        let (y0, y1) = ((cy - radius).floor() as i64, (cy + radius).ceil() as i64);
+        for y in y0..=y1 {
+            for x in x0..=x1 {
+                let (dx, dy) = (x as f32 + 0.5 - cx, y as f32 + 0.5 - cy);
+                if dx * dx + dy * dy <= radius * radius {
+                    paint(self, x, y);
+                }
+            }
+        }
+    }
+
+    /// Apply `paint` to every pixel of a pill (rounded rectangle) whose
+    /// left edge is `x` and top edge `y`.
+    fn pill(
+        &mut self,
+        x: f32,
+        y: f32,
+        width: f32,
+        height: f32,
+        mut paint: impl FnMut(&mut Self, i64, i64),
+    ) {
+        let radius = height / 2.0;
+        let cy = y + radius;
+        for py in y.floor() as i64..(y + height).ceil() as i64 {
+            for px in x.floor() as i64..(x + width).ceil() as i64 {
+                // Distance to the pill's center line
+                let fx = px as f32 + 0.5;
+                let nearest = fx.clamp(x + radius, x + width - radius);
+                let (dx, dy) = (fx - nearest, py as f32 + 0.5 - cy);
+                if dx * dx + dy * dy <= radius * radius {
+                    paint(self, px, py);
+                }
+            }
+        }
+    }
+}
+
+/// Badge text: the count, or "9+" above nine.
+fn badge_text(count: u32) -> String {
+    if count > 9 {
+        "9+".to_string()
+    } else {
+        count.to_string()
+    }
+}
+
+/// Render `spec` over the flame artwork in `rgba` (`width` x `height`).
+pub fn render(rgba: &[u8], width: u32, height: u32, template: bool, spec: IconSpec) -> Vec<u8> {
+    let mut canvas = Canvas {
+        width,
+        height,
+        rgba: rgba.to_vec(),
+        template,
+    };
+    // Artwork is drawn at 32x32; marks scale with it
+    let unit = width.min(height) as f32 / 32.0;
+
+    match spec.state {
+        IconState::Connected => {}
+        IconState::Reconnecting => canvas.fade(0.6),
+        IconState::Disconnected => {
+            canvas.fade(0.35);
+            if !template {
+                canvas.desaturate();
+            }
+        }
+        IconState::Error => canvas.fade(0.6),
+    }
+
+    // Status mark in the bottom right: a ring while reconnecting, a dot on
+    // error, each with a cleared gap so it stands apart from the flame
+    let mark = match spec.state {
+        IconState::Reconnecting => Some((AMBER, true)),
+        IconState::Error => Some((RED, false)),
+        IconState::Connected | IconState::Disconnected => None,
+    };
+    if let Some((color, ring)) = mark {
+        let radius = 6.0 * unit;
+        let (cx, cy) = (width as f32 - radius, height as f32 - radius);
+        canvas.disc(cx, cy, radius, |c, x, y| c.clear(x, y));
+        canvas.disc(cx, cy, radius - unit, |c, x, y| c.set(x, y, color, 255));
+        if ring {
+            canvas.disc(cx, cy, radius - 3.0 * unit, |c, x, y| c.clear(x, y));
+        }
+    }
+
+    if let Some((count, kind)) = spec.badge.filter(|(count, _)| *count > 0) {
+        let text = badge_text(count);
+        let dot = (2.0 * unit).max(1.0);
+        let glyph_width = 3.0 * dot;
+        let text_width = text.len() as f32 * glyph_width + (text.len() - 1) as f32 * dot;
+        let badge_height = 5.0 * dot + 4.0 * unit;
+        let badge_width = (text_width + 4.0 * unit).max(badge_height);
+        let (x, y) = (width as f32 - badge_width, 0.0);
+
+        // Gap around the badge, then the badge itself
+        canvas.pill(
+            x - unit,
+            y,
+            badge_width + unit,
+            badge_height + unit,
+            |c, px, py| c.clear(px, py),
+        );
+        let fill = match kind {
+            BadgeKind::Approvals => RED,
+            BadgeKind::Unread => BLUE,
+        };
+        canvas.pill(x, y, badge_width, badge_height, |c, px, py| {
+            c.set(px, py, fill, 255)
+        });
+
+        let mut gx = x + (badge_width - text_width) / 2.0;
+        let gy = y + (badge_height - 5.0 * dot) / 2.0;
+        for ch in text.chars() {
+            let Some((_, rows)) = GLYPHS.iter().find(|(g, _)| *g == ch) else {
+                continue;
+            };
+            for (row, bits) in rows.iter().enumerate() {
+                for col in 0..3 {
+                    if bits & (0b100 >> col) == 0 {
+                        continue;
+                    }
+                    let x0 = gx + col as f32 * dot;
+                    let y0 = gy + row as f32 * dot;
+                    for py in y0.round() as i64..(y0 + dot).round() as i64 {
+                        for px in x0.round() as i64..(x0 + dot).round() as i64 {
+                            if template {
+                                canvas.clear(px, py);
+                            } else {
+                                canvas.set(px, py, WHITE, 255);
+                            }
+                        }
+                    }
+                }
+            }
+            gx += glyph_width + dot;
+        }
+    }
+
+    canvas.rgba
+}
+
+// ---- Tests ----------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SIZE: u32 = 32;
+
+    /// Opaque green artwork covering the whole icon.
+    fn artwork() -> Vec<u8> {
+        [0x10, 0x80, 0x10, 0xff].repeat((SIZE * SIZE) as usize)
+    }
+
+    fn pixel(rgba: &[u8], x: u32, y: u32) -> [u8; 4] {
+        let offset = ((y * SIZE + x) * 4) as usize;
+        rgba[offset..offset + 4].try_into().unwrap()
+    }
+
+    fn spec(state: IconState, badge: Option<(u32, BadgeKind)>) -> IconSpec {
+        IconSpec { state, badge }
+    }
+
+    fn render_spec(template: bool, spec: IconSpec) -> Vec<u8> {
+        render(&artwork(), SIZE, SIZE, template, spec)
+    }
+
+    #[test]
+    fn badge_text_caps_at_nine_plus() {
+        assert_eq!(badge_text(1), "1");
+        assert_eq!(badge_text(9), "9");
+        assert_eq!(badge_text(10), "9+");
+        assert_eq!(badge_text(250), "9+");
+    }
+
+    #[test]
+    fn connected_without_badge_keeps_the_artwork() {
+        assert_eq!(
+            render_spec(false, spec(IconState::Connected, None)),
+            artwork()
+        );
+        assert_eq!(
+            render_spec(
+                false,
+                spec(IconState::Connected, Some((0, BadgeKind::Unread)))
+            ),
+            artwork()
+        );
+    }
+
+    #[test]
+    fn disconnected_fades_and_desaturates() {
+        let rgba = render_spec(false, spec(IconState::Disconnected, None));
+        let [r, g, b, a] = pixel(&rgba, 4, 4);
+        assert_eq!((r, g), (g, b));
+        assert_eq!(a, (255.0f32 * 0.35).round() as u8);
+
+        // Template icons keep their (black) color; only alpha changes
+        let rgba = render_spec(true, spec(IconState::Disconnected, None));
+        assert_eq!(pixel(&rgba, 4, 4)[..3], [0x10, 0x80, 0x10]);
+    }
+
+    #[test]
+    fn error_draws_a_red_dot_in_the_corner() {
+        let rgba = render_spec(false, spec(IconState::Error, None));
+        assert_eq!(pixel(&rgba, 26, 26), [RED[0], RED[1], RED[2], 255]);
+        // Faded flame outside the mark
+        assert_eq!(pixel(&rgba, 4, 4)[3], 153);
+
+        let rgba = render_spec(true, spec(IconState::Error, None));
+        assert_eq!(pixel(&rgba, 26, 26), [0, 0, 0, 255]);
+    }
+
+    #[test]
+    fn reconnecting_draws_a_ring() {
+        let rgba = render_spec(false, spec(IconState::Reconnecting, None));
+        // Center of the ring is cleared, its band is amber
+        assert_eq!(pixel(&rgba, 26, 26)[3], 0);
+        assert_eq!(pixel(&rgba, 26, 21), [AMBER[0], AMBER[1], AMBER[2], 255]);
+    }
+
+    #[test]
+    fn badge_above_nine_shows_nine_plus() {
+        let nine = render_spec(
+            false,
+            spec(IconState::Connected, Some((9, BadgeKind::Approvals))),
+        );
+        let ten = render_spec(
+            false,
+            spec(IconState::Connected, Some((10, BadgeKind::Approvals))),
+        );
+        let many = render_spec(
+            false,
+            spec(IconState::Connected, Some((42, BadgeKind::Approvals))),
+        );
+        assert_eq!(ten, many);
+        assert_ne!(nine, ten);
+
+        // "9+" is centered in an 18px pill at the top right: the 9 starts at
+        // x = 16 and the bar of the + spans x = 24..30 on row y = 6
+        assert_eq!(pixel(&ten, 16, 2), [0xff, 0xff, 0xff, 255]);
+        assert_eq!(pixel(&ten, 24, 6), [0xff, 0xff, 0xff, 255]);
+        assert_eq!(pixel(&ten, 29, 6), [0xff, 0xff, 0xff, 255]);
+        assert_eq!(pixel(&ten, 24, 2), [RED[0], RED[1], RED[2], 255]);
+        // The rest of the flame is untouched
+        assert_eq!(pixel(&ten, 4, 28), [0x10, 0x80, 0x10, 0xff]);
+    }
+
+    #[test]
+    fn badge_color_follows_its_kind() {
+        let rgba = render_spec(
+            false,
+            spec(IconState::Connected, Some((3, BadgeKind::Unread))),
+        );
+        assert_eq!(pixel(&rgba, 31, 7), [BLUE[0], BLUE[1], BLUE[2], 255]);
+    }
+
+    #[test]
+    fn template_badge_knocks_out_its_digits() {
+        let rgba = render_spec(
+            true,
+            spec(IconState::Connected, Some((10, BadgeKind::Approvals))),
+        );
+        // Digits are transparent holes in an opaque black pill
+        assert_eq!(pixel(&rgba, 16, 2)[3], 0);
+        assert_eq!(pixel(&rgba, 24, 6)[3], 0);
+        assert_eq!(pixel(&rgba, 24, 2), [0, 0, 0, 255]);
+        // Nothing else is colored in template mode
+        assert!(rgba
+            .chunks_exact(4)
+            .filter(|px| px[3] == 255)
+            .all(|px| px[..3] == [0, 0, 0] || px[..3] == [0x10, 0x80, 0x10]));
+    }
+}
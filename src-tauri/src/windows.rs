@@ -0,0 +1,157 @@
+// ---------------------------------------------------------------------------
+// Pop-out Windows
+// ---------------------------------------------------------------------------
+//
+// A chat session, the logs tail or the approvals queue in a window of its
+// own. Each pop-out loads the app at the view's route and is subscribed in
+// the `hub` to just the gateway events that view needs. Labels are derived
+// from what the window shows, so popping out the same thing again focuses
+// the open window instead of creating a second one.
+
+use crate::hub::{self, EventFilter, MAIN_CONNECTION_ID};
+use crate::navigation::Route;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use tauri::{AppHandle, Manager, WebviewUrl, WebviewWindowBuilder};
+
+/// Prefix of pop-out window labels; the default capability covers
+/// `popout-*`.
+const POP_OUT_PREFIX: &str = "popout-";
+
+/// Smallest pop-out size, in logical pixels.
+const MIN_SIZE: (f64, f64) = (400.0, 300.0);
+
+/// What a pop-out window shows.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+pub enum PopOut {
+    #[serde(rename_all = "camelCase")]
+    Chat {
+        #[serde(default)]
+        gateway_id: Option<String>,
+        session_key: String,
+    },
+    #[serde(rename_all = "camelCase")]
+    Logs {
+        #[serde(default)]
+        gateway_id: Option<String>,
+    },
+    #[serde(rename_all = "camelCase")]
+    Approvals {
+        #[serde(default)]
+        gateway_id: Option<String>,
+    },
+}
+
+impl PopOut {
+    fn route(&self) -> Route {
+        match self {
+            PopOut::Chat {
+                gateway_id,
+                session_key,
+            } => Route::Chat {
+                session_key: Some(session_key.clone()),
+                gateway_id: gateway_id.clone(),
+            },
+            PopOut::Logs { .. } => Route::Logs { cursor: None },
+            PopOut::Approvals { gateway_id } => Route::Approvals {
+                id: None,
+                gateway_id: gateway_id.clone(),
+            },
+        }
+    }
+
+    /// Gateway events the window is subscribed to. Without a gateway the
+    /// window follows the main window's connection.
+    fn filters(&self) -> Vec<EventFilter> {
+        let gateway_id = |id: &Option<String>| {
+            Some(id.clone().unwrap_or_else(|| MAIN_CONNECTION_ID.to_string()))
+        };
+        vec![match self {
+            PopOut::Chat {
+                gateway_id: id,
+                session_key,
+            } => EventFilter::Session {
+                gateway_id: gateway_id(id),
+                session_key: session_key.clone(),
+            },
+            PopOut::Logs { gateway_id: id } => EventFilter::Logs {
+                gateway_id: gateway_id(id),
+            },
+            PopOut::Approvals { gateway_id: id } => EventFilter::Approvals {
+                gateway_id: gateway_id(id),
+            },
+        }]
+    }
+
+    fn title(&self) -> String {
+        match self {
+            PopOut::Chat { session_key, .. } => format!("Chat — {}", session_key),
+            PopOut::Logs { .. } => "Logs".to_string(),
+            PopOut::Approvals { .. } => "Approvals".to_string(),
+        }
+    }
+
+    /// Initial size in logical pixels.
+    fn size(&self) -> (f64, f64) {
+        match self {
+            PopOut::Chat { .. } => (720.0, 800.0),
+            PopOut::Logs { .. } => (960.0, 600.0),
+            PopOut::Approvals { .. } => (640.0, 600.0),
+        }
+    }
+
+    /// Window label: the kind plus a digest of the target, since session
+    /// keys may hold characters labels do not allow.
+    fn label(&self) -> String {
+        let kind = match self {
+            PopOut::Chat { .. } => "chat",
+            PopOut::Logs { .. } => "logs",
+            PopOut::Approvals { .. } => "approvals",
+        };
+        let target = serde_json::to_string(self).unwrap_or_default();
+        let digest = hex::encode(Sha256::digest(target.as_bytes()));
+        format!("{}{}-{}", POP_OUT_PREFIX, kind, &digest[..16])
+    }
+}
+
+/// Open `target` in its own window, or focus it if already open. Returns
+/// the window label.
+pub fn pop_out(app: &AppHandle, target: PopOut) -> Result<String, String> {
+    let route = target.route();
+    route.validate().map_err(|e| e.to_string())?;
+    let label = target.label();
+    if let Some(window) = app.get_webview_window(&label) {
+        let _ = window.show();
+        let _ = window.unminimize();
+        let _ = window.set_focus();
+        return Ok(label);
+    }
+
+    // Subscribed before the page loads, so no event is missed
+    hub::subscribe(app, &label, target.filters());
+    let (width, height) = target.size();
+    let window = WebviewWindowBuilder::new(app, &label, WebviewUrl::App(route.path().into()))
+        .title(format!("The Fireplace — {}", target.title()))
+        .inner_size(width, height)
+        .min_inner_size(MIN_SIZE.0, MIN_SIZE.1)
+        .build()
+        .map_err(|e| {
+            hub::unsubscribe(app, &label);
+            e.to_string()
+        })?;
+    #[cfg(debug_assertions)]
+    crate::open_devtools_if_requested(&window);
+    #[cfg(not(debug_assertions))]
+    let _ = window;
+    Ok(label)
+}
+
+// ---- Tauri Commands -------------------------------------------------------
+
+/// Pop a chat session, the logs tail or the approvals queue out into its
+/// own window.
+#[tauri::command]
+pub async fn window_pop_out(app: AppHandle, target: PopOut) -> Result<String, String> {
+    pop_out(&app, target)
+}
@@ -1,4 +1,4 @@
-import { BrowserRouter, Routes, Route, Navigate } from 'react-router-dom';
+import { BrowserRouter, Routes, Route, Navigate, useSearchParams } from 'react-router-dom';
 import { useEffect, useRef } from 'react';
 import { Toaster } from 'sonner';
 import { useIsMobile } from '@/hooks/usePlatform';
@@ -11,7 +11,10 @@ import { UpdateBanner } from '@/components/UpdateBanner';
 import { ApprovalNotifier } from '@/components/ApprovalNotifier';
 import { TooltipProvider } from '@/components/ui/tooltip';
 import { useTraySync } from '@/hooks/useTraySync';
-import { useGlobalShortcut } from '@/hooks/useGlobalShortcut';
+import { useShortcutActions } from '@/hooks/useShortcutActions';
+import { useHubRelay } from '@/hooks/useHubRelay';
+import { useChatStore } from '@/stores/chat';
+import { isPopOutWindow, subscribeHub, MAIN_CONNECTION_ID } from '@/lib/windows';
 
 // Views
 import { Chat } from '@/views/Chat';
@@ -38,7 +41,8 @@ function AppShell() {
   const { connect, status, initGatewayUrl } = useConnectionStore();
   const connectAttempted = useRef(false);
   useTraySync();
-  useGlobalShortcut();
+  useShortcutActions();
+  useHubRelay();
 
   // Auto-connect on app startup — use a ref to prevent double-invocation
   // while still correctly reading status from state
@@ -112,12 +116,61 @@ function AppShell() {
   );
 }
 
-function App() {
+/**
+ * Shell of a pop-out window: one view, no navigation chrome. Tray sync,
+ * backend navigation and the gateway connection stay with the main window.
+ */
+function PopOutShell() {
+  const { connect, status } = useConnectionStore();
+  const connectAttempted = useRef(false);
+  const [searchParams] = useSearchParams();
+  const sessionKey = searchParams.get('session');
+  const gatewayId = searchParams.get('gateway') ?? MAIN_CONNECTION_ID;
+  const activeSessionKey = useChatStore((s) => s.activeSessionKey);
+  const isChat = sessionKey !== null;
+
+  useEffect(() => {
+    if (sessionKey) {
+      useChatStore.getState().setActiveSession(sessionKey);
+    }
+  }, [sessionKey]);
+
+  // Events come from the backend hub, which the window was subscribed to
+  // when it opened; no gateway connection of its own
+  useEffect(() => {
+    if (!connectAttempted.current && status === 'disconnected') {
+      connectAttempted.current = true;
+      connect().catch((err) => {
+        console.error('[App] Pop-out connect failed:', err);
+      });
+    }
+  }, [status, connect]);
+
+  // A chat pop-out that switches session follows the new one
+  useEffect(() => {
+    if (!isChat || !activeSessionKey) return;
+    subscribeHub([{ kind: 'session', sessionKey: activeSessionKey, gatewayId }]).catch((err) => {
+      console.error('[App] Pop-out subscribe failed:', err);
+    });
+  }, [isChat, activeSessionKey, gatewayId]);
+
   return (
-    <BrowserRouter>
-      <AppShell />
-    </BrowserRouter>
+    <TooltipProvider>
+      <Toaster theme="dark" richColors position="bottom-right" />
+      <main className="h-screen overflow-auto bg-zinc-950">
+        <Routes>
+          <Route path="/" element={<Chat />} />
+          <Route path="/approvals" element={<Approvals />} />
+          <Route path="/logs" element={<Logs />} />
+          <Route path="*" element={<Navigate to="/" replace />} />
+        </Routes>
+      </main>
+    </TooltipProvider>
   );
 }
 
+function App() {
+  return <BrowserRouter>{isPopOutWindow() ? <PopOutShell /> : <AppShell />}</BrowserRouter>;
+}
+
 export { App };
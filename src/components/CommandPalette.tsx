@@ -19,6 +19,8 @@ import {
 import { Dialog, DialogContent } from '@/components/ui/dialog';
 import { useKeyboardShortcut, formatShortcut } from '@/hooks/useKeyboard';
 import { useConnectionStore } from '@/stores/connection';
+import { useChatStore } from '@/stores/chat';
+import { popOut, type PopOutTarget } from '@/lib/windows';
 
 // ---- Navigation Items -----------------------------------------------------
 
@@ -128,6 +130,7 @@ export function CommandPalette() {
   const navigate = useNavigate();
   const location = useLocation();
   const { status, disconnect, connect } = useConnectionStore();
+  const activeSessionKey = useChatStore((s) => s.activeSessionKey);
 
   // Cmd+K to toggle
   useKeyboardShortcut(
@@ -135,6 +138,30 @@ export function CommandPalette() {
     useCallback(() => setOpen((prev) => !prev), [])
   );
 
+  // Global shortcut (registered in Rust) shows the window and opens the palette
+  useEffect(() => {
+    let unlisten: (() => void) | undefined;
+    let cancelled = false;
+    const subscribe = async () => {
+      try {
+        const { listen } = await import('@tauri-apps/api/event');
+        const stop = await listen('app://command-palette', () => setOpen(true));
+        if (cancelled) {
+          stop();
+          return;
+        }
+        unlisten = stop;
+      } catch {
+        // Not in Tauri context
+      }
+    };
+    subscribe();
+    return () => {
+      cancelled = true;
+      unlisten?.();
+    };
+  }, []);
+
   // Escape to close (only when open)
   useKeyboardShortcut(
     'escape',
@@ -188,8 +215,42 @@ export function CommandPalette() {
       },
     ];
 
-    return [...navItems, ...actionItems];
-  }, [navigate, status, disconnect, connect]);
+    const openPopOut = (target: PopOutTarget) => {
+      popOut(target).catch((err) => {
+        console.error('[CommandPalette] Pop-out failed:', err);
+      });
+    };
+    const popOutItems: PaletteItem[] = [
+      {
+        id: 'action-pop-out-logs',
+        label: 'Pop Out Logs',
+        icon: '^',
+        action: () => openPopOut({ kind: 'logs' }),
+        group: 'Actions',
+        keywords: ['window', 'pop', 'detach', 'log'],
+      },
+      {
+        id: 'action-pop-out-approvals',
+        label: 'Pop Out Approvals',
+        icon: '^',
+        action: () => openPopOut({ kind: 'approvals' }),
+        group: 'Actions',
+        keywords: ['window', 'pop', 'detach', 'approve'],
+      },
+    ];
+    if (activeSessionKey) {
+      popOutItems.unshift({
+        id: 'action-pop-out-chat',
+        label: 'Pop Out Chat',
+        icon: '^',
+        action: () => openPopOut({ kind: 'chat', sessionKey: activeSessionKey }),
+        group: 'Actions',
+        keywords: ['window', 'pop', 'detach', 'session'],
+      });
+    }
+
+    return [...navItems, ...actionItems, ...popOutItems];
+  }, [navigate, status, disconnect, connect, activeSessionKey]);
 
   if (!open) return null;
 
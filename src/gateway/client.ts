@@ -23,6 +23,7 @@ import type {
   GatewayError,
   ShutdownEventPayload,
   GatewayMethod,
+  RequestPriority,
 } from './types';
 import { SIDE_EFFECTING_METHODS } from './types';
 import {
@@ -183,6 +184,7 @@ export class GatewayClient {
   private _rlLastRefillAt = Date.now();
   private _rlQueue: Array<() => void> = [];
   private _rlDrainTimer: ReturnType<typeof setTimeout> | null = null;
+  private _schedulerAvailable = true;
 
   // -- Idempotency key tracking (Map<key, timestamp> for O(1) lookups)
   private idempotencyCache = new Map<string, number>();
@@ -444,8 +446,8 @@ export class GatewayClient {
       throw new Error(`Cannot send request: state is "${this._state}", expected "connected"`);
     }
 
-    // Rate limiting: wait for a token before proceeding
-    await this.acquireRateLimitToken(method);
+    // Rate limiting: wait for a slot in the method's budget before proceeding
+    await this.acquireRequestSlot(method, options?.priority ?? 'interactive', options?.signal);
 
     // Determine idempotency key
     let idempotencyKey = options?.idempotencyKey;
@@ -645,6 +647,9 @@ export class GatewayClient {
       pending.resolve(frame.payload);
     } else if (frame.error) {
       this._lastError = frame.error;
+      if (frame.error.retryable && frame.error.retryAfterMs) {
+        this.reportBackoff(pending.method, frame.error.retryAfterMs);
+      }
       pending.reject(new GatewayRequestError(frame.error));
     } else {
       pending.reject(new Error(`Request failed without error details: ${pending.method}`));
@@ -993,6 +998,61 @@ export class GatewayClient {
     }
   }
 
+  // ---- Internals: Request Scheduling --------------------------------------
+
+  /**
+   * Wait for a send slot from the Rust request scheduler, which budgets
+   * each method class separately and honors server-driven backoff.
+   * Aborting `signal` while queued gives the place back to the scheduler.
+   * Falls back to the local token bucket outside of Tauri.
+   */
+  private async acquireRequestSlot(
+    method: string,
+    priority: RequestPriority,
+    signal?: AbortSignal
+  ): Promise<void> {
+    if (signal?.aborted) {
+      throw new Error(`Request aborted: ${method}`);
+    }
+    if (this._schedulerAvailable) {
+      const { invoke, isTauri } = await import('@tauri-apps/api/core');
+      if (isTauri()) {
+        const ticket = crypto.randomUUID();
+        const onAbort = () => {
+          invoke('gateway_release_slot', { ticket }).catch(() => {
+            // The wait ends with the app anyway
+          });
+        };
+        signal?.addEventListener('abort', onAbort, { once: true });
+        try {
+          await invoke<number>('gateway_acquire_slot', { method, priority, ticket });
+          return;
+        } catch (err) {
+          if (signal?.aborted) {
+            throw new Error(`Request aborted: ${method}`);
+          }
+          // One failed call falls back for this request only
+          console.warn('[GatewayClient] Request scheduler unavailable:', err);
+        } finally {
+          signal?.removeEventListener('abort', onAbort);
+        }
+      } else {
+        this._schedulerAvailable = false;
+      }
+    }
+    await this.acquireRateLimitToken(method);
+  }
+
+  /** Tell the scheduler to pause a method class for `retryAfterMs`. */
+  private reportBackoff(method: string, retryAfterMs: number): void {
+    if (!this._schedulerAvailable) return;
+    void import('@tauri-apps/api/core')
+      .then(({ invoke }) => invoke('gateway_report_backoff', { method, retryAfterMs }))
+      .catch(() => {
+        // Not in Tauri context
+      });
+  }
+
   // ---- Internals: Rate Limiter (Token Bucket) -----------------------------
 
   private refillRateLimitTokens(): void {
@@ -1055,8 +1115,8 @@ export class GatewayClient {
       try {
         console.log('[Gateway] Requesting state refresh after sequence gap...');
         const [sessions, agents] = await Promise.all([
-          this.request('sessions.list').catch(() => null),
-          this.request('agents.list').catch(() => null),
+          this.request('sessions.list', undefined, { priority: 'background' }).catch(() => null),
+          this.request('agents.list', undefined, { priority: 'background' }).catch(() => null),
         ]);
 
         // If both requests failed, log a warning and skip the synthetic event
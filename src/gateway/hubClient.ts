@@ -0,0 +1,346 @@
+// ---------------------------------------------------------------------------
+// Hub Client — a pop-out window's view of a gateway, through the backend hub
+// ---------------------------------------------------------------------------
+//
+// Pop-out windows do not open a gateway connection of their own. The backend
+// hub sends them the `gateway://event`s their filters match, and their
+// requests go through `hub_request`, to a backend gateway session or to the
+// main window's connection. HubClient offers the part of GatewayClient the
+// connection store uses, so the stores work unchanged in a pop-out.
+//
+// Events are heard on this window only (a global listener would also hear
+// those sent to every other window), and envelopes from any gateway but the
+// window's own are dropped.
+
+import type {
+  EventFrame,
+  EventHandler,
+  GatewayConnectionState,
+  GatewayError,
+  GatewayMethod,
+  GatewayPolicy,
+  HelloOkAuth,
+  HelloOkFeatures,
+  HelloOkServer,
+  RequestOptions,
+  Snapshot,
+  StateVersion,
+  Unsubscribe,
+} from './types';
+import { SIDE_EFFECTING_METHODS } from './types';
+import { generateIdempotencyKey } from './protocol';
+import { GatewayRequestError, type StateChangeListener } from './client';
+
+// ---- Types ----------------------------------------------------------------
+
+/** How a large event payload was split; sent with its head payload. */
+interface ChunkHeader {
+  streamId: string;
+  /** Field holding the split array; null if the payload was the array. */
+  field: string | null;
+  totalItems: number;
+  chunks: number;
+}
+
+/** Payload of the backend `gateway://event` event. */
+interface HubEvent {
+  gatewayId: string;
+  event: string;
+  payload?: unknown;
+  seq?: number;
+  chunked?: ChunkHeader;
+}
+
+/** Payload of the backend `gateway://event-chunk` event. */
+interface HubEventChunk {
+  streamId: string;
+  index: number;
+  items: unknown[];
+}
+
+/** A chunked event waiting for the rest of its chunks. */
+interface PendingEvent {
+  frame: EventFrame;
+  header: ChunkHeader;
+  chunks: unknown[][];
+  received: number;
+}
+
+// ---- Helpers --------------------------------------------------------------
+
+/**
+ * Put the chunks of a split payload back into its head payload.
+ */
+export function assembleChunked(
+  head: unknown,
+  field: string | null,
+  chunks: unknown[][]
+): unknown {
+  const items = chunks.flat();
+  if (field === null) {
+    return items;
+  }
+  if (typeof head !== 'object' || head === null) {
+    return head;
+  }
+  return { ...head, [field]: items };
+}
+
+// ---- Client ---------------------------------------------------------------
+
+export class HubClient {
+  private _state: GatewayConnectionState = 'disconnected';
+  private stateListeners = new Set<StateChangeListener>();
+  private eventHandlers = new Map<string, Set<EventHandler>>();
+  private wildcardHandlers = new Set<EventHandler<EventFrame>>();
+  private pendingEvents = new Map<string, PendingEvent>();
+  private unlisteners: Array<() => void> = [];
+  private gatewayId: string | null = null;
+
+  get state(): GatewayConnectionState {
+    return this._state;
+  }
+
+  // The handshake happens on the connection the hub relays, so there is no
+  // server info, snapshot or policy of our own.
+  get lastError(): GatewayError | null {
+    return null;
+  }
+
+  get serverInfo(): HelloOkServer | null {
+    return null;
+  }
+
+  get serverProtocol(): number | null {
+    return null;
+  }
+
+  get serverFeatures(): HelloOkFeatures | null {
+    return null;
+  }
+
+  get serverPolicy(): GatewayPolicy | null {
+    return null;
+  }
+
+  get snapshot(): Snapshot | null {
+    return null;
+  }
+
+  get stateVersion(): StateVersion {
+    return { presence: 0, health: 0 };
+  }
+
+  get auth(): HelloOkAuth | null {
+    return null;
+  }
+
+  get reconnectAttempts(): number {
+    return 0;
+  }
+
+  // ---- State Management ---------------------------------------------------
+
+  private setState(next: GatewayConnectionState): void {
+    const prev = this._state;
+    if (prev === next) return;
+    this._state = next;
+    for (const listener of this.stateListeners) {
+      try {
+        listener(next, prev);
+      } catch (err) {
+        console.error('[Hub] State listener error:', err);
+      }
+    }
+  }
+
+  /** Subscribe to connection state changes. Returns an unsubscribe function. */
+  onStateChange(listener: StateChangeListener): Unsubscribe {
+    this.stateListeners.add(listener);
+    return () => {
+      this.stateListeners.delete(listener);
+    };
+  }
+
+  // ---- Connect / Disconnect -----------------------------------------------
+
+  /**
+   * Start listening for the events the hub sends this window.
+   */
+  async connect(): Promise<void> {
+    if (this._state === 'connected' || this._state === 'connecting') {
+      return;
+    }
+    this.setState('connecting');
+    try {
+      const { invoke } = await import('@tauri-apps/api/core');
+      const { getCurrentWebviewWindow } = await import('@tauri-apps/api/webviewWindow');
+      this.gatewayId = await invoke<string>('hub_gateway');
+      const window = getCurrentWebviewWindow();
+      this.unlisteners.push(
+        await window.listen<HubEvent>('gateway://event', (event) =>
+          this.handleEvent(event.payload)
+        ),
+        await window.listen<HubEventChunk>('gateway://event-chunk', (event) =>
+          this.handleChunk(event.payload)
+        )
+      );
+    } catch (err) {
+      this.stopListening();
+      this.setState('error');
+      throw err;
+    }
+    this.setState('connected');
+  }
+
+  disconnect(): void {
+    this.stopListening();
+    this.setState('disconnected');
+  }
+
+  destroy(): void {
+    this.disconnect();
+    this.stateListeners.clear();
+    this.eventHandlers.clear();
+    this.wildcardHandlers.clear();
+  }
+
+  private stopListening(): void {
+    for (const unlisten of this.unlisteners) {
+      unlisten();
+    }
+    this.unlisteners = [];
+    this.pendingEvents.clear();
+  }
+
+  // ---- RPC Requests -------------------------------------------------------
+
+  /**
+   * Send an RPC request through the hub. Side-effecting methods get an
+   * idempotency key, as with GatewayClient.
+   */
+  async request<T = unknown>(
+    method: GatewayMethod,
+    params?: unknown,
+    options?: RequestOptions
+  ): Promise<T> {
+    if (this._state !== 'connected') {
+      throw new Error(`Cannot send request: state is "${this._state}", expected "connected"`);
+    }
+
+    let idempotencyKey = options?.idempotencyKey;
+    if (!idempotencyKey && SIDE_EFFECTING_METHODS.has(method)) {
+      idempotencyKey = generateIdempotencyKey();
+    }
+    let finalParams = params;
+    if (idempotencyKey) {
+      if (typeof finalParams === 'object' && finalParams !== null) {
+        finalParams = { ...finalParams, idempotencyKey };
+      } else if (finalParams === undefined || finalParams === null) {
+        finalParams = { idempotencyKey };
+      }
+    }
+
+    const { invoke } = await import('@tauri-apps/api/core');
+    try {
+      return await invoke<T>('hub_request', {
+        method,
+        params: finalParams ?? null,
+        priority: options?.priority ?? 'interactive',
+      });
+    } catch (err) {
+      if (typeof err === 'object' && err !== null && 'code' in err && 'message' in err) {
+        throw new GatewayRequestError(err as GatewayError);
+      }
+      throw err instanceof Error ? err : new Error(String(err));
+    }
+  }
+
+  // ---- Event Subscriptions ------------------------------------------------
+
+  /**
+   * Subscribe to a named gateway event.
+   */
+  on<T = unknown>(event: string, handler: EventHandler<T>): Unsubscribe {
+    let handlers = this.eventHandlers.get(event);
+    if (!handlers) {
+      handlers = new Set();
+      this.eventHandlers.set(event, handlers);
+    }
+    handlers.add(handler as EventHandler);
+    return () => {
+      handlers.delete(handler as EventHandler);
+      if (handlers.size === 0) {
+        this.eventHandlers.delete(event);
+      }
+    };
+  }
+
+  /**
+   * Subscribe to ALL events (wildcard). Handler receives the full EventFrame.
+   */
+  onAny(handler: EventHandler<EventFrame>): Unsubscribe {
+    this.wildcardHandlers.add(handler);
+    return () => {
+      this.wildcardHandlers.delete(handler);
+    };
+  }
+
+  // ---- Internals: Event Handling ------------------------------------------
+
+  private handleEvent(envelope: HubEvent): void {
+    if (envelope.gatewayId !== this.gatewayId) return;
+    const frame: EventFrame = {
+      type: 'event',
+      event: envelope.event,
+      payload: envelope.payload,
+      seq: envelope.seq,
+    };
+    const header = envelope.chunked;
+    if (!header || header.chunks === 0) {
+      this.dispatch(frame);
+      return;
+    }
+    // The chunks follow the head payload
+    this.pendingEvents.set(header.streamId, {
+      frame,
+      header,
+      chunks: new Array<unknown[]>(header.chunks),
+      received: 0,
+    });
+  }
+
+  private handleChunk(chunk: HubEventChunk): void {
+    const pending = this.pendingEvents.get(chunk.streamId);
+    if (!pending || pending.chunks[chunk.index] !== undefined) return;
+    pending.chunks[chunk.index] = chunk.items;
+    pending.received += 1;
+    if (pending.received < pending.header.chunks) return;
+
+    this.pendingEvents.delete(chunk.streamId);
+    this.dispatch({
+      ...pending.frame,
+      payload: assembleChunked(pending.frame.payload, pending.header.field, pending.chunks),
+    });
+  }
+
+  private dispatch(frame: EventFrame): void {
+    const handlers = this.eventHandlers.get(frame.event);
+    if (handlers) {
+      for (const handler of handlers) {
+        try {
+          handler(frame.payload);
+        } catch (err) {
+          console.error(`[Hub] Event handler error for "${frame.event}":`, err);
+        }
+      }
+    }
+    for (const handler of this.wildcardHandlers) {
+      try {
+        handler(frame);
+      } catch (err) {
+        console.error('[Hub] Wildcard handler error:', err);
+      }
+    }
+  }
+}
@@ -4,6 +4,7 @@
 
 export { GatewayClient, GatewayRequestError } from './client';
 export type { StateChangeListener } from './client';
+export { HubClient } from './hubClient';
 
 export {
   buildRequestFrame,
@@ -53,6 +54,7 @@ export type {
   Unsubscribe,
   // Request helpers
   RequestOptions,
+  RequestPriority,
   PendingRequest,
   // Config
   GatewayClientConfig,
@@ -282,6 +282,9 @@ export type Unsubscribe = () => void;
 
 // ---- Request Options ------------------------------------------------------
 
+/** Scheduling priority: interactive requests are served before background polling. */
+export type RequestPriority = 'interactive' | 'background';
+
 export interface RequestOptions {
   /** Override the default request timeout (ms). */
   timeoutMs?: number;
@@ -289,6 +292,8 @@ export interface RequestOptions {
   idempotencyKey?: string;
   /** AbortSignal to cancel the request from outside. */
   signal?: AbortSignal;
+  /** Scheduling priority (default "interactive"). */
+  priority?: RequestPriority;
 }
 
 // ---- Pending Request Bookkeeping ------------------------------------------
@@ -469,7 +474,7 @@ export type GatewayMethod =
 
   // -- Agent (legacy / low-level)
   | 'send' // (*) low-level agent message send
-  | 'agent' // (*) agent control (start/stop/restart)
+  | 'agent' // (*) run an agent turn (message to an agent)
   | 'agent.identity.get' // (*) get agent identity info
   | 'agent.wait' // (*) wait for agent idle
 
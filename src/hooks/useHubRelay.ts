@@ -0,0 +1,109 @@
+// ---------------------------------------------------------------------------
+// Hub Relay Hook — shares the main window's connection with pop-outs
+// ---------------------------------------------------------------------------
+// Pop-out windows have no gateway connection of their own. While one
+// follows this window's connection, its events are published to the
+// backend hub, which sends each pop-out those its filters match, and the
+// requests pop-outs make are run here and answered through the hub.
+
+import { useEffect, useState } from 'react';
+import { GatewayRequestError } from '@/gateway/client';
+import type { GatewayError, GatewayMethod, RequestPriority } from '@/gateway/types';
+import { useConnectionStore } from '@/stores/connection';
+
+/** Payload of the backend `hub://request` event. */
+interface RelayedRequest {
+  id: string;
+  method: GatewayMethod;
+  params?: Record<string, unknown> | null;
+  priority: RequestPriority;
+}
+
+function toGatewayError(err: unknown): GatewayError {
+  if (err instanceof GatewayRequestError) {
+    return {
+      code: err.code,
+      message: err.message,
+      details: err.details,
+      retryable: err.retryable,
+      retryAfterMs: err.retryAfterMs,
+    };
+  }
+  return {
+    code: 'RELAY_FAILED',
+    message: err instanceof Error ? err.message : String(err),
+  };
+}
+
+async function answer(request: RelayedRequest): Promise<void> {
+  const { invoke } = await import('@tauri-apps/api/core');
+  const key = request.params?.idempotencyKey;
+  try {
+    const result = await useConnectionStore
+      .getState()
+      .request(request.method, request.params ?? undefined, {
+        priority: request.priority,
+        // Keep the pop-out's key rather than generating another
+        idempotencyKey: typeof key === 'string' ? key : undefined,
+      });
+    await invoke('hub_respond', { id: request.id, result: result ?? null });
+  } catch (err) {
+    await invoke('hub_respond', { id: request.id, error: toGatewayError(err) });
+  }
+}
+
+export function useHubRelay(): void {
+  const client = useConnectionStore((s) => s.client);
+  const [relaying, setRelaying] = useState(false);
+
+  // Whether any pop-out follows this window's connection, and the requests
+  // they make
+  useEffect(() => {
+    const unlisten: Array<() => void> = [];
+    let cancelled = false;
+    const subscribe = async () => {
+      try {
+        const { listen } = await import('@tauri-apps/api/event');
+        const { invoke } = await import('@tauri-apps/api/core');
+        const stops = [
+          await listen<boolean>('hub://relay', (event) => setRelaying(event.payload)),
+          await listen<RelayedRequest>('hub://request', (event) => {
+            answer(event.payload).catch((err) => {
+              console.error('[HubRelay] Failed to answer relayed request:', err);
+            });
+          }),
+        ];
+        if (cancelled) {
+          stops.forEach((stop) => stop());
+          return;
+        }
+        unlisten.push(...stops);
+        setRelaying(await invoke<boolean>('hub_relaying'));
+      } catch {
+        // Not in Tauri context
+      }
+    };
+    subscribe();
+    return () => {
+      cancelled = true;
+      unlisten.forEach((stop) => stop());
+    };
+  }, []);
+
+  // Publish this window's events while a pop-out follows them
+  useEffect(() => {
+    if (!relaying || !client) return;
+    return client.onAny((frame) => {
+      void (async () => {
+        const { invoke } = await import('@tauri-apps/api/core');
+        await invoke('hub_publish', {
+          event: frame.event,
+          payload: frame.payload ?? null,
+          seq: frame.seq ?? null,
+        });
+      })().catch(() => {
+        // Not in Tauri context
+      });
+    });
+  }, [relaying, client]);
+}
@@ -6,6 +6,7 @@
 // in the store and shows a confirmation toast.
 
 import { useEffect, useRef } from 'react';
+import { useNavigate, type NavigateFunction } from 'react-router-dom';
 import { toast } from 'sonner';
 import { useApprovalsStore } from '@/stores/approvals';
 
@@ -22,6 +23,10 @@ const ACTION_DENY = 'deny';
  */
 export function useNotificationActions(): void {
   const initialised = useRef(false);
+  const navigate = useNavigate();
+  // The listeners outlive renders; always route through the latest navigate
+  const navigateRef = useRef(navigate);
+  navigateRef.current = navigate;
 
   useEffect(() => {
     if (initialised.current) return;
@@ -61,14 +66,14 @@ export function useNotificationActions(): void {
         // Listen for action button taps (fires on iOS; may fire on macOS
         // if the plugin wires up UNNotificationAction responses).
         const actionListener = await onAction((notification) => {
-          handleNotificationAction(notification);
+          handleNotificationAction(notification, navigateRef.current);
         });
 
         // Also listen for notification body clicks. On macOS desktop this
         // is the primary interaction path -- clicking the notification
         // banner focuses the app and navigates to /approvals.
         const receivedListener = await onNotificationReceived((notification) => {
-          handleNotificationClick(notification);
+          handleNotificationClick(notification, navigateRef.current);
         });
 
         cleanupListener = () => {
@@ -98,12 +103,15 @@ interface NotificationPayload {
   extra?: Record<string, unknown>;
 }
 
-function handleNotificationAction(notification: NotificationPayload): void {
+function handleNotificationAction(
+  notification: NotificationPayload,
+  navigate: NavigateFunction
+): void {
   const extra = notification.extra;
   if (!extra) {
     // No extra data -- just a plain notification click. Focus the app and
     // navigate to approvals.
-    focusAndNavigate('/approvals');
+    focusAndNavigate('/approvals', navigate);
     return;
   }
 
@@ -112,7 +120,7 @@ function handleNotificationAction(notification: NotificationPayload): void {
 
   if (!requestId) {
     // Notification clicked without a specific action -- navigate to approvals
-    focusAndNavigate('/approvals');
+    focusAndNavigate('/approvals', navigate);
     return;
   }
 
@@ -132,7 +140,7 @@ function handleNotificationAction(notification: NotificationPayload): void {
       });
   } else {
     // Clicked the notification body itself (no specific action button)
-    focusAndNavigate('/approvals');
+    focusAndNavigate('/approvals', navigate);
   }
 }
 
@@ -140,32 +148,23 @@ function handleNotificationAction(notification: NotificationPayload): void {
  * Handles a click on the notification body (not an action button).
  * Focuses the app and navigates to /approvals.
  */
-function handleNotificationClick(notification: NotificationPayload): void {
+function handleNotificationClick(
+  notification: NotificationPayload,
+  navigate: NavigateFunction
+): void {
   // If the notification has an approval action type, navigate to approvals
   if (notification.actionTypeId === APPROVAL_ACTION_TYPE_ID) {
-    focusAndNavigate('/approvals');
+    focusAndNavigate('/approvals', navigate);
   }
 }
 
-async function focusAndNavigate(route: string): Promise<void> {
+async function focusAndNavigate(route: string, navigate: NavigateFunction): Promise<void> {
   try {
-    const { getCurrentWindow } = await import('@tauri-apps/api/window');
-    const win = getCurrentWindow();
-    await win.show();
-    await win.setFocus();
-
-    // Use the same tray navigation bridge if available
-    if (typeof window !== 'undefined') {
-      const trayNav = (window as unknown as Record<string, unknown>)['__trayNav'] as
-        | ((r: string) => void)
-        | undefined;
-      if (trayNav) {
-        trayNav(route);
-      } else {
-        window.location.hash = `#${route}`;
-      }
-    }
+    // The backend shows and focuses the main window and routes it
+    const { invoke } = await import('@tauri-apps/api/core');
+    await invoke('app_navigate', { path: route });
   } catch {
     // Not in Tauri context
+    navigate(route);
   }
 }
@@ -0,0 +1,61 @@
+// ---------------------------------------------------------------------------
+// Shortcut Actions Hook — global shortcuts for this window's connection
+// ---------------------------------------------------------------------------
+// Global shortcuts are registered by the backend, which answers approvals
+// and aborts turns held by its own gateway sessions. Those this window's
+// gateway connection holds are handed over as `shortcuts://action` events.
+
+import { useEffect } from 'react';
+import { useApprovalsStore } from '@/stores/approvals';
+import { useChatStore } from '@/stores/chat';
+
+/** Backend `ShortcutAction`s that are sent to the main window. */
+type ForwardedAction = 'approveOldest' | 'denyOldest' | 'abortChat';
+
+function runAction(action: ForwardedAction): void {
+  switch (action) {
+    case 'approveOldest':
+    case 'denyOldest': {
+      // Pending requests are kept oldest first
+      const oldest = useApprovalsStore.getState().pendingRequests.find((r) => r.id);
+      if (oldest?.id) {
+        void useApprovalsStore
+          .getState()
+          .resolveApproval(oldest.id, action === 'approveOldest' ? 'approve' : 'deny');
+      }
+      break;
+    }
+    case 'abortChat':
+      if (useChatStore.getState().isStreaming) {
+        void useChatStore.getState().abortStream();
+      }
+      break;
+  }
+}
+
+export function useShortcutActions(): void {
+  useEffect(() => {
+    let unlisten: (() => void) | undefined;
+    let cancelled = false;
+    const subscribe = async () => {
+      try {
+        const { listen } = await import('@tauri-apps/api/event');
+        const stop = await listen<ForwardedAction>('shortcuts://action', (event) => {
+          runAction(event.payload);
+        });
+        if (cancelled) {
+          stop();
+          return;
+        }
+        unlisten = stop;
+      } catch {
+        // Not in Tauri context
+      }
+    };
+    subscribe();
+    return () => {
+      cancelled = true;
+      unlisten?.();
+    };
+  }, []);
+}
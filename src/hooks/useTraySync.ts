@@ -1,10 +1,11 @@
 // ---------------------------------------------------------------------------
-// Tray Sync Hook — keeps macOS tray menu in sync with app state
+// Tray Sync Hook — keeps the desktop tray menu in sync with app state
 // ---------------------------------------------------------------------------
 
 import { useEffect } from 'react';
 import { useConnectionStore } from '@/stores/connection';
 import { useApprovalsStore } from '@/stores/approvals';
+import { useChatStore } from '@/stores/chat';
 import { useNavigate } from 'react-router-dom';
 
 function statusLabel(status: string): string {
@@ -21,21 +22,51 @@ function statusLabel(status: string): string {
   }
 }
 
+/** Payload of the backend `app://navigate` event. */
+interface NavigateEvent {
+  /** Frontend path, validated in Rust. */
+  path: string;
+  /** The typed route behind `path`, tagged by `view`. */
+  route: { view: string; [key: string]: unknown };
+}
+
 export function useTraySync(): void {
   const connectionStatus = useConnectionStore((s) => s.status);
-  const pendingCount = useApprovalsStore((s) => s.pendingRequests.length);
+  const pendingRequests = useApprovalsStore((s) => s.pendingRequests);
+  const pendingCount = pendingRequests.length;
   const navigate = useNavigate();
 
-  // Expose navigation function for tray menu events
+  // Navigate events from the backend (tray, notifications, shortcuts, deep
+  // links). The backend queues them until we report that we are listening.
   useEffect(() => {
-    const nav = (route: string) => {
-      navigate(route);
+    let unlisten: (() => void) | undefined;
+    let cancelled = false;
+    const subscribe = async () => {
+      try {
+        const { listen } = await import('@tauri-apps/api/event');
+        const { invoke } = await import('@tauri-apps/api/core');
+        const stop = await listen<NavigateEvent>('app://navigate', (event) => {
+          const { path, route } = event.payload;
+          // Chat routes select their session before the chat view opens
+          if (route.view === 'chat' && typeof route.sessionKey === 'string') {
+            useChatStore.getState().setActiveSession(route.sessionKey);
+          }
+          navigate(path);
+        });
+        if (cancelled) {
+          stop();
+          return;
+        }
+        unlisten = stop;
+        await invoke('navigation_ready');
+      } catch {
+        // Not in Tauri context
+      }
     };
-    // eslint-disable-next-line @typescript-eslint/no-explicit-any
-    (window as any).__trayNav = nav;
+    subscribe();
     return () => {
-      // eslint-disable-next-line @typescript-eslint/no-explicit-any
-      delete (window as any).__trayNav;
+      cancelled = true;
+      unlisten?.();
     };
   }, [navigate]);
 
@@ -47,11 +78,13 @@ export function useTraySync(): void {
         await invoke('update_tray_status', {
           connectionStatus: statusLabel(connectionStatus),
           pendingApprovals: pendingCount,
+          // Lets the tray count approvals a backend session also holds once
+          approvalIds: pendingRequests.flatMap((r) => (r.id ? [r.id] : [])),
         });
       } catch {
-        // Not in Tauri context or macOS-only command
+        // Not in Tauri context, or no tray (mobile)
       }
     };
     sync();
-  }, [connectionStatus, pendingCount]);
+  }, [connectionStatus, pendingRequests]);
 }
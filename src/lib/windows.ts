@@ -0,0 +1,61 @@
+// ---------------------------------------------------------------------------
+// Pop-out Windows (TypeScript bindings)
+// ---------------------------------------------------------------------------
+//
+// Bindings for the Rust window commands. A pop-out shows one chat session,
+// the logs tail or the approvals queue in its own window; the backend hub
+// sends each window only the gateway events it subscribed to. Pop-outs have
+// no gateway connection of their own (see `HubClient`).
+
+import { invoke } from '@tauri-apps/api/core';
+import { getCurrentWindow } from '@tauri-apps/api/window';
+
+// ---- Types ----------------------------------------------------------------
+
+/** What a pop-out window shows. */
+export type PopOutTarget =
+  | { kind: 'chat'; sessionKey: string; gatewayId?: string }
+  | { kind: 'logs'; gatewayId?: string }
+  | { kind: 'approvals'; gatewayId?: string };
+
+/** Which gateway events a window receives. */
+export type EventFilter =
+  | { kind: 'all' }
+  | { kind: 'session'; sessionKey: string; gatewayId?: string }
+  | { kind: 'approvals'; gatewayId?: string }
+  | { kind: 'logs'; gatewayId?: string };
+
+/** Label prefix of pop-out windows. */
+const POP_OUT_PREFIX = 'popout-';
+
+/** Gateway ID the hub relays the main window's own connection under. */
+export const MAIN_CONNECTION_ID = 'main';
+
+// ---- Public API -----------------------------------------------------------
+
+/**
+ * Open `target` in its own window, or focus the window already showing it.
+ *
+ * @returns The window label
+ */
+export async function popOut(target: PopOutTarget): Promise<string> {
+  return invoke<string>('window_pop_out', { target });
+}
+
+/**
+ * Replace the calling window's gateway event filters.
+ */
+export async function subscribeHub(filters: EventFilter[]): Promise<void> {
+  await invoke('hub_subscribe', { filters });
+}
+
+/**
+ * Whether this webview is a pop-out window. False outside Tauri.
+ */
+export function isPopOutWindow(): boolean {
+  try {
+    return getCurrentWindow().label.startsWith(POP_OUT_PREFIX);
+  } catch {
+    return false;
+  }
+}
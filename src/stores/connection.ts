@@ -4,6 +4,8 @@
 
 import { create } from 'zustand';
 import { GatewayClient } from '@/gateway/client';
+import { HubClient } from '@/gateway/hubClient';
+import { isPopOutWindow } from '@/lib/windows';
 import type {
   GatewayConnectionState,
   GatewayPolicy,
@@ -65,8 +67,9 @@ interface ConnectionState {
   /** Live presence list — updated by presence events after initial connect. */
   presence: PresenceEntry[];
 
-  // -- Client instance (not serializable, kept as a ref)
-  client: GatewayClient | null;
+  // -- Client instance (not serializable, kept as a ref). Pop-out windows
+  // reach the gateway through the backend hub instead of a connection.
+  client: GatewayClient | HubClient | null;
 
   // -- Presence subscription (internal ref)
   _presenceUnsub: Unsubscribe | null;
@@ -157,12 +160,12 @@ export const useConnectionStore = create<ConnectionState>((set, get) => ({
 
     set({ error: null, status: 'connecting' });
 
-    const clientInfo = buildClientInfo();
-
-    const client = new GatewayClient({
-      url: gatewayUrl,
-      clientInfo,
-    });
+    const client = isPopOutWindow()
+      ? new HubClient()
+      : new GatewayClient({
+          url: gatewayUrl,
+          clientInfo: buildClientInfo(),
+        });
 
     // Sync state changes into Zustand
     client.onStateChange((newState) => {
@@ -0,0 +1,118 @@
+import { describe, expect, it, vi } from 'vitest';
+
+import { HubClient, assembleChunked } from '@/gateway/hubClient';
+
+// ---- Fake windows -----------------------------------------------------------
+// Each client connects as the window named by `currentLabel`; `emitTo`
+// delivers to that window's listeners only, like the backend's `emit_to`.
+
+type Listener = (event: { payload: unknown }) => void;
+
+const windows = new Map<string, { gatewayId: string; listeners: Map<string, Listener[]> }>();
+let currentLabel = '';
+
+function openWindow(label: string, gatewayId: string): void {
+  windows.set(label, { gatewayId, listeners: new Map() });
+  currentLabel = label;
+}
+
+function emitTo(label: string, event: string, payload: unknown): void {
+  for (const listener of windows.get(label)?.listeners.get(event) ?? []) {
+    listener({ payload });
+  }
+}
+
+vi.mock('@tauri-apps/api/core', () => ({
+  invoke: async (command: string) => {
+    if (command === 'hub_gateway') return windows.get(currentLabel)?.gatewayId;
+    throw new Error(`unexpected command ${command}`);
+  },
+}));
+
+vi.mock('@tauri-apps/api/webviewWindow', () => ({
+  getCurrentWebviewWindow: () => {
+    const window = windows.get(currentLabel)!;
+    return {
+      listen: async (event: string, listener: Listener) => {
+        const listeners = window.listeners.get(event) ?? [];
+        listeners.push(listener);
+        window.listeners.set(event, listeners);
+        return () => listeners.splice(listeners.indexOf(listener), 1);
+      },
+    };
+  },
+}));
+
+async function connectAs(label: string, gatewayId: string): Promise<unknown[]> {
+  openWindow(label, gatewayId);
+  const client = new HubClient();
+  await client.connect();
+  const heard: unknown[] = [];
+  client.on('chat', (payload) => heard.push(payload));
+  return heard;
+}
+
+describe('hub client chunk assembly', () => {
+  it('puts the chunks back into the split field', () => {
+    const head = { sessionKey: 'agent:main', messages: [] };
+    expect(assembleChunked(head, 'messages', [[1, 2], [3]])).toEqual({
+      sessionKey: 'agent:main',
+      messages: [1, 2, 3],
+    });
+  });
+
+  it('returns the items when the payload was the array', () => {
+    expect(assembleChunked([], null, [['a'], ['b', 'c']])).toEqual(['a', 'b', 'c']);
+  });
+});
+
+describe('hub client delivery', () => {
+  it('hears only the events sent to its own window', async () => {
+    const home = await connectAs('popout-home', 'home');
+    const main = await connectAs('popout-main', 'main');
+
+    emitTo('popout-home', 'gateway://event', {
+      gatewayId: 'home',
+      event: 'chat',
+      payload: { n: 1 },
+    });
+    emitTo('popout-main', 'gateway://event', {
+      gatewayId: 'main',
+      event: 'chat',
+      payload: { n: 2 },
+    });
+
+    expect(home).toEqual([{ n: 1 }]);
+    expect(main).toEqual([{ n: 2 }]);
+  });
+
+  it("drops envelopes from another window's gateway", async () => {
+    const home = await connectAs('popout-stray', 'home');
+
+    emitTo('popout-stray', 'gateway://event', {
+      gatewayId: 'work',
+      event: 'chat',
+      payload: { n: 1 },
+      chunked: { streamId: 's1', field: 'messages', totalItems: 1, chunks: 1 },
+    });
+    emitTo('popout-stray', 'gateway://event-chunk', { streamId: 's1', index: 0, items: [1] });
+
+    expect(home).toEqual([]);
+  });
+
+  it('reassembles chunks that arrive out of order', async () => {
+    const home = await connectAs('popout-chunks', 'home');
+
+    emitTo('popout-chunks', 'gateway://event', {
+      gatewayId: 'home',
+      event: 'chat',
+      payload: { messages: [] },
+      chunked: { streamId: 's2', field: 'messages', totalItems: 3, chunks: 2 },
+    });
+    emitTo('popout-chunks', 'gateway://event-chunk', { streamId: 's2', index: 1, items: [3] });
+    expect(home).toEqual([]);
+    emitTo('popout-chunks', 'gateway://event-chunk', { streamId: 's2', index: 0, items: [1, 2] });
+
+    expect(home).toEqual([{ messages: [1, 2, 3] }]);
+  });
+});